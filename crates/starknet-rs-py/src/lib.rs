@@ -18,6 +18,7 @@ use crate::{
         contract_entry_point::PyEntryPointType,
         general_config::build_general_config,
         starknet_message_to_l1::PyStarknetMessageToL1,
+        stored_event::PyStoredEvent,
         transaction::{PyTransaction, PyTransactionType},
         transaction_execution_info::PyTransactionExecutionInfo,
         transactions::{
@@ -75,6 +76,7 @@ pub fn starknet_rs_py(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyTransaction>()?;
     m.add_class::<PyTransactionType>()?;
     m.add_class::<PyStarknetMessageToL1>()?;
+    m.add_class::<PyStoredEvent>()?;
     m.add_class::<PyTransactionExecutionInfo>()?;
     m.add_class::<PyInternalDeclare>()?;
     m.add_class::<PyInternalDeploy>()?;
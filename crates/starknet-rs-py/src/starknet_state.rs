@@ -2,14 +2,17 @@ use crate::cached_state::PyCachedState;
 use crate::types::block_info::PyBlockInfo;
 use crate::types::{
     call_info::PyCallInfo, contract_class::PyContractClass,
-    general_config::PyStarknetGeneralConfig, transaction::PyTransaction,
-    transaction_execution_info::PyTransactionExecutionInfo,
+    general_config::PyStarknetGeneralConfig, stored_event::PyStoredEvent,
+    transaction::PyTransaction, transaction_execution_info::PyTransactionExecutionInfo,
 };
 use cairo_vm::felt::Felt252;
 use num_bigint::BigUint;
 use pyo3::{exceptions::PyRuntimeError, prelude::*};
 use starknet_rs::business_logic::state::state_api::{State, StateReader};
-use starknet_rs::testing::starknet_state::StarknetState as InnerStarknetState;
+use starknet_rs::testing::{
+    cheatcodes::TxInfoSpoof, event_store::EventQuery,
+    starknet_state::StarknetState as InnerStarknetState,
+};
 use starknet_rs::utils::{Address, ClassHash};
 
 #[pyclass(name = "StarknetState")]
@@ -51,7 +54,7 @@ impl PyStarknetState {
         contract_address: BigUint,
         selector: BigUint,
         calldata: Vec<BigUint>,
-        max_fee: u64,
+        max_fee: u128,
         signature: Option<Vec<BigUint>>,
         nonce: Option<BigUint>,
     ) -> PyResult<PyTransactionExecutionInfo> {
@@ -197,6 +200,135 @@ impl PyStarknetState {
     pub fn set_block_info(&mut self, block_info: PyBlockInfo) {
         *self.inner.general_config.block_info_mut() = block_info.into();
     }
+
+    // ~~~~~~~~~~~~~~~~~~~~
+    //  Cheatcodes
+    // ~~~~~~~~~~~~~~~~~~~~
+
+    pub fn prank(&mut self, target: BigUint, caller_address: BigUint, n_calls: Option<usize>) {
+        self.inner.cheatcodes().prank(
+            Address(Felt252::from(target)),
+            Address(Felt252::from(caller_address)),
+            n_calls,
+        );
+    }
+
+    pub fn stop_prank(&mut self, target: BigUint) {
+        self.inner
+            .cheatcodes()
+            .stop_prank(&Address(Felt252::from(target)));
+    }
+
+    pub fn roll(&mut self, block_number: u64) {
+        self.inner.cheatcodes().roll(block_number);
+    }
+
+    pub fn stop_roll(&mut self) {
+        self.inner.cheatcodes().stop_roll();
+    }
+
+    pub fn warp(&mut self, block_timestamp: u64) {
+        self.inner.cheatcodes().warp(block_timestamp);
+    }
+
+    pub fn stop_warp(&mut self) {
+        self.inner.cheatcodes().stop_warp();
+    }
+
+    pub fn mock_call(
+        &mut self,
+        contract_address: BigUint,
+        selector: BigUint,
+        retdata: Vec<BigUint>,
+    ) {
+        self.inner.cheatcodes().mock_call(
+            Address(Felt252::from(contract_address)),
+            Felt252::from(selector),
+            retdata.into_iter().map(Felt252::from).collect(),
+        );
+    }
+
+    pub fn clear_mock_call(&mut self, contract_address: BigUint, selector: BigUint) {
+        self.inner.cheatcodes().clear_mock_call(
+            Address(Felt252::from(contract_address)),
+            Felt252::from(selector),
+        );
+    }
+
+    pub fn spoof_tx_info(
+        &mut self,
+        version: Option<u64>,
+        account_contract_address: Option<BigUint>,
+        max_fee: Option<u128>,
+        signature: Option<Vec<BigUint>>,
+        transaction_hash: Option<BigUint>,
+        nonce: Option<BigUint>,
+    ) {
+        self.inner.cheatcodes().spoof_tx_info(TxInfoSpoof {
+            version,
+            account_contract_address: account_contract_address.map(|a| Address(Felt252::from(a))),
+            max_fee,
+            signature: signature.map(|s| s.into_iter().map(Felt252::from).collect()),
+            transaction_hash: transaction_hash.map(Felt252::from),
+            nonce: nonce.map(Felt252::from),
+        });
+    }
+
+    pub fn stop_spoof_tx_info(&mut self) {
+        self.inner.cheatcodes().stop_spoof_tx_info();
+    }
+
+    pub fn expect_event(&mut self, from_address: BigUint, keys: Vec<BigUint>, data: Vec<BigUint>) {
+        self.inner.cheatcodes().expect_event(
+            Address(Felt252::from(from_address)),
+            keys.into_iter().map(Felt252::from).collect(),
+            data.into_iter().map(Felt252::from).collect(),
+        );
+    }
+
+    pub fn assert_expected_events_emitted(&mut self) -> PyResult<()> {
+        self.inner
+            .assert_expected_events_emitted()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+    }
+
+    pub fn reset_cheatcodes(&mut self) {
+        self.inner.cheatcodes().reset();
+    }
+
+    /// Returns the events matching the filters, and the token to pass to get the next ones if
+    /// more events match.
+    pub fn get_events(
+        &self,
+        from_block: Option<u64>,
+        to_block: Option<u64>,
+        address: Option<BigUint>,
+        keys: Option<Vec<Vec<BigUint>>>,
+        chunk_size: usize,
+        continuation_token: Option<String>,
+    ) -> PyResult<(Vec<PyStoredEvent>, Option<String>)> {
+        let query = EventQuery {
+            from_block,
+            to_block,
+            address: address.map(|address| Address(Felt252::from(address))),
+            keys: keys
+                .unwrap_or_default()
+                .into_iter()
+                .map(|keys| keys.into_iter().map(Felt252::from).collect())
+                .collect(),
+            chunk_size,
+            continuation_token,
+        };
+        let page = self
+            .inner
+            .get_events(&query)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+        Ok((
+            page.events.into_iter().map(PyStoredEvent::from).collect(),
+            page.continuation_token,
+        ))
+    }
 }
 
 #[cfg(test)]
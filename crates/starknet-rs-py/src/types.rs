@@ -7,6 +7,7 @@ pub mod general_config;
 pub mod ordered_event;
 pub mod ordered_l2_to_l1_message;
 pub mod starknet_message_to_l1;
+pub mod stored_event;
 pub mod transaction;
 pub mod transaction_execution_info;
 pub mod transactions;
@@ -18,7 +18,7 @@ impl PyBlockInfo {
     fn new(
         block_number: u64,
         block_timestamp: u64,
-        gas_price: u64,
+        gas_price: u128,
         sequencer_address: BigUint,
         starknet_version: String,
     ) -> Self {
@@ -46,7 +46,7 @@ impl PyBlockInfo {
         _cls: &PyType,
         block_number: u64,
         block_timestamp: u64,
-        gas_price: Option<u64>,
+        gas_price: Option<u128>,
     ) -> PyBlockInfo {
         let gas_price = gas_price.unwrap_or(DEFAULT_GAS_PRICE);
         let inner = BlockInfo {
@@ -76,7 +76,7 @@ impl PyBlockInfo {
     }
 
     #[getter]
-    fn gas_price(&self) -> u64 {
+    fn gas_price(&self) -> u128 {
         self.inner.gas_price
     }
 
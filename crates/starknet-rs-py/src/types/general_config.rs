@@ -107,7 +107,7 @@ impl PyStarknetGeneralConfig {
     }
 
     #[getter]
-    fn min_gas_price(&self) -> u64 {
+    fn min_gas_price(&self) -> u128 {
         DEFAULT_GAS_PRICE
     }
 }
@@ -151,7 +151,7 @@ impl PyStarknetOsConfig {
     ))]
     fn new(chain_id: PyStarknetChainId, fee_token_address: BigUint) -> Self {
         let address = Address(Felt252::from(fee_token_address));
-        let inner = StarknetOsConfig::new(chain_id.into(), address, 0);
+        let inner = StarknetOsConfig::new(chain_id.into(), address);
         Self { inner }
     }
 }
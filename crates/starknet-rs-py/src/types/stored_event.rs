@@ -0,0 +1,57 @@
+use cairo_vm::felt::Felt252;
+use num_bigint::BigUint;
+use pyo3::prelude::*;
+use starknet_rs::testing::event_store::StoredEvent;
+
+#[pyclass(name = "StoredEvent")]
+#[derive(Debug)]
+pub struct PyStoredEvent {
+    inner: StoredEvent,
+}
+
+#[pymethods]
+impl PyStoredEvent {
+    #[getter]
+    fn from_address(&self) -> BigUint {
+        self.inner.event.from_address.0.to_biguint()
+    }
+
+    #[getter]
+    fn keys(&self) -> Vec<BigUint> {
+        self.inner
+            .event
+            .keys
+            .iter()
+            .map(Felt252::to_biguint)
+            .collect()
+    }
+
+    #[getter]
+    fn data(&self) -> Vec<BigUint> {
+        self.inner
+            .event
+            .data
+            .iter()
+            .map(Felt252::to_biguint)
+            .collect()
+    }
+
+    #[getter]
+    fn block_number(&self) -> u64 {
+        self.inner.block_number
+    }
+
+    #[getter]
+    fn transaction_hash(&self) -> Option<BigUint> {
+        self.inner
+            .transaction_hash
+            .as_ref()
+            .map(Felt252::to_biguint)
+    }
+}
+
+impl From<StoredEvent> for PyStoredEvent {
+    fn from(inner: StoredEvent) -> Self {
+        Self { inner }
+    }
+}
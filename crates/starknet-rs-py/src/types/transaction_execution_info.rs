@@ -1,4 +1,5 @@
 use super::{call_info::PyCallInfo, transaction::PyTransactionType};
+use num_bigint::BigUint;
 use pyo3::{exceptions::PyValueError, prelude::*};
 use starknet_rs::business_logic::execution::objects::TransactionExecutionInfo;
 use std::collections::HashMap;
@@ -13,7 +14,7 @@ pub struct PyTransactionExecutionInfo {
 impl PyTransactionExecutionInfo {
     #[new]
     fn new(
-        actual_fee: u64,
+        actual_fee: u128,
         actual_resources: HashMap<String, usize>,
         validate_info: Option<PyCallInfo>,
         call_info: Option<PyCallInfo>,
@@ -47,7 +48,7 @@ impl PyTransactionExecutionInfo {
     }
 
     #[getter]
-    fn actual_fee(&self) -> u64 {
+    fn actual_fee(&self) -> u128 {
         self.inner.actual_fee
     }
 
@@ -56,6 +57,23 @@ impl PyTransactionExecutionInfo {
         self.inner.actual_resources.clone()
     }
 
+    #[getter]
+    fn revert_error(&self) -> Option<String> {
+        self.inner.revert_error.as_ref().map(ToString::to_string)
+    }
+
+    #[getter]
+    fn is_reverted(&self) -> bool {
+        self.inner.is_reverted()
+    }
+
+    #[getter]
+    fn fee_payer(&self) -> Option<BigUint> {
+        self.inner
+            .fee_payer()
+            .map(|fee_payer| fee_payer.0.to_biguint())
+    }
+
     #[getter]
     fn transaction_type(&self) -> Option<u64> {
         Some(self.inner.tx_type?.into())
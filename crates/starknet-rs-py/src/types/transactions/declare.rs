@@ -32,7 +32,7 @@ impl PyInternalDeclare {
     fn new(
         hash_value: BigUint,
         version: u64,
-        max_fee: u64,
+        max_fee: u128,
         signature: Vec<BigUint>,
         nonce: BigUint,
         class_hash: ClassHash,
@@ -56,6 +56,8 @@ impl PyInternalDeclare {
             nonce,
             hash_value,
             contract_class,
+            compiled_class_hash: None,
+            v3_fields: None,
         };
 
         Self { inner }
@@ -20,7 +20,7 @@ use std::collections::HashMap;
 #[pyfunction(name = "calculate_tx_fee")]
 pub(crate) fn py_calculate_tx_fee(
     resources: HashMap<String, usize>,
-    gas_price: u64,
+    gas_price: u128,
     general_config: PyStarknetGeneralConfig,
 ) -> PyResult<BigUint> {
     match calculate_tx_fee(&resources, gas_price, &general_config.into()) {
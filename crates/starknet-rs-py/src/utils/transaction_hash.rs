@@ -76,19 +76,22 @@ pub(crate) fn py_calculate_declare_transaction_hash(
     contract_class: &PyContractClass,
     chain_id: BigUint,
     sender_address: BigUint,
-    max_fee: u64,
+    max_fee: u128,
     version: u64,
     nonce: BigUint,
+    compiled_class_hash: Option<BigUint>,
 ) -> PyResult<BigUint> {
     let chain_id = Felt252::from(chain_id);
     let sender_address = Address(Felt252::from(sender_address));
     let nonce = Felt252::from(nonce);
+    let compiled_class_hash = compiled_class_hash.map(Felt252::from);
     match calculate_declare_transaction_hash(
         contract_class.into(),
+        compiled_class_hash.as_ref(),
         chain_id,
         &sender_address,
         max_fee,
-        version,
+        version.into(),
         nonce,
     ) {
         Ok(res) => Ok(res.to_biguint()),
@@ -104,7 +107,7 @@ pub(crate) fn py_calculate_transaction_hash_common(
     contract_address: BigUint,
     entry_point_selector: BigUint,
     calldata: Vec<BigUint>,
-    max_fee: u64,
+    max_fee: u128,
     chain_id: BigUint,
     additional_data: Vec<BigUint>,
 ) -> PyResult<BigUint> {
@@ -117,7 +120,7 @@ pub(crate) fn py_calculate_transaction_hash_common(
 
     match calculate_transaction_hash_common(
         tx_hash_prefix,
-        version,
+        version.into(),
         &contract_address,
         entry_point_selector,
         &calldata,
@@ -113,7 +113,7 @@ fn create_initial_state() -> CachedState<InMemoryStateReader> {
 
 pub fn new_starknet_general_config_for_testing() -> StarknetGeneralConfig {
     StarknetGeneralConfig::new(
-        StarknetOsConfig::new(StarknetChainId::TestNet, Address(Felt252::zero()), 0),
+        StarknetOsConfig::new(StarknetChainId::TestNet, Address(Felt252::zero())),
         0,
         0,
         Default::default(),
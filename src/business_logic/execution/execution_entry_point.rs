@@ -2,15 +2,24 @@ use crate::{
     business_logic::{
         execution::objects::{CallInfo, CallType, TransactionExecutionContext},
         fact_state::state::ExecutionResourcesManager,
-        state::state_api::State,
-        state::state_api::StateReader,
+        state::{
+            child_state::ChildState,
+            state_api::{State, StateReader},
+        },
         transaction::error::TransactionError,
     },
     core::syscalls::{
         business_logic_syscall_handler::BusinessLogicSyscallHandler,
         syscall_handler::{SyscallHandler, SyscallHintProcessor},
     },
-    definitions::{constants::DEFAULT_ENTRY_POINT_SELECTOR, general_config::StarknetGeneralConfig},
+    definitions::{
+        constants::{
+            DEFAULT_ENTRY_POINT_SELECTOR, VALIDATE_DECLARE_ENTRY_POINT_SELECTOR,
+            VALIDATE_DEPLOY_ENTRY_POINT_SELECTOR,
+        },
+        general_config::StarknetGeneralConfig,
+    },
+    public::abi::VALIDATE_ENTRY_POINT_SELECTOR,
     services::api::contract_class::{ContractClass, ContractEntryPoint, EntryPointType},
     starknet_runner::runner::{prepare_os_context, StarknetRunner},
     utils::{get_deployed_address_class_hash_at_address, validate_contract_deployed, Address},
@@ -35,6 +44,8 @@ pub struct ExecutionEntryPoint {
     caller_address: Address,
     entry_point_selector: Felt252,
     entry_point_type: EntryPointType,
+    is_fee_transfer: bool,
+    max_n_steps: Option<u64>,
 }
 
 impl ExecutionEntryPoint {
@@ -56,9 +67,23 @@ impl ExecutionEntryPoint {
             caller_address,
             entry_point_selector,
             entry_point_type,
+            is_fee_transfer: false,
+            max_n_steps: None,
         }
     }
 
+    /// Marks the call as the fee transfer of a transaction.
+    pub(crate) fn fee_transfer(mut self) -> Self {
+        self.is_fee_transfer = true;
+        self
+    }
+
+    /// Stops the run and fails once it reaches `max_n_steps` steps.
+    pub(crate) fn with_max_n_steps(mut self, max_n_steps: u64) -> Self {
+        self.max_n_steps = Some(max_n_steps);
+        self
+    }
+
     /// Executes the selected entry point with the given calldata in the specified contract.
     /// The information collected from this run (number of steps required, modifications to the
     /// contract storage, etc.) is saved on the resources manager.
@@ -75,23 +100,40 @@ impl ExecutionEntryPoint {
     {
         let previous_cairo_usage = resources_manager.cairo_usage.clone();
 
-        let runner = self.run(
-            state,
-            resources_manager,
-            general_config,
-            tx_execution_context,
-        )?;
-
-        // Update resources usage (for bouncer).
-        resources_manager.cairo_usage =
-            &resources_manager.cairo_usage + &runner.get_execution_resources()?;
-
-        let retdata = runner.get_return_values()?;
-        self.build_call_info::<T>(
-            previous_cairo_usage,
-            runner.hint_processor.syscall_handler,
-            retdata,
-        )
+        // The call writes to its own cache layer, which is merged back only if it succeeds.
+        // Nested calls do the same, so a failing call leaves no partial writes behind.
+        let mut call_state = ChildState::new(state);
+
+        let call_info = {
+            let runner = self.run(
+                &mut call_state,
+                resources_manager,
+                general_config,
+                tx_execution_context,
+            )?;
+
+            // Update resources usage (for bouncer).
+            resources_manager.cairo_usage =
+                &resources_manager.cairo_usage + &runner.get_execution_resources()?;
+            // The syscall handler counts on its own copy of the resources manager, which already
+            // includes the syscalls of any nested call.
+            resources_manager.syscall_counter = runner
+                .hint_processor
+                .syscall_handler
+                .resources_manager
+                .syscall_counter
+                .clone();
+
+            let retdata = runner.get_return_values()?;
+            self.build_call_info(
+                previous_cairo_usage,
+                runner.hint_processor.syscall_handler,
+                retdata,
+            )?
+        };
+
+        call_state.commit()?;
+        Ok(call_info)
     }
 
     /// Runs the selected entry point with the given calldata in the code of the contract deployed
@@ -103,7 +145,7 @@ impl ExecutionEntryPoint {
     fn run<'a, T>(
         &self,
         state: &'a mut T,
-        resources_manager: &ExecutionResourcesManager,
+        resources_manager: &mut ExecutionResourcesManager,
         general_config: &StarknetGeneralConfig,
         tx_execution_context: &TransactionExecutionContext,
     ) -> Result<StarknetRunner<BusinessLogicSyscallHandler<'a, T>>, TransactionError>
@@ -135,11 +177,18 @@ impl ExecutionEntryPoint {
             _ => return Err(TransactionError::NotARelocatableValue),
         };
 
+        let caller_address = general_config
+            .syscall_hooks
+            .as_ref()
+            .filter(|_| self.is_user_call())
+            .and_then(|syscall_hooks| syscall_hooks.take_caller_address(&self.contract_address))
+            .unwrap_or_else(|| self.caller_address.clone());
+
         let syscall_handler = BusinessLogicSyscallHandler::new(
             tx_execution_context.clone(),
             state,
             resources_manager.clone(),
-            self.caller_address.clone(),
+            caller_address,
             self.contract_address.clone(),
             general_config.clone(),
             initial_syscall_ptr,
@@ -166,7 +215,32 @@ impl ExecutionEntryPoint {
         let entrypoint = entry_point.offset;
 
         // cairo runner entry point
-        runner.run_from_entrypoint(entrypoint, &entry_point_args)?;
+        if let Err(error) =
+            runner.run_from_entrypoint(entrypoint, &entry_point_args, self.max_n_steps)
+        {
+            // Account for the resources used up to the failure, so that a reverted transaction
+            // can still be charged for them.
+            runner.vm.segments.compute_effective_sizes();
+            if let Ok(execution_resources) = runner.get_execution_resources() {
+                resources_manager.cairo_usage =
+                    &resources_manager.cairo_usage + &execution_resources;
+            }
+            resources_manager.syscall_counter = runner
+                .hint_processor
+                .syscall_handler
+                .resources_manager
+                .syscall_counter
+                .clone();
+
+            // Prefer the syscall error that aborted the run (e.g. a failed nested call), which
+            // keeps the whole chain of errors instead of its message.
+            return Err(runner
+                .hint_processor
+                .syscall_error
+                .take()
+                .map(TransactionError::from)
+                .unwrap_or(error));
+        }
         runner.validate_and_process_os_context(os_context)?;
 
         // When execution starts the stack holds entry_points_args + [ret_fp, ret_pc].
@@ -183,6 +257,22 @@ impl ExecutionEntryPoint {
         Ok(runner)
     }
 
+    /// Returns whether this is a call to an external entry point of a contract, as opposed to
+    /// the calls made around a transaction: account validation, constructors, library calls
+    /// and the fee transfer. Only user calls can have their caller overridden by the syscall
+    /// hooks.
+    fn is_user_call(&self) -> bool {
+        self.call_type == CallType::Call
+            && self.entry_point_type == EntryPointType::External
+            && !self.is_fee_transfer
+            && ![
+                &*VALIDATE_ENTRY_POINT_SELECTOR,
+                &*VALIDATE_DECLARE_ENTRY_POINT_SELECTOR,
+                &*VALIDATE_DEPLOY_ENTRY_POINT_SELECTOR,
+            ]
+            .contains(&&self.entry_point_selector)
+    }
+
     /// Returns the entry point with selector corresponding with self.entry_point_selector, or the
     /// default if there is one and the requested one is not found.
     fn get_selected_entry_point(
@@ -229,7 +319,8 @@ impl ExecutionEntryPoint {
             &syscall_handler.resources_manager.cairo_usage - &previous_cairo_usage;
 
         Ok(CallInfo {
-            caller_address: self.caller_address.clone(),
+            // The caller the call ran with, which a prank may have overridden.
+            caller_address: syscall_handler.caller_address.clone(),
             call_type: Some(self.call_type.clone()),
             contract_address: self.contract_address.clone(),
             code_address: self.code_address.clone(),
@@ -281,7 +372,8 @@ mod tests {
     use crate::business_logic::{
         execution::execution_entry_point::ExecutionEntryPoint, state::cached_state::CachedState,
     };
-    use crate::definitions::constants::TRANSACTION_VERSION;
+    use crate::definitions::constants::{TRANSACTION_VERSION, TRANSFER_ENTRY_POINT_SELECTOR};
+    use crate::public::abi::{CONSTRUCTOR_ENTRY_POINT_SELECTOR, VALIDATE_ENTRY_POINT_SELECTOR};
     use crate::utils::calculate_sn_keccak;
     use crate::{
         business_logic::{
@@ -395,4 +487,42 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn only_user_calls_can_be_pranked() {
+        let entry_point = |selector: &Felt252, entry_point_type, call_type| {
+            ExecutionEntryPoint::new(
+                Address(1.into()),
+                vec![],
+                selector.clone(),
+                Address(0.into()),
+                entry_point_type,
+                Some(call_type),
+                None,
+            )
+        };
+        let transfer = &*TRANSFER_ENTRY_POINT_SELECTOR;
+
+        assert!(entry_point(transfer, EntryPointType::External, CallType::Call).is_user_call());
+        assert!(
+            !entry_point(transfer, EntryPointType::External, CallType::Call)
+                .fee_transfer()
+                .is_user_call()
+        );
+        assert!(
+            !entry_point(transfer, EntryPointType::External, CallType::Delegate).is_user_call()
+        );
+        assert!(!entry_point(
+            &CONSTRUCTOR_ENTRY_POINT_SELECTOR,
+            EntryPointType::Constructor,
+            CallType::Call
+        )
+        .is_user_call());
+        assert!(!entry_point(
+            &VALIDATE_ENTRY_POINT_SELECTOR,
+            EntryPointType::External,
+            CallType::Call
+        )
+        .is_user_call());
+    }
 }
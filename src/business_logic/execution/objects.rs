@@ -1,5 +1,8 @@
 use crate::{
-    business_logic::{state::state_cache::StorageEntry, transaction::error::TransactionError},
+    business_logic::{
+        state::state_cache::StorageEntry,
+        transaction::error::{RevertError, TransactionError},
+    },
     core::errors::syscall_handler_errors::SyscallHandlerError,
     definitions::{
         constants::CONSTRUCTOR_ENTRY_POINT_SELECTOR, general_config::StarknetChainId,
@@ -255,7 +258,7 @@ pub struct TransactionExecutionContext {
     pub(crate) n_emitted_events: u64,
     pub(crate) version: u64,
     pub(crate) account_contract_address: Address,
-    pub(crate) max_fee: u64,
+    pub(crate) max_fee: u128,
     pub(crate) transaction_hash: Felt252,
     pub(crate) signature: Vec<Felt252>,
     #[get = "pub"]
@@ -269,7 +272,7 @@ impl TransactionExecutionContext {
         account_contract_address: Address,
         transaction_hash: Felt252,
         signature: Vec<Felt252>,
-        max_fee: u64,
+        max_fee: u128,
         nonce: Felt252,
         n_steps: u64,
         version: u64,
@@ -289,7 +292,7 @@ impl TransactionExecutionContext {
 
     pub fn create_for_testing(
         account_contract_address: Address,
-        _max_fee: u64,
+        _max_fee: u128,
         nonce: Felt252,
         n_steps: u64,
         version: u64,
@@ -312,7 +315,7 @@ impl TransactionExecutionContext {
 pub(crate) struct TxInfoStruct {
     pub(crate) version: usize,
     pub(crate) account_contract_address: Address,
-    pub(crate) max_fee: u64,
+    pub(crate) max_fee: u128,
     pub(crate) signature_len: usize,
     pub(crate) signature: Relocatable,
     pub(crate) transaction_hash: Felt252,
@@ -342,7 +345,7 @@ impl TxInfoStruct {
         vec![
             MaybeRelocatable::from(Felt252::new(self.version)),
             MaybeRelocatable::from(&self.account_contract_address.0),
-            MaybeRelocatable::from(Felt252::new(self.max_fee)),
+            MaybeRelocatable::from(Felt252::from(self.max_fee)),
             MaybeRelocatable::from(Felt252::new(self.signature_len)),
             MaybeRelocatable::from(&self.signature),
             MaybeRelocatable::from(&self.transaction_hash),
@@ -359,8 +362,8 @@ impl TxInfoStruct {
 
         let account_contract_address = Address(get_big_int(vm, &tx_info_ptr + 1)?);
         let max_fee = get_big_int(vm, &tx_info_ptr + 2)?
-            .to_u64()
-            .ok_or(SyscallHandlerError::FeltToU64Fail)?;
+            .to_u128()
+            .ok_or(SyscallHandlerError::FeltToU128Fail)?;
         let signature_len = get_integer(vm, &tx_info_ptr + 3)?;
         let signature = get_relocatable(vm, &tx_info_ptr + 4)?;
         let transaction_hash = get_big_int(vm, &tx_info_ptr + 5)?;
@@ -385,9 +388,12 @@ pub struct TransactionExecutionInfo {
     pub validate_info: Option<CallInfo>,
     pub call_info: Option<CallInfo>,
     pub fee_transfer_info: Option<CallInfo>,
-    pub actual_fee: u64,
+    pub actual_fee: u128,
     pub actual_resources: HashMap<String, usize>,
     pub tx_type: Option<TransactionType>,
+    /// The reason why the execution of the transaction failed, if it was reverted. A reverted
+    /// transaction only keeps its nonce increment and fee charge.
+    pub revert_error: Option<RevertError>,
 }
 
 impl TransactionExecutionInfo {
@@ -395,7 +401,7 @@ impl TransactionExecutionInfo {
         validate_info: Option<CallInfo>,
         call_info: Option<CallInfo>,
         fee_transfer_info: Option<CallInfo>,
-        actual_fee: u64,
+        actual_fee: u128,
         actual_resources: HashMap<String, usize>,
         tx_type: Option<TransactionType>,
     ) -> Self {
@@ -406,9 +412,23 @@ impl TransactionExecutionInfo {
             actual_fee,
             actual_resources,
             tx_type,
+            revert_error: None,
         }
     }
 
+    /// Returns whether the execution of the transaction failed and its changes were discarded.
+    pub fn is_reverted(&self) -> bool {
+        self.revert_error.is_some()
+    }
+
+    /// Returns the account debited the fee of the transaction, i.e. the caller of the fee
+    /// transfer, or `None` if no fee was charged.
+    pub fn fee_payer(&self) -> Option<&Address> {
+        self.fee_transfer_info
+            .as_ref()
+            .map(|fee_transfer_info| &fee_transfer_info.caller_address)
+    }
+
     // In deploy account tx, validation will take place after execution of the constructor.
     pub fn non_optional_calls(&self) -> Vec<CallInfo> {
         let calls = match self.tx_type {
@@ -444,6 +464,7 @@ impl TransactionExecutionInfo {
             actual_fee: 0,
             actual_resources: HashMap::new(),
             tx_type,
+            revert_error: None,
         }
     }
 
@@ -460,12 +481,13 @@ impl TransactionExecutionInfo {
             actual_fee: 0,
             actual_resources,
             tx_type,
+            revert_error: None,
         }
     }
 
     pub fn from_concurrent_state_execution_info(
         concurrent_execution_info: TransactionExecutionInfo,
-        actual_fee: u64,
+        actual_fee: u128,
         fee_transfer_info: Option<CallInfo>,
     ) -> Self {
         TransactionExecutionInfo {
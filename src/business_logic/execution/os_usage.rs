@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
+use lazy_static::lazy_static;
 
 use crate::{
     business_logic::transaction::error::TransactionError,
@@ -9,72 +10,259 @@ use crate::{
 
 #[derive(Debug, Clone)]
 pub struct OsResources {
-    _execute_syscalls: HashMap<String, ExecutionResources>,
+    execute_syscalls: HashMap<String, ExecutionResources>,
     execute_txs_inner: HashMap<TransactionType, ExecutionResources>,
 }
 
-impl Default for OsResources {
-    fn default() -> Self {
+lazy_static! {
+    /// OS resources tables, keyed by the first StarkNet version they apply to.
+    /// Must be non-empty and sorted in ascending version order. Only the 0.11.0 table is
+    /// supported, so every version currently resolves to it, including later ones whose OS
+    /// costs differ; supporting another version means adding its table here.
+    static ref VERSIONED_OS_RESOURCES: Vec<(Vec<u64>, OsResources)> =
+        vec![(vec![0, 11, 0], OsResources::v0_11_0())];
+}
+
+impl OsResources {
+    /// Returns the OS resources table used by the given StarkNet version.
+    /// Versions older than every known table (e.g. the "0.0.0" placeholder) use the oldest one.
+    pub fn for_version(starknet_version: &str) -> &'static Self {
+        let version = parse_starknet_version(starknet_version);
+
+        let (_, os_resources) = VERSIONED_OS_RESOURCES
+            .iter()
+            .rev()
+            .find(|(table_version, _)| *table_version <= version)
+            .unwrap_or(&VERSIONED_OS_RESOURCES[0]);
+        os_resources
+    }
+
+    pub fn execute_syscalls(&self) -> &HashMap<String, ExecutionResources> {
+        &self.execute_syscalls
+    }
+
+    pub fn execute_txs_inner(&self) -> &HashMap<TransactionType, ExecutionResources> {
+        &self.execute_txs_inner
+    }
+
+    fn v0_11_0() -> Self {
         let execute_txs_inner: HashMap<TransactionType, ExecutionResources> = HashMap::from([
             (
                 TransactionType::InvokeFunction,
-                ExecutionResources {
-                    n_steps: 2839,
-                    n_memory_holes: 0,
-                    builtin_instance_counter: HashMap::from([
-                        ("pedersen_builtin".to_string(), 16),
-                        ("range_check_builtin".to_string(), 70),
-                    ]),
-                },
+                resources(
+                    2839,
+                    &[("pedersen_builtin", 16), ("range_check_builtin", 70)],
+                ),
             ),
             (
                 TransactionType::Declare,
-                ExecutionResources {
-                    n_steps: 2336,
-                    n_memory_holes: 0,
-                    builtin_instance_counter: HashMap::from([
-                        ("pedersen_builtin".to_string(), 15),
-                        ("range_check_builtin".to_string(), 57),
-                    ]),
-                },
-            ),
-            (
-                TransactionType::Deploy,
-                ExecutionResources {
-                    n_steps: 0,
-                    n_memory_holes: 0,
-                    builtin_instance_counter: HashMap::new(),
-                },
+                resources(
+                    2336,
+                    &[("pedersen_builtin", 15), ("range_check_builtin", 57)],
+                ),
             ),
+            (TransactionType::Deploy, resources(0, &[])),
             (
                 TransactionType::DeployAccount,
-                ExecutionResources {
-                    n_steps: 3098,
-                    n_memory_holes: 0,
-                    builtin_instance_counter: HashMap::from([
-                        ("pedersen_builtin".to_string(), 23),
-                        ("range_check_builtin".to_string(), 74),
-                    ]),
-                },
+                resources(
+                    3098,
+                    &[("pedersen_builtin", 23), ("range_check_builtin", 74)],
+                ),
             ),
         ]);
 
+        let execute_syscalls: HashMap<String, ExecutionResources> = [
+            (
+                "call_contract",
+                resources(690, &[("range_check_builtin", 19)]),
+            ),
+            (
+                "delegate_call",
+                resources(712, &[("range_check_builtin", 19)]),
+            ),
+            (
+                "delegate_l1_handler",
+                resources(691, &[("range_check_builtin", 15)]),
+            ),
+            (
+                "deploy",
+                resources(936, &[("pedersen_builtin", 7), ("range_check_builtin", 18)]),
+            ),
+            ("emit_event", resources(19, &[])),
+            ("get_block_number", resources(40, &[])),
+            ("get_block_timestamp", resources(38, &[])),
+            ("get_caller_address", resources(32, &[])),
+            ("get_contract_address", resources(36, &[])),
+            ("get_sequencer_address", resources(34, &[])),
+            ("get_tx_info", resources(29, &[])),
+            ("get_tx_signature", resources(44, &[])),
+            (
+                "library_call",
+                resources(679, &[("range_check_builtin", 19)]),
+            ),
+            (
+                "library_call_l1_handler",
+                resources(658, &[("range_check_builtin", 15)]),
+            ),
+            ("replace_class", resources(73, &[])),
+            ("send_message_to_l1", resources(84, &[])),
+            ("storage_read", resources(44, &[])),
+            ("storage_write", resources(46, &[])),
+        ]
+        .into_iter()
+        .map(|(name, resources)| (name.to_string(), resources))
+        .collect();
+
         OsResources {
-            _execute_syscalls: HashMap::new(),
+            execute_syscalls,
             execute_txs_inner,
         }
     }
 }
 
+impl Default for OsResources {
+    fn default() -> Self {
+        OsResources::v0_11_0()
+    }
+}
+
+fn resources(n_steps: usize, builtins: &[(&str, usize)]) -> ExecutionResources {
+    ExecutionResources {
+        n_steps,
+        n_memory_holes: 0,
+        builtin_instance_counter: builtins
+            .iter()
+            .map(|(name, count)| (name.to_string(), *count))
+            .collect(),
+    }
+}
+
+/// Parses a version string such as "0.11.0.2" into its numeric components.
+/// Non-numeric components are treated as zero.
+fn parse_starknet_version(starknet_version: &str) -> Vec<u64> {
+    starknet_version
+        .split('.')
+        .map(|component| component.parse().unwrap_or(0))
+        .collect()
+}
+
+/// Returns the additional resources needed for the OS to run the given syscalls and the
+/// transaction itself, using the OS resources of the given StarkNet version.
 pub fn get_additional_os_resources(
-    _syscall_counter: HashMap<String, u64>,
+    syscall_counter: HashMap<String, u64>,
     tx_type: &TransactionType,
+    starknet_version: &str,
 ) -> Result<ExecutionResources, TransactionError> {
-    let os_resources = OsResources::default();
+    let os_resources = OsResources::for_version(starknet_version);
+
+    let mut additional_os_resources = ExecutionResources::default();
+
+    // Calculate additional resources needed for the OS to run the given syscalls;
+    // i.e., the resources of the function execute_syscalls().
+    for (syscall_name, count) in syscall_counter {
+        if count == 0 {
+            continue;
+        }
 
-    Ok(os_resources
+        let syscall_resources = os_resources
+            .execute_syscalls
+            .get(&syscall_name)
+            .ok_or_else(|| TransactionError::NoneSyscallName(syscall_name.clone()))?;
+        let count = count as usize;
+
+        let scaled_resources = ExecutionResources {
+            n_steps: syscall_resources.n_steps * count,
+            n_memory_holes: syscall_resources.n_memory_holes * count,
+            builtin_instance_counter: syscall_resources
+                .builtin_instance_counter
+                .iter()
+                .map(|(builtin, value)| (builtin.clone(), value * count))
+                .collect(),
+        };
+        additional_os_resources = &additional_os_resources + &scaled_resources;
+    }
+
+    // Calculate additional resources needed for the OS to run the given transaction;
+    // i.e., the resources of the StarkNet OS function execute_{tx_type}.
+    let tx_resources = os_resources
         .execute_txs_inner
         .get(tx_type)
-        .ok_or_else(|| TransactionError::NoneTransactionType(*tx_type, os_resources.clone()))?
-        .clone())
+        .ok_or_else(|| TransactionError::NoneTransactionType(*tx_type, os_resources.clone()))?;
+
+    Ok(&additional_os_resources + tx_resources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+
+    #[test]
+    fn get_additional_os_resources_without_syscalls() {
+        let resources =
+            get_additional_os_resources(HashMap::new(), &TransactionType::InvokeFunction, "0.11.0")
+                .unwrap();
+
+        assert_eq!(resources.n_steps, 2839);
+        assert_eq!(
+            resources.builtin_instance_counter,
+            HashMap::from([
+                ("pedersen_builtin".to_string(), 16),
+                ("range_check_builtin".to_string(), 70),
+            ])
+        );
+    }
+
+    #[test]
+    fn get_additional_os_resources_scales_syscalls_by_count() {
+        let syscall_counter = HashMap::from([
+            ("call_contract".to_string(), 2),
+            ("storage_read".to_string(), 3),
+            ("deploy".to_string(), 0),
+        ]);
+
+        let resources = get_additional_os_resources(
+            syscall_counter,
+            &TransactionType::InvokeFunction,
+            "0.11.0",
+        )
+        .unwrap();
+
+        assert_eq!(resources.n_steps, 2839 + 2 * 690 + 3 * 44);
+        assert_eq!(
+            resources.builtin_instance_counter,
+            HashMap::from([
+                ("pedersen_builtin".to_string(), 16),
+                ("range_check_builtin".to_string(), 70 + 2 * 19),
+            ])
+        );
+    }
+
+    #[test]
+    fn get_additional_os_resources_unknown_syscall_should_fail() {
+        let syscall_counter = HashMap::from([("unknown_syscall".to_string(), 1)]);
+
+        assert_matches!(
+            get_additional_os_resources(syscall_counter, &TransactionType::Declare, "0.11.0"),
+            Err(TransactionError::NoneSyscallName(name)) if name == "unknown_syscall"
+        );
+    }
+
+    #[test]
+    fn os_resources_for_version_falls_back_to_known_tables() {
+        for version in ["0.0.0", "0.11.0", "0.11.0.2", "0.12.1", "not-a-version"] {
+            let os_resources = OsResources::for_version(version);
+            assert_eq!(
+                os_resources.execute_syscalls()["call_contract"].n_steps,
+                690
+            );
+        }
+    }
+
+    #[test]
+    fn parse_starknet_version_compares_numerically() {
+        assert!(parse_starknet_version("0.11.0") > parse_starknet_version("0.9.1"));
+        assert!(parse_starknet_version("0.11.0.2") > parse_starknet_version("0.11.0"));
+        assert_eq!(parse_starknet_version("0.x.1"), vec![0, 0, 1]);
+    }
 }
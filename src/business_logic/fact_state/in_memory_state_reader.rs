@@ -71,6 +71,10 @@ impl StateReader for InMemoryStateReader {
         storage.cloned()
     }
 
+    fn storage_updates(&mut self) -> HashMap<StorageEntry, Felt252> {
+        HashMap::new()
+    }
+
     fn count_actual_storage_changes(&mut self) -> (usize, usize) {
         todo!()
     }
@@ -147,11 +147,15 @@ impl<T: StateReader + Clone> StateReader for CachedState<T> {
             .cloned()
     }
 
-    fn count_actual_storage_changes(&mut self) -> (usize, usize) {
-        let storage_updates = subtract_mappings(
+    fn storage_updates(&mut self) -> HashMap<StorageEntry, Felt252> {
+        subtract_mappings(
             self.cache.storage_writes.clone(),
             self.cache.storage_initial_values.clone(),
-        );
+        )
+    }
+
+    fn count_actual_storage_changes(&mut self) -> (usize, usize) {
+        let storage_updates = self.storage_updates();
         let modified_contracts = storage_updates.keys().map(|k| k.0.clone()).len();
         (modified_contracts, storage_updates.len())
     }
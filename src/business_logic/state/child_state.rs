@@ -0,0 +1,281 @@
+use super::{
+    state_api::{State, StateReader},
+    state_cache::StorageEntry,
+};
+use crate::{
+    core::errors::state_errors::StateError,
+    services::api::contract_class::ContractClass,
+    utils::{Address, ClassHash},
+};
+use cairo_vm::felt::Felt252;
+use num_traits::Zero;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+/// A state that can be the parent of a `ChildState`.
+pub(crate) trait ParentState: State + StateReader {}
+
+impl<T: State + StateReader> ParentState for T {}
+
+/// Cache layer over a parent state, used to run something whose writes must be discarded if it
+/// fails. Reads fall through to the parent, writes are kept in the layer until `commit` applies
+/// them to the parent, and dropping the layer without committing discards them.
+///
+/// The parent is type-erased so that nesting layers (one per nested call) does not nest types.
+pub(crate) struct ChildState<'a> {
+    parent: &'a mut (dyn ParentState + 'a),
+    contract_classes: HashMap<ClassHash, ContractClass>,
+    deployed_contracts: HashMap<Address, ClassHash>,
+    nonce_increments: HashMap<Address, u64>,
+    storage_writes: HashMap<StorageEntry, Felt252>,
+}
+
+impl<'a> ChildState<'a> {
+    pub(crate) fn new(parent: &'a mut (dyn ParentState + 'a)) -> Self {
+        ChildState {
+            parent,
+            contract_classes: HashMap::new(),
+            deployed_contracts: HashMap::new(),
+            nonce_increments: HashMap::new(),
+            storage_writes: HashMap::new(),
+        }
+    }
+
+    /// Applies the writes of this layer to its parent.
+    pub(crate) fn commit(self) -> Result<(), StateError> {
+        for (class_hash, contract_class) in self.contract_classes {
+            self.parent
+                .set_contract_class(&class_hash, &contract_class)?;
+        }
+        for (contract_address, class_hash) in self.deployed_contracts {
+            self.parent.deploy_contract(contract_address, class_hash)?;
+        }
+        for (contract_address, increments) in self.nonce_increments {
+            for _ in 0..increments {
+                self.parent.increment_nonce(&contract_address)?;
+            }
+        }
+        for (storage_entry, value) in self.storage_writes {
+            self.parent.set_storage_at(&storage_entry, value);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Debug for ChildState<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChildState")
+            .field("deployed_contracts", &self.deployed_contracts)
+            .field("nonce_increments", &self.nonce_increments)
+            .field("storage_writes", &self.storage_writes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> StateReader for ChildState<'a> {
+    fn get_contract_class(&mut self, class_hash: &ClassHash) -> Result<ContractClass, StateError> {
+        match self.contract_classes.get(class_hash) {
+            Some(contract_class) => Ok(contract_class.clone()),
+            None => self.parent.get_contract_class(class_hash),
+        }
+    }
+
+    fn get_class_hash_at(&mut self, contract_address: &Address) -> Result<ClassHash, StateError> {
+        match self.deployed_contracts.get(contract_address) {
+            Some(class_hash) => Ok(*class_hash),
+            None => self.parent.get_class_hash_at(contract_address),
+        }
+    }
+
+    fn get_nonce_at(&mut self, contract_address: &Address) -> Result<Felt252, StateError> {
+        let nonce = if self.deployed_contracts.contains_key(contract_address) {
+            Felt252::zero()
+        } else {
+            self.parent.get_nonce_at(contract_address)?
+        };
+        let increments = self
+            .nonce_increments
+            .get(contract_address)
+            .copied()
+            .unwrap_or_default();
+
+        Ok(nonce + Felt252::from(increments))
+    }
+
+    fn get_storage_at(&mut self, storage_entry: &StorageEntry) -> Result<Felt252, StateError> {
+        match self.storage_writes.get(storage_entry) {
+            Some(value) => Ok(value.clone()),
+            None => self.parent.get_storage_at(storage_entry),
+        }
+    }
+
+    /// Returns the pending updates of the parent with the writes of this layer that change a
+    /// value merged over them, as they would be once committed.
+    fn storage_updates(&mut self) -> HashMap<StorageEntry, Felt252> {
+        let mut storage_updates = self.parent.storage_updates();
+        for (storage_entry, value) in &self.storage_writes {
+            if self.parent.get_storage_at(storage_entry).as_ref() != Ok(value) {
+                storage_updates.insert(storage_entry.clone(), value.clone());
+            }
+        }
+        storage_updates
+    }
+
+    fn count_actual_storage_changes(&mut self) -> (usize, usize) {
+        let storage_updates = self.storage_updates();
+        let modified_contracts = storage_updates
+            .keys()
+            .map(|(contract_address, _)| contract_address)
+            .collect::<HashSet<_>>()
+            .len();
+
+        (modified_contracts, storage_updates.len())
+    }
+}
+
+impl<'a> State for ChildState<'a> {
+    fn set_contract_class(
+        &mut self,
+        class_hash: &ClassHash,
+        contract_class: &ContractClass,
+    ) -> Result<(), StateError> {
+        self.contract_classes
+            .insert(*class_hash, contract_class.clone());
+        Ok(())
+    }
+
+    fn deploy_contract(
+        &mut self,
+        deploy_contract_address: Address,
+        class_hash: ClassHash,
+    ) -> Result<(), StateError> {
+        if deploy_contract_address == Address(0.into()) {
+            return Err(StateError::ContractAddressOutOfRangeAddress(
+                deploy_contract_address,
+            ));
+        }
+
+        match self.get_class_hash_at(&deploy_contract_address) {
+            Ok(x) if x == [0; 32] => {}
+            Ok(_) => {
+                return Err(StateError::ContractAddressUnavailable(
+                    deploy_contract_address,
+                ))
+            }
+            _ => {}
+        }
+
+        self.nonce_increments.remove(&deploy_contract_address);
+        self.deployed_contracts
+            .insert(deploy_contract_address, class_hash);
+        Ok(())
+    }
+
+    fn increment_nonce(&mut self, contract_address: &Address) -> Result<(), StateError> {
+        // Fails like the parent would if the nonce can't be read.
+        self.get_nonce_at(contract_address)?;
+        *self
+            .nonce_increments
+            .entry(contract_address.clone())
+            .or_default() += 1;
+        Ok(())
+    }
+
+    fn set_storage_at(&mut self, storage_entry: &StorageEntry, value: Felt252) {
+        self.storage_writes.insert(storage_entry.clone(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::business_logic::{
+        fact_state::in_memory_state_reader::InMemoryStateReader, state::cached_state::CachedState,
+    };
+    use coverage_helper::test;
+
+    fn parent_state() -> CachedState<InMemoryStateReader> {
+        let mut state_reader = InMemoryStateReader::default();
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(Address(1.into()), [1; 32]);
+        state_reader
+            .address_to_nonce_mut()
+            .insert(Address(1.into()), 3.into());
+        CachedState::new(state_reader, Some(HashMap::new()))
+    }
+
+    #[test]
+    fn writes_are_applied_on_commit() {
+        let mut state = parent_state();
+        let storage_entry = (Address(1.into()), [7; 32]);
+
+        let mut child_state = ChildState::new(&mut state);
+        child_state.set_storage_at(&storage_entry, 5.into());
+        child_state.increment_nonce(&Address(1.into())).unwrap();
+        child_state
+            .deploy_contract(Address(2.into()), [2; 32])
+            .unwrap();
+        child_state.increment_nonce(&Address(2.into())).unwrap();
+
+        assert_eq!(child_state.get_storage_at(&storage_entry), Ok(5.into()));
+        assert_eq!(child_state.get_nonce_at(&Address(1.into())), Ok(4.into()));
+        assert_eq!(child_state.get_nonce_at(&Address(2.into())), Ok(1.into()));
+        child_state.commit().unwrap();
+
+        assert_eq!(state.get_storage_at(&storage_entry), Ok(5.into()));
+        assert_eq!(state.get_nonce_at(&Address(1.into())), Ok(4.into()));
+        assert_eq!(state.get_class_hash_at(&Address(2.into())), Ok([2; 32]));
+        assert_eq!(state.get_nonce_at(&Address(2.into())), Ok(1.into()));
+    }
+
+    #[test]
+    fn writes_are_discarded_when_dropped() {
+        let mut state = parent_state();
+        let storage_entry = (Address(1.into()), [7; 32]);
+
+        {
+            let mut parent_layer = ChildState::new(&mut state);
+            let mut child_state = ChildState::new(&mut parent_layer);
+            child_state.set_storage_at(&storage_entry, 5.into());
+            child_state
+                .deploy_contract(Address(2.into()), [2; 32])
+                .unwrap();
+            drop(child_state);
+            parent_layer.commit().unwrap();
+        }
+
+        assert_eq!(state.get_storage_at(&storage_entry), Ok(0.into()));
+        assert_eq!(state.get_class_hash_at(&Address(2.into())), Ok([0; 32]));
+        assert!(state.cache().storage_writes().is_empty());
+    }
+
+    #[test]
+    fn storage_changes_include_the_ones_of_the_layer() {
+        let mut state = parent_state();
+        state.set_storage_at(&(Address(1.into()), [1; 32]), 1.into());
+
+        let mut child_state = ChildState::new(&mut state);
+        // An entry changed by the parent and by the layer is a single change.
+        child_state.set_storage_at(&(Address(1.into()), [1; 32]), 4.into());
+        child_state.set_storage_at(&(Address(1.into()), [2; 32]), 2.into());
+        child_state.set_storage_at(&(Address(2.into()), [2; 32]), 2.into());
+        // Writing the current value is not a change.
+        child_state.set_storage_at(&(Address(1.into()), [3; 32]), 0.into());
+
+        assert_eq!(child_state.count_actual_storage_changes(), (2, 3));
+    }
+
+    #[test]
+    fn deploy_to_a_used_address_should_fail() {
+        let mut state = parent_state();
+        let mut child_state = ChildState::new(&mut state);
+
+        assert_eq!(
+            child_state.deploy_contract(Address(1.into()), [2; 32]),
+            Err(StateError::ContractAddressUnavailable(Address(1.into())))
+        );
+    }
+}
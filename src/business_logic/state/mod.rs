@@ -1,4 +1,5 @@
 pub mod cached_state;
+pub(crate) mod child_state;
 pub(crate) mod contract_storage_state;
 pub mod state_api;
 pub mod state_api_objects;
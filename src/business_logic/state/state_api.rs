@@ -5,6 +5,7 @@ use crate::{
     utils::{Address, ClassHash},
 };
 use cairo_vm::felt::Felt252;
+use std::collections::HashMap;
 
 pub trait StateReader {
     /// Returns the contract class of the given class hash.
@@ -15,6 +16,8 @@ pub trait StateReader {
     fn get_nonce_at(&mut self, contract_address: &Address) -> Result<Felt252, StateError>;
     /// Returns the storage value under the given key in the given contract instance.
     fn get_storage_at(&mut self, storage_entry: &StorageEntry) -> Result<Felt252, StateError>;
+    /// Returns the pending storage writes that change the value of their entry.
+    fn storage_updates(&mut self) -> HashMap<StorageEntry, Felt252>;
     /// Counts the amount of modified contracts and the updates to the storage
     fn count_actual_storage_changes(&mut self) -> (usize, usize);
 }
@@ -7,7 +7,7 @@ use crate::{
     definitions::transaction_type::TransactionType,
     starknet_runner::starknet_runner_error::StarknetRunnerError,
     starkware_utils::starkware_errors::StarkwareError,
-    utils::ClassHash,
+    utils::{Address, ClassHash},
 };
 use cairo_vm::{
     types::{errors::math_errors::MathError, relocatable::Relocatable},
@@ -16,6 +16,8 @@ use cairo_vm::{
         trace_errors::TraceError, vm_errors::VirtualMachineError,
     },
 };
+use num_bigint::BigUint;
+use std::{fmt, sync::Arc};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -32,6 +34,16 @@ pub enum TransactionError {
     StarknetError(String),
     #[error("{0}")]
     FeeError(String),
+    #[error("Account balance ({0}) is lower than the max fee ({1})")]
+    InsufficientBalance(BigUint, u128),
+    #[error("Max fee ({0}) is lower than the minimal fee ({1})")]
+    MaxFeeTooLow(u128, u128),
+    #[error("Paymaster {0:?} is not allowed to pay for transactions")]
+    UnauthorizedPaymaster(Address),
+    #[error("Max L1 gas price ({0}) is lower than the actual gas price ({1})")]
+    MaxL1GasPriceTooLow(u128, u128),
+    #[error("Invalid __execute__ return data: {0}")]
+    InvalidExecuteRetdata(String),
     #[error("Cairo resource names must be contained in fee weights dict")]
     ResourcesError,
     #[error("Could not calculate resources")]
@@ -44,6 +56,8 @@ pub enum TransactionError {
     State(#[from] StateError),
     #[error("Calling other contracts during validate execution is forbidden")]
     UnauthorizedActionOnValidate,
+    #[error("Execution was stopped after reaching the limit of {0} steps")]
+    StepsLimitExceeded(u64),
     #[error("Class hash {0:?} already declared")]
     ClassAlreadyDeclared(ClassHash),
     #[error(transparent)]
@@ -106,6 +120,56 @@ pub enum TransactionError {
     StarknetRunner(#[from] StarknetRunnerError),
     #[error("Transaction type {0:?} not found in OsResources: {1:?}")]
     NoneTransactionType(TransactionType, OsResources),
+    #[error("Syscall {0} not found in OsResources")]
+    NoneSyscallName(String),
     #[error(transparent)]
     MathError(#[from] MathError),
 }
+
+impl TransactionError {
+    /// Returns the error that started a chain of failed nested calls, or `self` if the failure
+    /// didn't come from a nested call.
+    pub fn root_cause(&self) -> &TransactionError {
+        match self {
+            TransactionError::Syscall(SyscallHandlerError::NestedCall { source, .. }) => {
+                source.root_cause()
+            }
+            _ => self,
+        }
+    }
+}
+
+/// The error that made a transaction revert, kept in its `TransactionExecutionInfo`.
+///
+/// Execution infos are cloned and compared, which `TransactionError` doesn't support, so the
+/// error is shared and two revert errors are equal when they have the same message.
+#[derive(Clone, Debug)]
+pub struct RevertError(Arc<TransactionError>);
+
+impl RevertError {
+    pub fn error(&self) -> &TransactionError {
+        &self.0
+    }
+
+    pub fn root_cause(&self) -> &TransactionError {
+        self.0.root_cause()
+    }
+}
+
+impl From<TransactionError> for RevertError {
+    fn from(error: TransactionError) -> Self {
+        RevertError(Arc::new(error))
+    }
+}
+
+impl fmt::Display for RevertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl PartialEq for RevertError {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
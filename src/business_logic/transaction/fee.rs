@@ -3,30 +3,104 @@ use crate::{
     business_logic::{
         execution::{
             execution_entry_point::ExecutionEntryPoint,
+            gas_usage::calculate_tx_gas_usage,
             objects::{CallInfo, TransactionExecutionContext},
+            os_usage::get_additional_os_resources,
         },
         fact_state::state::ExecutionResourcesManager,
         state::state_api::{State, StateReader},
+        transaction::objects::resource_bounds::V3TransactionFields,
     },
     definitions::{
         constants::TRANSFER_ENTRY_POINT_SELECTOR, general_config::StarknetGeneralConfig,
+        transaction_type::TransactionType,
     },
     services::api::contract_class::EntryPointType,
+    utils::{get_erc20_balance_var_addresses, split_uint256, Address},
 };
 use cairo_vm::felt::Felt252;
-use num_traits::ToPrimitive;
-use std::collections::HashMap;
+use num_bigint::BigUint;
+use num_traits::{ToPrimitive, Zero};
+use std::collections::{HashMap, HashSet};
 
 // second element is the actual fee that the transaction uses
-pub type FeeInfo = (Option<CallInfo>, u64);
+pub type FeeInfo = (Option<CallInfo>, u128);
 
-/// Transfers the amount actual_fee from the caller account to the sequencer.
+/// Token in which a transaction pays its fee.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeType {
+    Eth,
+    Strk,
+}
+
+impl FeeType {
+    /// Version 3 transactions pay in STRK, older ones in ETH.
+    pub fn for_version(version: u64) -> Self {
+        if version >= 3 {
+            FeeType::Strk
+        } else {
+            FeeType::Eth
+        }
+    }
+
+    pub fn fee_token_address(self, general_config: &StarknetGeneralConfig) -> Address {
+        let os_config = &general_config.starknet_os_config;
+        match self {
+            FeeType::Eth => os_config.fee_token_address.clone(),
+            FeeType::Strk => os_config.strk_fee_token_address.clone(),
+        }
+    }
+}
+
+/// Decides which account is debited the fee of a transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum FeePayerPolicy {
+    /// Transactions pay their own fee.
+    #[default]
+    Sender,
+    /// A sponsor contract pays the fee of every transaction.
+    Sponsor(Address),
+    /// Version 3 transactions are paid by the address in the first element of their
+    /// `paymaster_data`, which must be one of the paymasters allowed by the operator.
+    /// Transactions without paymaster data pay their own fee.
+    Paymaster(HashSet<Address>),
+}
+
+impl FeePayerPolicy {
+    /// Fails if the transaction names a paymaster that isn't allowed to pay for it.
+    pub fn fee_payer(
+        &self,
+        sender_address: &Address,
+        v3_fields: Option<&V3TransactionFields>,
+    ) -> Result<Address, TransactionError> {
+        match self {
+            FeePayerPolicy::Sender => Ok(sender_address.clone()),
+            FeePayerPolicy::Sponsor(sponsor_address) => Ok(sponsor_address.clone()),
+            FeePayerPolicy::Paymaster(allowed_paymasters) => {
+                match v3_fields.and_then(|v3_fields| v3_fields.paymaster_data.first()) {
+                    Some(paymaster_address) => {
+                        let paymaster_address = Address(paymaster_address.clone());
+                        if !allowed_paymasters.contains(&paymaster_address) {
+                            return Err(TransactionError::UnauthorizedPaymaster(paymaster_address));
+                        }
+                        Ok(paymaster_address)
+                    }
+                    None => Ok(sender_address.clone()),
+                }
+            }
+        }
+    }
+}
+
+/// Transfers the amount actual_fee from the fee payer to the sequencer, in the fee token of the
+/// transaction's version.
 /// Returns the resulting CallInfo of the transfer call.
 pub(crate) fn execute_fee_transfer<S: State + StateReader>(
     state: &mut S,
     general_config: &StarknetGeneralConfig,
     tx_context: &TransactionExecutionContext,
-    actual_fee: u64,
+    fee_payer: &Address,
+    actual_fee: u128,
 ) -> Result<CallInfo, TransactionError> {
     if actual_fee > tx_context.max_fee {
         return Err(TransactionError::FeeError(
@@ -34,12 +108,15 @@ pub(crate) fn execute_fee_transfer<S: State + StateReader>(
         ));
     }
 
-    let fee_token_address = general_config.starknet_os_config.fee_token_address.clone();
+    let fee_token_address =
+        FeeType::for_version(tx_context.version).fee_token_address(general_config);
 
+    // The amount is a Uint256, passed as its low and high 128-bit words.
+    let (amount_low, amount_high) = split_uint256(&BigUint::from(actual_fee));
     let calldata = [
         general_config.block_info.sequencer_address.0.clone(),
-        Felt252::from(actual_fee),
-        0.into(),
+        amount_low,
+        amount_high,
     ]
     .to_vec();
 
@@ -47,11 +124,12 @@ pub(crate) fn execute_fee_transfer<S: State + StateReader>(
         fee_token_address,
         calldata,
         TRANSFER_ENTRY_POINT_SELECTOR.clone(),
-        tx_context.account_contract_address.clone(),
+        fee_payer.clone(),
         EntryPointType::External,
         None,
         None,
-    );
+    )
+    .fee_transfer();
 
     let mut resources_manager = ExecutionResourcesManager::default();
     fee_transfer_call
@@ -59,6 +137,96 @@ pub(crate) fn execute_fee_transfer<S: State + StateReader>(
         .map_err(|_| TransactionError::FeeError("Fee transfer failure".to_string()))
 }
 
+// ----------------------------------------------------------------------------------------
+/// Returns the balance of `account_address` in the fee token of the given type, read from the
+/// ERC20 `balances` storage variable.
+
+pub fn get_fee_token_balance<S: StateReader>(
+    state: &mut S,
+    general_config: &StarknetGeneralConfig,
+    fee_type: FeeType,
+    account_address: &Address,
+) -> Result<BigUint, TransactionError> {
+    let fee_token_address = fee_type.fee_token_address(general_config);
+    let (balance_low_key, balance_high_key) = get_erc20_balance_var_addresses(account_address)?;
+
+    let balance_low = state.get_storage_at(&(fee_token_address.clone(), balance_low_key))?;
+    let balance_high = state.get_storage_at(&(fee_token_address, balance_high_key))?;
+
+    Ok(balance_low.to_biguint() + (balance_high.to_biguint() << 128))
+}
+
+// ----------------------------------------------------------------------------------------
+/// Estimates the lowest fee a transaction of the given type can be charged: the OS resources of
+/// the transaction itself, plus the L1 gas of updating the sender's state and a fee balance.
+
+pub fn estimate_minimal_fee(
+    general_config: &StarknetGeneralConfig,
+    tx_type: TransactionType,
+) -> Result<u128, TransactionError> {
+    let n_deployments = match tx_type {
+        TransactionType::DeployAccount => 1,
+        _ => 0,
+    };
+    let l1_gas_usage = calculate_tx_gas_usage(Vec::new(), 1, 1, None, n_deployments);
+
+    let os_resources = get_additional_os_resources(
+        HashMap::new(),
+        &tx_type,
+        &general_config.block_info.starknet_version,
+    )?;
+
+    // Weighted like the actual resources of a transaction, see `calculate_tx_resources`.
+    let mut resources = os_resources.builtin_instance_counter;
+    resources.insert("l1_gas_usage".to_string(), l1_gas_usage);
+
+    calculate_tx_fee(
+        &resources,
+        general_config.block_info.gas_price,
+        general_config,
+    )
+}
+
+// ----------------------------------------------------------------------------------------
+/// Checks, before running a transaction, that its `max_fee` covers the minimal fee of its type
+/// and that the fee payer can pay it. Transactions without a `max_fee` are not charged, so they
+/// are not checked.
+
+pub(crate) fn check_fee_bounds_and_balance<S: StateReader>(
+    state: &mut S,
+    general_config: &StarknetGeneralConfig,
+    tx_context: &TransactionExecutionContext,
+    fee_payer: &Address,
+    tx_type: TransactionType,
+) -> Result<(), TransactionError> {
+    if tx_context.max_fee.is_zero() {
+        return Ok(());
+    }
+
+    let minimal_fee = estimate_minimal_fee(general_config, tx_type)?;
+    if tx_context.max_fee < minimal_fee {
+        return Err(TransactionError::MaxFeeTooLow(
+            tx_context.max_fee,
+            minimal_fee,
+        ));
+    }
+
+    let balance = get_fee_token_balance(
+        state,
+        general_config,
+        FeeType::for_version(tx_context.version),
+        fee_payer,
+    )?;
+    if balance < BigUint::from(tx_context.max_fee) {
+        return Err(TransactionError::InsufficientBalance(
+            balance,
+            tx_context.max_fee,
+        ));
+    }
+
+    Ok(())
+}
+
 // ----------------------------------------------------------------------------------------
 /// Calculates the fee of a transaction given its execution resources.
 /// We add the l1_gas_usage (which may include, for example, the direct cost of L2-to-L1
@@ -66,9 +234,9 @@ pub(crate) fn execute_fee_transfer<S: State + StateReader>(
 
 pub fn calculate_tx_fee(
     resources: &HashMap<String, usize>,
-    gas_price: u64,
+    gas_price: u128,
     general_config: &StarknetGeneralConfig,
-) -> Result<u64, TransactionError> {
+) -> Result<u128, TransactionError> {
     let gas_usage = resources
         .get(&"l1_gas_usage".to_string())
         .ok_or_else(|| TransactionError::FeeError("Invalid fee value".to_string()))?
@@ -77,7 +245,9 @@ pub fn calculate_tx_fee(
     let l1_gas_by_cairo_usage = calculate_l1_gas_by_cairo_usage(general_config, resources)?;
     let total_l1_gas_usage = gas_usage.to_f64().unwrap() + l1_gas_by_cairo_usage;
 
-    Ok(total_l1_gas_usage.ceil() as u64 * gas_price)
+    (total_l1_gas_usage.ceil() as u128)
+        .checked_mul(gas_price)
+        .ok_or_else(|| TransactionError::FeeError("Fee overflows u128".to_string()))
 }
 
 // ----------------------------------------------------------------------------------------
@@ -111,3 +281,74 @@ fn max_of_keys(cairo_rsc: &HashMap<String, usize>, weights: &HashMap<String, f64
     }
     max
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::constants::DEFAULT_GAS_PRICE;
+    use coverage_helper::test;
+
+    #[test]
+    fn fee_payer_follows_the_policy() {
+        let sender_address = Address(1.into());
+        let paymaster_fields = V3TransactionFields {
+            paymaster_data: vec![3.into()],
+            ..Default::default()
+        };
+        let paymaster_policy = FeePayerPolicy::Paymaster(HashSet::from([Address(3.into())]));
+
+        assert_eq!(
+            FeePayerPolicy::Sender
+                .fee_payer(&sender_address, Some(&paymaster_fields))
+                .unwrap(),
+            sender_address
+        );
+        assert_eq!(
+            FeePayerPolicy::Sponsor(Address(2.into()))
+                .fee_payer(&sender_address, None)
+                .unwrap(),
+            Address(2.into())
+        );
+        assert_eq!(
+            paymaster_policy
+                .fee_payer(&sender_address, Some(&paymaster_fields))
+                .unwrap(),
+            Address(3.into())
+        );
+        assert_eq!(
+            paymaster_policy.fee_payer(&sender_address, None).unwrap(),
+            sender_address
+        );
+    }
+
+    #[test]
+    fn unauthorized_paymaster_should_fail() {
+        let paymaster_fields = V3TransactionFields {
+            paymaster_data: vec![4.into()],
+            ..Default::default()
+        };
+
+        assert_matches!(
+            FeePayerPolicy::Paymaster(HashSet::from([Address(3.into())]))
+                .fee_payer(&Address(1.into()), Some(&paymaster_fields)),
+            Err(TransactionError::UnauthorizedPaymaster(address)) if address == Address(4.into())
+        );
+    }
+
+    #[test]
+    fn calculate_tx_fee_above_u64_max() {
+        let mut general_config = StarknetGeneralConfig::default();
+        general_config.block_info.gas_price = DEFAULT_GAS_PRICE;
+        let resources = HashMap::from([("l1_gas_usage".to_string(), 200_000_000)]);
+
+        let fee = calculate_tx_fee(
+            &resources,
+            general_config.block_info.gas_price,
+            &general_config,
+        )
+        .unwrap();
+
+        assert_eq!(fee, 200_000_000 * DEFAULT_GAS_PRICE);
+        assert!(fee > u64::MAX as u128);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{
+    business_logic::{execution::objects::CallInfo, transaction::error::TransactionError},
+    utils::{calculate_sn_keccak, Address},
+};
+use cairo_vm::felt::Felt252;
+use num_traits::ToPrimitive;
+
+/// Calldata layout expected by the `__execute__` entry point of an account.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CallFormat {
+    /// OpenZeppelin Cairo 0 accounts:
+    /// `[call_array_len, (to, selector, data_offset, data_len)*, calldata_len, calldata*]`.
+    #[default]
+    Legacy,
+    /// Cairo 1 accounts, taking an `Array<Call>`:
+    /// `[calls_len, (to, selector, calldata_len, calldata*)*]`.
+    Cairo1,
+}
+
+/// A call made by an account on behalf of the sender of a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Call {
+    pub to: Address,
+    pub selector: Felt252,
+    pub calldata: Vec<Felt252>,
+}
+
+impl Call {
+    pub fn new(to: Address, selector: Felt252, calldata: Vec<Felt252>) -> Self {
+        Call {
+            to,
+            selector,
+            calldata,
+        }
+    }
+
+    /// Creates a call to the entry point with the given name.
+    pub fn from_entry_point_name(
+        to: Address,
+        entry_point_name: &str,
+        calldata: Vec<Felt252>,
+    ) -> Self {
+        let selector = Felt252::from_bytes_be(&calculate_sn_keccak(entry_point_name.as_bytes()));
+        Self::new(to, selector, calldata)
+    }
+}
+
+/// Encodes a list of calls into the calldata of an account's `__execute__` entry point.
+pub fn encode_execute_calldata(calls: &[Call], format: CallFormat) -> Vec<Felt252> {
+    let mut calldata = vec![Felt252::from(calls.len())];
+
+    match format {
+        CallFormat::Legacy => {
+            let mut flattened_calldata = Vec::new();
+            for call in calls {
+                calldata.extend([
+                    call.to.0.clone(),
+                    call.selector.clone(),
+                    Felt252::from(flattened_calldata.len()),
+                    Felt252::from(call.calldata.len()),
+                ]);
+                flattened_calldata.extend(call.calldata.iter().cloned());
+            }
+            calldata.push(Felt252::from(flattened_calldata.len()));
+            calldata.extend(flattened_calldata);
+        }
+        CallFormat::Cairo1 => {
+            for call in calls {
+                calldata.extend([
+                    call.to.0.clone(),
+                    call.selector.clone(),
+                    Felt252::from(call.calldata.len()),
+                ]);
+                calldata.extend(call.calldata.iter().cloned());
+            }
+        }
+    }
+
+    calldata
+}
+
+/// Splits the return data of an account's `__execute__` call into the return data of each of
+/// its calls.
+/// Legacy accounts concatenate the return data of their calls, so it is split using the nested
+/// calls of `execute_call_info`. Cairo 1 accounts return an `Array<Span<felt252>>`.
+pub fn decode_execute_retdata(
+    execute_call_info: &CallInfo,
+    format: CallFormat,
+) -> Result<Vec<Vec<Felt252>>, TransactionError> {
+    let retdata = &execute_call_info.retdata;
+
+    match format {
+        CallFormat::Legacy => {
+            let mut results = Vec::with_capacity(execute_call_info.internal_calls.len());
+            let mut offset = 0;
+            for internal_call in &execute_call_info.internal_calls {
+                let end = offset + internal_call.retdata.len();
+                let result = retdata.get(offset..end).ok_or_else(|| {
+                    TransactionError::InvalidExecuteRetdata(
+                        "return data is shorter than the one of the calls".to_string(),
+                    )
+                })?;
+                results.push(result.to_vec());
+                offset = end;
+            }
+
+            if offset != retdata.len() {
+                return Err(TransactionError::InvalidExecuteRetdata(
+                    "return data is longer than the one of the calls".to_string(),
+                ));
+            }
+            Ok(results)
+        }
+        CallFormat::Cairo1 => {
+            let mut felts = retdata.iter();
+            let n_results = next_len(&mut felts)?;
+
+            let mut results = Vec::with_capacity(n_results);
+            for _ in 0..n_results {
+                let result_len = next_len(&mut felts)?;
+                let result: Vec<Felt252> = felts.by_ref().take(result_len).cloned().collect();
+                if result.len() != result_len {
+                    return Err(TransactionError::InvalidExecuteRetdata(
+                        "missing elements of a call result".to_string(),
+                    ));
+                }
+                results.push(result);
+            }
+
+            if felts.next().is_some() {
+                return Err(TransactionError::InvalidExecuteRetdata(
+                    "unexpected elements after the call results".to_string(),
+                ));
+            }
+            Ok(results)
+        }
+    }
+}
+
+fn next_len<'a>(felts: &mut impl Iterator<Item = &'a Felt252>) -> Result<usize, TransactionError> {
+    felts
+        .next()
+        .and_then(|len| len.to_usize())
+        .ok_or_else(|| TransactionError::InvalidExecuteRetdata("invalid length".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+
+    fn calls() -> Vec<Call> {
+        vec![
+            Call::new(Address(1.into()), 10.into(), vec![100.into(), 101.into()]),
+            Call::new(Address(2.into()), 20.into(), vec![]),
+            Call::new(Address(3.into()), 30.into(), vec![300.into()]),
+        ]
+    }
+
+    #[test]
+    fn encode_legacy_calldata() {
+        let expected: Vec<Felt252> = vec![
+            3, // call_array_len
+            1, 10, 0, 2, // first call
+            2, 20, 2, 0, // second call
+            3, 30, 2, 1, // third call
+            3, // calldata_len
+            100, 101, 300,
+        ]
+        .into_iter()
+        .map(Felt252::from)
+        .collect();
+
+        assert_eq!(
+            encode_execute_calldata(&calls(), CallFormat::Legacy),
+            expected
+        );
+    }
+
+    #[test]
+    fn encode_cairo1_calldata() {
+        let expected: Vec<Felt252> = vec![
+            3, // calls_len
+            1, 10, 2, 100, 101, // first call
+            2, 20, 0, // second call
+            3, 30, 1, 300, // third call
+        ]
+        .into_iter()
+        .map(Felt252::from)
+        .collect();
+
+        assert_eq!(
+            encode_execute_calldata(&calls(), CallFormat::Cairo1),
+            expected
+        );
+    }
+
+    #[test]
+    fn decode_legacy_retdata() {
+        let call_info = CallInfo {
+            retdata: vec![1.into(), 2.into(), 3.into()],
+            internal_calls: vec![
+                CallInfo {
+                    retdata: vec![1.into()],
+                    ..Default::default()
+                },
+                CallInfo::default(),
+                CallInfo {
+                    retdata: vec![2.into(), 3.into()],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            decode_execute_retdata(&call_info, CallFormat::Legacy).unwrap(),
+            vec![vec![1.into()], vec![], vec![2.into(), 3.into()]]
+        );
+    }
+
+    #[test]
+    fn decode_cairo1_retdata() {
+        let mut call_info = CallInfo {
+            retdata: vec![2, 1, 7, 2, 8, 9]
+                .into_iter()
+                .map(Felt252::from)
+                .collect(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            decode_execute_retdata(&call_info, CallFormat::Cairo1).unwrap(),
+            vec![vec![7.into()], vec![8.into(), 9.into()]]
+        );
+
+        call_info.retdata.pop();
+        assert_matches!(
+            decode_execute_retdata(&call_info, CallFormat::Cairo1),
+            Err(TransactionError::InvalidExecuteRetdata(_))
+        );
+    }
+}
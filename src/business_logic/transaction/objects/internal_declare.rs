@@ -5,16 +5,23 @@ use crate::{
             objects::{CallInfo, TransactionExecutionContext, TransactionExecutionInfo},
         },
         fact_state::state::ExecutionResourcesManager,
-        state::state_api::{State, StateReader},
+        state::{
+            child_state::ChildState,
+            state_api::{State, StateReader},
+        },
         transaction::{
             error::TransactionError,
-            fee::{calculate_tx_fee, execute_fee_transfer, FeeInfo},
+            fee::{calculate_tx_fee, check_fee_bounds_and_balance, execute_fee_transfer, FeeInfo},
+            objects::resource_bounds::V3TransactionFields,
         },
     },
     core::{
         contract_address::starknet_contract_address::compute_class_hash,
-        errors::state_errors::StateError,
-        transaction_hash::starknet_transaction_hash::calculate_declare_transaction_hash,
+        errors::{state_errors::StateError, syscall_handler_errors::SyscallHandlerError},
+        transaction_hash::starknet_transaction_hash::{
+            calculate_declare_transaction_hash, calculate_declare_v3_transaction_hash,
+            query_version,
+        },
     },
     definitions::{
         constants::VALIDATE_DECLARE_ENTRY_POINT_SELECTOR, general_config::StarknetGeneralConfig,
@@ -30,6 +37,9 @@ use std::collections::HashMap;
 // ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~
 ///  Represents an internal transaction in the StarkNet network that is a declaration of a Cairo
 ///  contract class.
+///  Version 0 declarations are unsigned and fee-less, version 1 declarations are signed and
+///  hash the class hash, and version 2 declarations also commit to a `compiled_class_hash`.
+///  Version 3 declarations replace `max_fee` with resource bounds and pay their fee in STRK.
 #[derive(Debug)]
 pub struct InternalDeclare {
     pub class_hash: ClassHash,
@@ -37,56 +47,175 @@ pub struct InternalDeclare {
     pub tx_type: TransactionType,
     pub validate_entry_point_selector: Felt252,
     pub version: u64,
-    pub max_fee: u64,
+    pub max_fee: u128,
     pub signature: Vec<Felt252>,
     pub nonce: Felt252,
     pub hash_value: Felt252,
     pub contract_class: ContractClass,
+    pub compiled_class_hash: Option<Felt252>,
+    pub v3_fields: Option<V3TransactionFields>,
 }
 
 // ------------------------------------------------------------
 //                        Functions
 // ------------------------------------------------------------
 impl InternalDeclare {
+    /// Creates a declare transaction of version 0 or 1.
     pub fn new(
         contract_class: ContractClass,
         chain_id: Felt252,
         sender_address: Address,
-        max_fee: u64,
+        max_fee: u128,
         version: u64,
         signature: Vec<Felt252>,
         nonce: Felt252,
     ) -> Result<Self, TransactionError> {
-        let hash = compute_class_hash(&contract_class)?;
-        let class_hash = hash.to_be_bytes();
-
-        let hash_value = calculate_declare_transaction_hash(
-            &contract_class,
+        Self::new_with_compiled_class_hash(
+            contract_class,
+            None,
             chain_id,
-            &sender_address,
+            sender_address,
             max_fee,
             version,
-            nonce.clone(),
-        )?;
+            signature,
+            nonce,
+        )
+    }
+
+    /// Creates a declare transaction of version 2, which commits to the hash of the compiled
+    /// class. The executable `contract_class` stands in for the Sierra class.
+    pub fn new_v2(
+        contract_class: ContractClass,
+        compiled_class_hash: Felt252,
+        chain_id: Felt252,
+        sender_address: Address,
+        max_fee: u128,
+        signature: Vec<Felt252>,
+        nonce: Felt252,
+    ) -> Result<Self, TransactionError> {
+        Self::new_with_compiled_class_hash(
+            contract_class,
+            Some(compiled_class_hash),
+            chain_id,
+            sender_address,
+            max_fee,
+            2,
+            signature,
+            nonce,
+        )
+    }
 
-        let validate_entry_point_selector = VALIDATE_DECLARE_ENTRY_POINT_SELECTOR.clone();
+    #[allow(clippy::too_many_arguments)]
+    fn new_with_compiled_class_hash(
+        contract_class: ContractClass,
+        compiled_class_hash: Option<Felt252>,
+        chain_id: Felt252,
+        sender_address: Address,
+        max_fee: u128,
+        version: u64,
+        signature: Vec<Felt252>,
+        nonce: Felt252,
+    ) -> Result<Self, TransactionError> {
+        let class_hash = compute_class_hash(&contract_class)?.to_be_bytes();
 
-        let internal_declare = InternalDeclare {
+        InternalDeclare {
             class_hash,
             sender_address,
             tx_type: TransactionType::Declare,
-            validate_entry_point_selector,
+            validate_entry_point_selector: VALIDATE_DECLARE_ENTRY_POINT_SELECTOR.clone(),
             version,
             max_fee,
             signature,
             nonce,
-            hash_value,
+            hash_value: Felt252::zero(),
+            contract_class,
+            compiled_class_hash,
+            v3_fields: None,
+        }
+        .with_hash_value(chain_id)
+    }
+
+    /// Creates a declare transaction of version 3, which pays its fee in STRK within the given
+    /// resource bounds instead of having a `max_fee`.
+    pub fn new_v3(
+        contract_class: ContractClass,
+        compiled_class_hash: Felt252,
+        chain_id: Felt252,
+        sender_address: Address,
+        signature: Vec<Felt252>,
+        nonce: Felt252,
+        v3_fields: V3TransactionFields,
+    ) -> Result<Self, TransactionError> {
+        let class_hash = compute_class_hash(&contract_class)?.to_be_bytes();
+
+        InternalDeclare {
+            class_hash,
+            sender_address,
+            tx_type: TransactionType::Declare,
+            validate_entry_point_selector: VALIDATE_DECLARE_ENTRY_POINT_SELECTOR.clone(),
+            version: 3,
+            max_fee: v3_fields.max_fee(),
+            signature,
+            nonce,
+            hash_value: Felt252::zero(),
             contract_class,
+            compiled_class_hash: Some(compiled_class_hash),
+            v3_fields: Some(v3_fields),
+        }
+        .with_hash_value(chain_id)
+    }
+
+    /// Verifies the version fields and sets the transaction hash.
+    /// The version is checked before hashing, so that malformed transactions are reported as such.
+    fn with_hash_value(self, chain_id: Felt252) -> Result<Self, TransactionError> {
+        self.verify_version()?;
+
+        let hash_value = match &self.v3_fields {
+            Some(v3_fields) => calculate_declare_v3_transaction_hash(
+                Felt252::from_bytes_be(&self.class_hash),
+                self.compiled_class_hash
+                    .clone()
+                    .ok_or(SyscallHandlerError::MissingCompiledClassHash)?,
+                &self.sender_address,
+                chain_id,
+                self.nonce.clone(),
+                v3_fields,
+            )?,
+            None => calculate_declare_transaction_hash(
+                &self.contract_class,
+                self.compiled_class_hash.as_ref(),
+                chain_id,
+                &self.sender_address,
+                self.max_fee,
+                self.version.into(),
+                self.nonce.clone(),
+            )?,
         };
 
-        internal_declare.verify_version()?;
+        Ok(InternalDeclare { hash_value, ..self })
+    }
+
+    /// Rehashes the transaction with the query version of its version, as clients do for
+    /// transactions sent only to be estimated or simulated. Version 3 transactions aren't
+    /// supported.
+    pub fn with_query_version(self, chain_id: Felt252) -> Result<Self, TransactionError> {
+        if self.v3_fields.is_some() {
+            return Err(TransactionError::StarknetError(
+                "Queries of version 3 declare transactions are not supported.".to_string(),
+            ));
+        }
+
+        let hash_value = calculate_declare_transaction_hash(
+            &self.contract_class,
+            self.compiled_class_hash.as_ref(),
+            chain_id,
+            &self.sender_address,
+            self.max_fee,
+            query_version(self.version),
+            self.nonce.clone(),
+        )?;
 
-        Ok(internal_declare)
+        Ok(InternalDeclare { hash_value, ..self })
     }
 
     pub fn get_calldata(&self) -> Vec<Felt252> {
@@ -95,26 +224,62 @@ impl InternalDeclare {
     }
 
     pub fn verify_version(&self) -> Result<(), TransactionError> {
-        if self.version.is_zero() {
-            if !self.max_fee.is_zero() {
-                return Err(TransactionError::StarknetError(
-                    "The max_fee field in Declare transactions of version 0 must be 0.".to_string(),
-                ));
+        match self.version {
+            0 => {
+                if !self.max_fee.is_zero() {
+                    return Err(TransactionError::StarknetError(
+                        "The max_fee field in Declare transactions of version 0 must be 0."
+                            .to_string(),
+                    ));
+                }
+
+                if !self.nonce.is_zero() {
+                    return Err(TransactionError::StarknetError(
+                        "The nonce field in Declare transactions of version 0 must be 0."
+                            .to_string(),
+                    ));
+                }
+
+                if !self.signature.is_empty() {
+                    return Err(TransactionError::StarknetError(
+                        "The signature field in Declare transactions of version 0 must be an empty list."
+                            .to_string(),
+                    ));
+                }
             }
-
-            if !self.nonce.is_zero() {
-                return Err(TransactionError::StarknetError(
-                    "The nonce field in Declare transactions of version 0 must be 0.".to_string(),
-                ));
+            1 | 2 => {
+                if self.v3_fields.is_some() {
+                    return Err(TransactionError::StarknetError(format!(
+                        "Declare transactions of version {} cannot have resource bounds.",
+                        self.version
+                    )));
+                }
+            }
+            3 => {
+                if self.v3_fields.is_none() {
+                    return Err(TransactionError::StarknetError(
+                        "Declare transactions of version 3 must have resource bounds.".to_string(),
+                    ));
+                }
+            }
+            version => {
+                return Err(TransactionError::StarknetError(format!(
+                    "Declare transactions of version {version} are not supported."
+                )));
             }
         }
 
-        if !self.signature.len().is_zero() {
-            return Err(TransactionError::StarknetError(
-                "The signature field in Declare transactions must be an empty list.".to_string(),
-            ));
+        match (self.version, &self.compiled_class_hash) {
+            (2 | 3, None) => Err(TransactionError::StarknetError(format!(
+                "Declare transactions of version {} must have a compiled_class_hash.",
+                self.version
+            ))),
+            (0 | 1, Some(_)) => Err(TransactionError::StarknetError(format!(
+                "Declare transactions of version {} cannot have a compiled_class_hash.",
+                self.version
+            ))),
+            _ => Ok(()),
         }
-        Ok(())
     }
 
     /// Executes a call to the cairo-vm using the accounts_validation.cairo contract to validate
@@ -138,6 +303,7 @@ impl InternalDeclare {
             TransactionType::Declare,
             changes,
             None,
+            &general_config.block_info.starknet_version,
         )
         .map_err(|_| TransactionError::ResourcesCalculation)?;
 
@@ -186,13 +352,14 @@ impl InternalDeclare {
             EntryPointType::External,
             None,
             None,
-        );
+        )
+        .with_max_n_steps(general_config.validate_max_n_steps);
 
         let call_info = entry_point.execute(
             state,
             general_config,
             resources_manager,
-            &self.get_execution_context(general_config.invoke_tx_max_n_steps),
+            &self.get_execution_context(general_config.validate_max_n_steps),
         )?;
 
         verify_no_calls_to_other_contracts(&call_info)
@@ -201,12 +368,36 @@ impl InternalDeclare {
         Ok(Some(call_info))
     }
 
-    /// Calculates and charges the actual fee.
+    /// Runs only the `__validate_declare__` entry point of the account over `state`, discarding
+    /// its writes. The class isn't declared, the nonce isn't checked nor incremented and no fee
+    /// is charged.
+    /// Returns the validate call info along with the resources it used.
+    pub fn validate<S: State + StateReader>(
+        &self,
+        state: &mut S,
+        general_config: &StarknetGeneralConfig,
+    ) -> Result<TransactionExecutionInfo, TransactionError> {
+        self.apply(&mut ChildState::new(state), general_config)
+    }
+
+    /// Returns the account debited the fee of the transaction, see `FeePayerPolicy`.
+    pub fn fee_payer(
+        &self,
+        general_config: &StarknetGeneralConfig,
+    ) -> Result<Address, TransactionError> {
+        general_config
+            .fee_payer_policy
+            .fee_payer(&self.sender_address, self.v3_fields.as_ref())
+    }
+
+    /// Calculates and charges the actual fee. A reverted transaction is charged at most its max
+    /// fee.
     pub fn charge_fee<S: State + StateReader>(
         &self,
         state: &mut S,
         resources: &HashMap<String, usize>,
         general_config: &StarknetGeneralConfig,
+        is_reverted: bool,
     ) -> Result<FeeInfo, TransactionError> {
         if self.max_fee.is_zero() {
             return Ok((None, 0));
@@ -214,13 +405,23 @@ impl InternalDeclare {
 
         let actual_fee = calculate_tx_fee(
             resources,
-            general_config.starknet_os_config.gas_price,
+            general_config.block_info.gas_price,
             general_config,
         )?;
+        let actual_fee = if is_reverted {
+            actual_fee.min(self.max_fee)
+        } else {
+            actual_fee
+        };
 
         let tx_context = self.get_execution_context(general_config.invoke_tx_max_n_steps);
-        let fee_transfer_info =
-            execute_fee_transfer(state, general_config, &tx_context, actual_fee)?;
+        let fee_transfer_info = execute_fee_transfer(
+            state,
+            general_config,
+            &tx_context,
+            &self.fee_payer(general_config)?,
+            actual_fee,
+        )?;
 
         Ok((Some(fee_transfer_info), actual_fee))
     }
@@ -246,19 +447,30 @@ impl InternalDeclare {
 
     /// Calculates actual fee used by the transaction using the execution
     /// info returned by apply(), then updates the transaction execution info with the data of the fee.
+    /// A rejected transaction (e.g. an already declared class, an invalid nonce or a failed
+    /// validation) leaves the state untouched. Declaring the class is the execution of the
+    /// transaction: if it fails, the transaction is reverted and only keeps its nonce increment
+    /// and fee charge.
     pub fn execute<S: State + StateReader>(
         &self,
         state: &mut S,
         general_config: &StarknetGeneralConfig,
     ) -> Result<TransactionExecutionInfo, TransactionError> {
-        let concurrent_exec_info = self.apply(state, general_config)?;
-        self.handle_nonce(state)?;
-        // Set contract class
-        match state.get_contract_class(&self.class_hash) {
-            Err(StateError::MissingClassHash()) => {
-                // Class is undeclared; declare it.
-                state.set_contract_class(&self.class_hash, &self.contract_class)?;
-            }
+        if let Some(v3_fields) = &self.v3_fields {
+            v3_fields.check_l1_gas_price(general_config.block_info.gas_price)?;
+        }
+
+        let mut tx_state = ChildState::new(state);
+
+        check_fee_bounds_and_balance(
+            &mut tx_state,
+            general_config,
+            &self.get_execution_context(general_config.invoke_tx_max_n_steps),
+            &self.fee_payer(general_config)?,
+            self.tx_type,
+        )?;
+        match tx_state.get_contract_class(&self.class_hash) {
+            Err(StateError::MissingClassHash()) => {}
             Err(error) => return Err(error.into()),
             Ok(_) => {
                 // Class is already declared; cannot redeclare.
@@ -266,12 +478,18 @@ impl InternalDeclare {
             }
         }
 
+        let concurrent_exec_info = self.apply(&mut tx_state, general_config)?;
+        self.handle_nonce(&mut tx_state)?;
+        tx_state.set_contract_class(&self.class_hash, &self.contract_class)?;
+
         let (fee_transfer_info, actual_fee) = self.charge_fee(
-            state,
+            &mut tx_state,
             &concurrent_exec_info.actual_resources,
             general_config,
+            concurrent_exec_info.is_reverted(),
         )?;
 
+        tx_state.commit()?;
         Ok(
             TransactionExecutionInfo::from_concurrent_state_execution_info(
                 concurrent_exec_info,
@@ -402,6 +620,7 @@ mod tests {
             actual_fee: 0,
             actual_resources,
             tx_type: Some(TransactionType::Declare),
+            revert_error: None,
         };
 
         // ---------------------
@@ -603,6 +822,93 @@ mod tests {
         );
     }
 
+    #[test]
+    fn declare_v1_accepts_signature_and_passes_it_to_validation() {
+        let fib_contract_class =
+            ContractClass::try_from(PathBuf::from("starknet_programs/fibonacci.json")).unwrap();
+        let signature = vec![1.into(), 2.into()];
+
+        let internal_declare = InternalDeclare::new(
+            fib_contract_class,
+            StarknetChainId::TestNet.to_felt(),
+            Address(Felt252::one()),
+            0,
+            1,
+            signature.clone(),
+            Felt252::zero(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            internal_declare.get_execution_context(0).signature,
+            signature
+        );
+    }
+
+    #[test]
+    fn declare_v2_commits_to_compiled_class_hash() {
+        let fib_contract_class =
+            ContractClass::try_from(PathBuf::from("starknet_programs/fibonacci.json")).unwrap();
+        let chain_id = StarknetChainId::TestNet.to_felt();
+
+        let declare_v1 = InternalDeclare::new(
+            fib_contract_class.clone(),
+            chain_id.clone(),
+            Address(Felt252::one()),
+            0,
+            1,
+            vec![1.into()],
+            Felt252::zero(),
+        )
+        .unwrap();
+        let declare_v2 = InternalDeclare::new_v2(
+            fib_contract_class.clone(),
+            3.into(),
+            chain_id.clone(),
+            Address(Felt252::one()),
+            0,
+            vec![1.into()],
+            Felt252::zero(),
+        )
+        .unwrap();
+        let other_declare_v2 = InternalDeclare::new_v2(
+            fib_contract_class,
+            4.into(),
+            chain_id,
+            Address(Felt252::one()),
+            0,
+            vec![1.into()],
+            Felt252::zero(),
+        )
+        .unwrap();
+
+        assert_eq!(declare_v2.version, 2);
+        assert_eq!(declare_v2.class_hash, declare_v1.class_hash);
+        assert_ne!(declare_v2.hash_value, declare_v1.hash_value);
+        assert_ne!(declare_v2.hash_value, other_declare_v2.hash_value);
+    }
+
+    #[test]
+    fn declare_v2_without_compiled_class_hash_should_fail() {
+        let fib_contract_class =
+            ContractClass::try_from(PathBuf::from("starknet_programs/fibonacci.json")).unwrap();
+
+        let internal_declare = InternalDeclare::new(
+            fib_contract_class,
+            StarknetChainId::TestNet.to_felt(),
+            Address(Felt252::one()),
+            0,
+            2,
+            Vec::new(),
+            Felt252::zero(),
+        );
+
+        assert_matches!(
+            internal_declare.unwrap_err(),
+            TransactionError::StarknetError(..)
+        );
+    }
+
     #[test]
     fn execute_class_already_declared_should_fail() {
         // accounts contract class must be stored before running declaration of fibonacci
@@ -681,6 +987,8 @@ mod tests {
             expected_error.unwrap_err(),
             TransactionError::ClassAlreadyDeclared(..)
         );
+        // The rejected transaction doesn't increment the nonce.
+        assert_eq!(state.get_nonce_at(&Address(1.into())), Ok(Felt252::one()));
     }
 
     #[test]
@@ -790,7 +1098,7 @@ mod tests {
     }
 
     #[test]
-    fn execute_transaction_charge_fee_should_fail() {
+    fn execute_transaction_without_balance_should_fail() {
         // accounts contract class must be stored before running declaration of fibonacci
         let path = PathBuf::from("starknet_programs/account_without_validation.json");
         let contract_class = ContractClass::try_from(path).unwrap();
@@ -829,7 +1137,7 @@ mod tests {
 
         let chain_id = StarknetChainId::TestNet.to_felt();
 
-        // Use non-zero value so that the actual fee calculation is done
+        // Use non-zero value so that the fee checks are done
         let internal_declare = InternalDeclare::new(
             fib_contract_class,
             chain_id,
@@ -841,10 +1149,14 @@ mod tests {
         )
         .unwrap();
 
-        // We expect a fee transfer failure because the fee token contract is not set up
+        // The sender has no balance in the fee token, so the declaration is rejected before
+        // running
         assert_matches!(
             internal_declare.execute(&mut state, &StarknetGeneralConfig::default()),
-            Err(TransactionError::FeeError(e)) if e == "Fee transfer failure"
+            Err(TransactionError::InsufficientBalance(balance, 10)) if balance == 0_u32.into()
         );
+        assert!(state
+            .get_contract_class(&internal_declare.class_hash)
+            .is_err());
     }
 }
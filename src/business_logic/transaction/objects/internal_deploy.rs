@@ -92,7 +92,7 @@ impl InternalDeploy {
 
         if constructors.map(Vec::is_empty).unwrap_or(true) {
             // Contract has no constructors
-            Ok(self.handle_empty_constructor(state)?)
+            Ok(self.handle_empty_constructor(state, general_config)?)
         } else {
             self.invoke_constructor(state, general_config)
         }
@@ -101,6 +101,7 @@ impl InternalDeploy {
     pub fn handle_empty_constructor<T: State + StateReader>(
         &self,
         state: &mut T,
+        general_config: &StarknetGeneralConfig,
     ) -> Result<TransactionExecutionInfo, StarkwareError> {
         if !self.constructor_calldata.is_empty() {
             return Err(StarkwareError::TransactionFailed);
@@ -122,6 +123,7 @@ impl InternalDeploy {
             self.tx_type,
             changes,
             None,
+            &general_config.block_info.starknet_version,
         )
         .map_err(|_| StarkwareError::UnexpectedHolesL2toL1Messages)?;
 
@@ -175,6 +177,7 @@ impl InternalDeploy {
             self.tx_type,
             changes,
             None,
+            &general_config.block_info.starknet_version,
         )?;
 
         Ok(
@@ -6,15 +6,22 @@ use crate::{
             objects::{CallInfo, TransactionExecutionContext, TransactionExecutionInfo},
         },
         fact_state::state::ExecutionResourcesManager,
-        state::state_api::{State, StateReader},
+        state::{
+            child_state::ChildState,
+            state_api::{State, StateReader},
+        },
         transaction::{
             error::TransactionError,
-            fee::{calculate_tx_fee, execute_fee_transfer, FeeInfo},
+            fee::{calculate_tx_fee, check_fee_bounds_and_balance, execute_fee_transfer, FeeInfo},
+            objects::resource_bounds::V3TransactionFields,
         },
     },
     core::{
         errors::syscall_handler_errors::SyscallHandlerError,
-        transaction_hash::starknet_transaction_hash::calculate_deploy_account_transaction_hash,
+        transaction_hash::starknet_transaction_hash::{
+            calculate_deploy_account_transaction_hash,
+            calculate_deploy_account_v3_transaction_hash, query_version,
+        },
     },
     definitions::{
         constants::{CONSTRUCTOR_ENTRY_POINT_SELECTOR, VALIDATE_DEPLOY_ENTRY_POINT_SELECTOR},
@@ -48,20 +55,25 @@ pub struct InternalDeployAccount {
     #[getset(get = "pub")]
     constructor_calldata: Vec<Felt252>,
     version: u64,
+    #[getset(get = "pub")]
     nonce: Felt252,
-    max_fee: u64,
+    #[getset(get = "pub")]
+    max_fee: u128,
     #[getset(get = "pub")]
     hash_value: Felt252,
     #[getset(get = "pub")]
     signature: Vec<Felt252>,
+    #[getset(get = "pub")]
     chain_id: StarknetChainId,
+    #[getset(get = "pub")]
+    v3_fields: Option<V3TransactionFields>,
 }
 
 impl InternalDeployAccount {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         class_hash: ClassHash,
-        max_fee: u64,
+        max_fee: u128,
         version: u64,
         nonce: Felt252,
         constructor_calldata: Vec<Felt252>,
@@ -77,7 +89,7 @@ impl InternalDeployAccount {
         )?);
 
         let hash_value = calculate_deploy_account_transaction_hash(
-            version,
+            version.into(),
             &contract_address,
             Felt252::from_bytes_be(&class_hash),
             &constructor_calldata,
@@ -98,9 +110,89 @@ impl InternalDeployAccount {
             hash_value,
             signature,
             chain_id,
+            v3_fields: None,
+        })
+    }
+
+    /// Creates a version 3 deploy account transaction, which pays its fee in STRK within the
+    /// given resource bounds.
+    pub fn new_v3(
+        class_hash: ClassHash,
+        nonce: Felt252,
+        constructor_calldata: Vec<Felt252>,
+        signature: Vec<Felt252>,
+        contract_address_salt: Address,
+        chain_id: StarknetChainId,
+        v3_fields: V3TransactionFields,
+    ) -> Result<Self, TransactionError> {
+        if !v3_fields.account_deployment_data.is_empty() {
+            return Err(TransactionError::StarknetError(
+                "Deploy account transactions cannot have account deployment data.".to_string(),
+            ));
+        }
+
+        let contract_address = Address(calculate_contract_address(
+            &contract_address_salt,
+            &Felt252::from_bytes_be(&class_hash),
+            &constructor_calldata,
+            Address(Felt252::zero()),
+        )?);
+
+        let hash_value = calculate_deploy_account_v3_transaction_hash(
+            &contract_address,
+            Felt252::from_bytes_be(&class_hash),
+            &constructor_calldata,
+            contract_address_salt.0.clone(),
+            chain_id.to_felt(),
+            nonce.clone(),
+            &v3_fields,
+        )?;
+
+        Ok(Self {
+            contract_address,
+            contract_address_salt,
+            class_hash,
+            constructor_calldata,
+            version: 3,
+            nonce,
+            max_fee: v3_fields.max_fee(),
+            hash_value,
+            signature,
+            chain_id,
+            v3_fields: Some(v3_fields),
         })
     }
 
+    /// Signature checked by `__validate_deploy__`.
+    pub fn with_signature(mut self, signature: Vec<Felt252>) -> Self {
+        self.signature = signature;
+        self
+    }
+
+    /// Rehashes the transaction with the query version of its version, as clients do for
+    /// transactions sent only to be estimated or simulated. Version 3 transactions aren't
+    /// supported.
+    pub fn with_query_version(self) -> Result<Self, TransactionError> {
+        if self.v3_fields.is_some() {
+            return Err(TransactionError::StarknetError(
+                "Queries of version 3 deploy account transactions are not supported.".to_string(),
+            ));
+        }
+
+        let hash_value = calculate_deploy_account_transaction_hash(
+            query_version(self.version),
+            &self.contract_address,
+            Felt252::from_bytes_be(&self.class_hash),
+            &self.constructor_calldata,
+            self.max_fee,
+            self.nonce.clone(),
+            self.contract_address_salt.0.clone(),
+            self.chain_id.to_felt(),
+        )?;
+
+        Ok(Self { hash_value, ..self })
+    }
+
     pub fn get_state_selector(&self, _general_config: StarknetGeneralConfig) -> StateSelector {
         StateSelector {
             contract_addresses: vec![self.contract_address.clone()],
@@ -108,6 +200,11 @@ impl InternalDeployAccount {
         }
     }
 
+    /// Deploys the account, runs its constructor and validates the transaction, then charges the
+    /// fee. A failure leaves the state untouched.
+    /// Unlike invoke and declare transactions, a deploy account transaction never reverts: its
+    /// constructor runs before `__validate_deploy__`, so a failed constructor is not authorized
+    /// by the account to pay for it and rejects the transaction.
     pub fn execute<S>(
         &self,
         state: &mut S,
@@ -116,12 +213,27 @@ impl InternalDeployAccount {
     where
         S: State + StateReader,
     {
-        let tx_info = self.apply(state, general_config)?;
+        if let Some(v3_fields) = &self.v3_fields {
+            v3_fields.check_l1_gas_price(general_config.block_info.gas_price)?;
+        }
+
+        let mut tx_state = ChildState::new(state);
 
-        self.handle_nonce(state)?;
+        check_fee_bounds_and_balance(
+            &mut tx_state,
+            general_config,
+            &self.get_execution_context(general_config.invoke_tx_max_n_steps),
+            &self.fee_payer(general_config)?,
+            TransactionType::DeployAccount,
+        )?;
+
+        let tx_info = self.apply(&mut tx_state, general_config)?;
+
+        self.handle_nonce(&mut tx_state)?;
         let (fee_transfer_info, actual_fee) =
-            self.charge_fee(state, &tx_info.actual_resources, general_config)?;
+            self.charge_fee(&mut tx_state, &tx_info.actual_resources, general_config)?;
 
+        tx_state.commit()?;
         Ok(
             TransactionExecutionInfo::from_concurrent_state_execution_info(
                 tx_info,
@@ -162,6 +274,7 @@ impl InternalDeployAccount {
             TransactionType::DeployAccount,
             state.count_actual_storage_changes(),
             None,
+            &general_config.block_info.starknet_version,
         )
         .map_err::<TransactionError, _>(|_| TransactionError::ResourcesCalculation)?;
 
@@ -261,17 +374,7 @@ impl InternalDeployAccount {
     pub fn get_execution_context(&self, n_steps: u64) -> TransactionExecutionContext {
         TransactionExecutionContext::new(
             self.contract_address.clone(),
-            calculate_deploy_account_transaction_hash(
-                self.version,
-                &self.contract_address,
-                Felt252::from_bytes_be(&self.class_hash),
-                &self.constructor_calldata,
-                self.max_fee,
-                self.nonce.clone(),
-                self.contract_address_salt.0.clone(),
-                self.chain_id.to_felt(),
-            )
-            .unwrap(),
+            self.hash_value.clone(),
             self.signature.clone(),
             self.max_fee,
             self.nonce.clone(),
@@ -307,7 +410,8 @@ impl InternalDeployAccount {
             EntryPointType::External,
             None,
             None,
-        );
+        )
+        .with_max_n_steps(general_config.validate_max_n_steps);
 
         let call_info = call.execute(
             state,
@@ -322,6 +426,28 @@ impl InternalDeployAccount {
         Ok(Some(call_info))
     }
 
+    /// Runs the constructor and then the `__validate_deploy__` entry point of the account over
+    /// `state`, discarding their writes. The account isn't deployed, the nonce isn't checked nor
+    /// incremented and no fee is charged.
+    /// Returns the validate and constructor call infos along with the resources they used.
+    pub fn validate<S: State + StateReader>(
+        &self,
+        state: &mut S,
+        general_config: &StarknetGeneralConfig,
+    ) -> Result<TransactionExecutionInfo, TransactionError> {
+        self.apply(&mut ChildState::new(state), general_config)
+    }
+
+    /// Returns the account debited the fee of the transaction, see `FeePayerPolicy`.
+    pub fn fee_payer(
+        &self,
+        general_config: &StarknetGeneralConfig,
+    ) -> Result<Address, TransactionError> {
+        general_config
+            .fee_payer_policy
+            .fee_payer(&self.contract_address, self.v3_fields.as_ref())
+    }
+
     fn charge_fee<S>(
         &self,
         state: &mut S,
@@ -337,13 +463,18 @@ impl InternalDeployAccount {
 
         let actual_fee = calculate_tx_fee(
             resources,
-            general_config.starknet_os_config.gas_price,
+            general_config.block_info.gas_price,
             general_config,
         )?;
 
         let tx_context = self.get_execution_context(general_config.invoke_tx_max_n_steps);
-        let fee_transfer_info =
-            execute_fee_transfer(state, general_config, &tx_context, actual_fee)?;
+        let fee_transfer_info = execute_fee_transfer(
+            state,
+            general_config,
+            &tx_context,
+            &self.fee_payer(general_config)?,
+            actual_fee,
+        )?;
 
         Ok((Some(fee_transfer_info), actual_fee))
     }
@@ -7,14 +7,22 @@ use crate::{
             objects::{CallInfo, TransactionExecutionContext, TransactionExecutionInfo},
         },
         fact_state::state::ExecutionResourcesManager,
-        state::state_api::{State, StateReader},
+        state::{
+            child_state::ChildState,
+            state_api::{State, StateReader},
+        },
         transaction::{
             error::TransactionError,
-            fee::{calculate_tx_fee, execute_fee_transfer, FeeInfo},
+            fee::{calculate_tx_fee, check_fee_bounds_and_balance, execute_fee_transfer, FeeInfo},
+            objects::{
+                call::{encode_execute_calldata, Call, CallFormat},
+                resource_bounds::V3TransactionFields,
+            },
         },
     },
     core::transaction_hash::starknet_transaction_hash::{
-        calculate_transaction_hash_common, TransactionHashPrefix,
+        calculate_invoke_v3_transaction_hash, calculate_transaction_hash_common, query_version,
+        TransactionHashPrefix,
     },
     definitions::{
         constants::{EXECUTE_ENTRY_POINT_SELECTOR, TRANSACTION_VERSION},
@@ -36,6 +44,7 @@ pub struct InternalInvokeFunction {
     entry_point_selector: Felt252,
     #[allow(dead_code)]
     entry_point_type: EntryPointType,
+    #[getset(get = "pub")]
     calldata: Vec<Felt252>,
     tx_type: TransactionType,
     version: u64,
@@ -44,15 +53,19 @@ pub struct InternalInvokeFunction {
     hash_value: Felt252,
     #[getset(get = "pub")]
     signature: Vec<Felt252>,
-    max_fee: u64,
+    #[getset(get = "pub")]
+    max_fee: u128,
+    #[getset(get = "pub")]
     nonce: Option<Felt252>,
+    #[getset(get = "pub")]
+    v3_fields: Option<V3TransactionFields>,
 }
 
 impl InternalInvokeFunction {
     pub fn new(
         contract_address: Address,
         entry_point_selector: Felt252,
-        max_fee: u64,
+        max_fee: u128,
         calldata: Vec<Felt252>,
         signature: Vec<Felt252>,
         chain_id: Felt252,
@@ -66,7 +79,7 @@ impl InternalInvokeFunction {
         )?;
         let hash_value = calculate_transaction_hash_common(
             TransactionHashPrefix::Invoke,
-            version,
+            version.into(),
             &contract_address,
             entry_point_selector_field,
             &calldata,
@@ -88,9 +101,101 @@ impl InternalInvokeFunction {
             validate_entry_point_selector,
             nonce,
             hash_value,
+            v3_fields: None,
         })
     }
 
+    /// Creates an invoke transaction of the account's `__execute__` entry point, which makes
+    /// the given calls encoded in the calldata format of the account.
+    pub fn from_calls(
+        account_address: Address,
+        calls: &[Call],
+        call_format: CallFormat,
+        max_fee: u128,
+        signature: Vec<Felt252>,
+        chain_id: Felt252,
+        nonce: Felt252,
+    ) -> Result<Self, TransactionError> {
+        Self::new(
+            account_address,
+            EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+            max_fee,
+            encode_execute_calldata(calls, call_format),
+            signature,
+            chain_id,
+            Some(nonce),
+        )
+    }
+
+    /// Creates a version 3 invoke transaction of the account's `__execute__` entry point, which
+    /// pays its fee in STRK within the given resource bounds.
+    pub fn new_v3(
+        contract_address: Address,
+        calldata: Vec<Felt252>,
+        signature: Vec<Felt252>,
+        chain_id: Felt252,
+        nonce: Felt252,
+        v3_fields: V3TransactionFields,
+    ) -> Result<Self, TransactionError> {
+        let hash_value = calculate_invoke_v3_transaction_hash(
+            &contract_address,
+            &calldata,
+            chain_id,
+            nonce.clone(),
+            &v3_fields,
+        )?;
+
+        Ok(InternalInvokeFunction {
+            contract_address,
+            entry_point_selector: EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+            entry_point_type: EntryPointType::External,
+            calldata,
+            tx_type: TransactionType::InvokeFunction,
+            version: 3,
+            max_fee: v3_fields.max_fee(),
+            signature,
+            validate_entry_point_selector: VALIDATE_ENTRY_POINT_SELECTOR.clone(),
+            nonce: Some(nonce),
+            hash_value,
+            v3_fields: Some(v3_fields),
+        })
+    }
+
+    /// Replaces the signature checked by the account's `__validate__`.
+    pub fn with_signature(mut self, signature: Vec<Felt252>) -> Self {
+        self.signature = signature;
+        self
+    }
+
+    /// Rehashes the transaction with the query version of its version, as clients do for
+    /// transactions sent only to be estimated or simulated. Version 3 transactions aren't
+    /// supported.
+    pub fn with_query_version(self, chain_id: Felt252) -> Result<Self, TransactionError> {
+        if self.v3_fields.is_some() {
+            return Err(TransactionError::StarknetError(
+                "Queries of version 3 invoke transactions are not supported.".to_string(),
+            ));
+        }
+
+        let (entry_point_selector_field, additional_data) = preprocess_invoke_function_fields(
+            self.entry_point_selector.clone(),
+            self.nonce.clone(),
+            self.version,
+        )?;
+        let hash_value = calculate_transaction_hash_common(
+            TransactionHashPrefix::Invoke,
+            query_version(self.version),
+            &self.contract_address,
+            entry_point_selector_field,
+            &self.calldata,
+            self.max_fee,
+            chain_id,
+            &additional_data,
+        )?;
+
+        Ok(InternalInvokeFunction { hash_value, ..self })
+    }
+
     fn get_execution_context(
         &self,
         n_steps: u64,
@@ -130,7 +235,8 @@ impl InternalInvokeFunction {
             EntryPointType::External,
             None,
             None,
-        );
+        )
+        .with_max_n_steps(general_config.validate_max_n_steps);
 
         let call_info = call.execute(
             state,
@@ -178,8 +284,27 @@ impl InternalInvokeFunction {
         )
     }
 
+    pub fn verify_version(&self) -> Result<(), TransactionError> {
+        match (self.version, &self.v3_fields) {
+            (0 | 1 | u64::MAX, None) | (3, Some(_)) => Ok(()),
+            (3, None) => Err(TransactionError::StarknetError(
+                "Invoke transactions of version 3 must have resource bounds.".to_string(),
+            )),
+            (0 | 1 | u64::MAX, Some(_)) => Err(TransactionError::StarknetError(format!(
+                "Invoke transactions of version {} cannot have resource bounds.",
+                self.version
+            ))),
+            (version, _) => Err(TransactionError::StarknetError(format!(
+                "Invoke transactions of version {version} are not supported."
+            ))),
+        }
+    }
+
     /// Execute a call to the cairo-vm using the accounts_validation.cairo contract to validate
     /// the contract that is being declared. Then it returns the transaction execution info of the run.
+    /// A validation failure rejects the transaction and is returned as an error, while an
+    /// execution failure reverts it: its state changes are discarded and the returned execution
+    /// info holds the `revert_error`, along with the resources used up to the failure.
     pub fn apply<T>(
         &self,
         state: &mut T,
@@ -188,30 +313,85 @@ impl InternalInvokeFunction {
     where
         T: State + StateReader,
     {
+        self.verify_version()?;
+
         let mut resources_manager = ExecutionResourcesManager::default();
 
         let validate_info =
             self.run_validate_entrypoint(state, &mut resources_manager, general_config)?;
         // Execute transaction
-        let call_info =
-            self.run_execute_entrypoint(state, general_config, &mut resources_manager)?;
+        let (call_info, revert_error) =
+            match self.run_execute_entrypoint(state, general_config, &mut resources_manager) {
+                Ok(call_info) => (Some(call_info), None),
+                Err(error) => (None, Some(error.into())),
+            };
         let changes = state.count_actual_storage_changes();
         let actual_resources = calculate_tx_resources(
             resources_manager,
-            &vec![Some(call_info.clone()), validate_info.clone()],
+            &vec![call_info.clone(), validate_info.clone()],
             self.tx_type,
             changes,
             None,
+            &general_config.block_info.starknet_version,
         )?;
 
         let transaction_execution_info =
             TransactionExecutionInfo::create_concurrent_stage_execution_info(
                 validate_info,
-                Some(call_info),
+                call_info,
                 actual_resources,
                 Some(self.tx_type),
             );
-        Ok(transaction_execution_info)
+        Ok(TransactionExecutionInfo {
+            revert_error,
+            ..transaction_execution_info
+        })
+    }
+
+    /// Runs only the `__validate__` entry point of the account over `state`, discarding its
+    /// writes, e.g. to check the signature of a transaction before submitting it. The nonce isn't
+    /// checked nor incremented and no fee is charged.
+    /// Returns the validate call info along with the resources it used, or the error that would
+    /// reject the transaction.
+    pub fn validate<S: State + StateReader>(
+        &self,
+        state: &mut S,
+        general_config: &StarknetGeneralConfig,
+    ) -> Result<TransactionExecutionInfo, TransactionError> {
+        self.verify_version()?;
+
+        let mut state = ChildState::new(state);
+        let mut resources_manager = ExecutionResourcesManager::default();
+
+        let validate_info =
+            self.run_validate_entrypoint(&mut state, &mut resources_manager, general_config)?;
+        let actual_resources = calculate_tx_resources(
+            resources_manager,
+            &[validate_info.clone()],
+            self.tx_type,
+            state.count_actual_storage_changes(),
+            None,
+            &general_config.block_info.starknet_version,
+        )?;
+
+        Ok(
+            TransactionExecutionInfo::create_concurrent_stage_execution_info(
+                validate_info,
+                None,
+                actual_resources,
+                Some(self.tx_type),
+            ),
+        )
+    }
+
+    /// Returns the account debited the fee of the transaction, see `FeePayerPolicy`.
+    pub fn fee_payer(
+        &self,
+        general_config: &StarknetGeneralConfig,
+    ) -> Result<Address, TransactionError> {
+        general_config
+            .fee_payer_policy
+            .fee_payer(&self.contract_address, self.v3_fields.as_ref())
     }
 
     fn charge_fee<S>(
@@ -219,6 +399,7 @@ impl InternalInvokeFunction {
         state: &mut S,
         resources: &HashMap<String, usize>,
         general_config: &StarknetGeneralConfig,
+        is_reverted: bool,
     ) -> Result<FeeInfo, TransactionError>
     where
         S: State + StateReader,
@@ -229,33 +410,63 @@ impl InternalInvokeFunction {
 
         let actual_fee = calculate_tx_fee(
             resources,
-            general_config.starknet_os_config.gas_price,
+            general_config.block_info.gas_price,
             general_config,
         )?;
+        // A reverted transaction is still included, so it is charged at most its max fee
+        // instead of being rejected.
+        let actual_fee = if is_reverted {
+            actual_fee.min(self.max_fee)
+        } else {
+            actual_fee
+        };
 
         let tx_context = self.get_execution_context(general_config.invoke_tx_max_n_steps)?;
-        let fee_transfer_info =
-            execute_fee_transfer(state, general_config, &tx_context, actual_fee)?;
+        let fee_transfer_info = execute_fee_transfer(
+            state,
+            general_config,
+            &tx_context,
+            &self.fee_payer(general_config)?,
+            actual_fee,
+        )?;
 
         Ok((Some(fee_transfer_info), actual_fee))
     }
 
     /// Calculates actual fee used by the transaction using the execution info returned by apply(),
     /// then updates the transaction execution info with the data of the fee.
+    /// A rejected transaction (e.g. an invalid nonce, an account that cannot pay its max fee or a
+    /// failed validation) leaves the state untouched, while a reverted one still increments the
+    /// nonce and charges the fee.
     pub fn execute<S: State + StateReader>(
         &self,
         state: &mut S,
         general_config: &StarknetGeneralConfig,
     ) -> Result<TransactionExecutionInfo, TransactionError> {
-        let concurrent_exec_info = self.apply(state, general_config)?;
-        self.handle_nonce(state)?;
+        if let Some(v3_fields) = &self.v3_fields {
+            v3_fields.check_l1_gas_price(general_config.block_info.gas_price)?;
+        }
+
+        let mut tx_state = ChildState::new(state);
+
+        check_fee_bounds_and_balance(
+            &mut tx_state,
+            general_config,
+            &self.get_execution_context(general_config.invoke_tx_max_n_steps)?,
+            &self.fee_payer(general_config)?,
+            self.tx_type,
+        )?;
+        self.handle_nonce(&mut tx_state)?;
+        let concurrent_exec_info = self.apply(&mut tx_state, general_config)?;
 
         let (fee_transfer_info, actual_fee) = self.charge_fee(
-            state,
+            &mut tx_state,
             &concurrent_exec_info.actual_resources,
             general_config,
+            concurrent_exec_info.is_reverted(),
         )?;
 
+        tx_state.commit()?;
         Ok(
             TransactionExecutionInfo::from_concurrent_state_execution_info(
                 concurrent_exec_info,
@@ -275,10 +486,7 @@ impl InternalInvokeFunction {
         let current_nonce = state.get_nonce_at(contract_address)?;
 
         match &self.nonce {
-            None => {
-                // TODO: Remove this once we have a better way to handle the nonce.
-                Ok(())
-            }
+            None => Err(TransactionError::MissingNonce),
             Some(nonce) => {
                 if *nonce != current_nonce {
                     return Err(TransactionError::InvalidTransactionNonce(
@@ -344,8 +552,10 @@ mod tests {
         business_logic::{
             fact_state::in_memory_state_reader::InMemoryStateReader,
             state::cached_state::CachedState,
+            transaction::{error::RevertError, fee::estimate_minimal_fee},
         },
         services::api::contract_class::ContractClass,
+        utils::{calculate_sn_keccak, get_erc20_balance_var_addresses},
     };
     use coverage_helper::test;
     use num_traits::Num;
@@ -369,6 +579,7 @@ mod tests {
             signature: Vec::new(),
             max_fee: 0,
             nonce: Some(0.into()),
+            v3_fields: None,
         };
 
         // Instantiate CachedState
@@ -435,6 +646,7 @@ mod tests {
             signature: Vec::new(),
             max_fee: 0,
             nonce: Some(0.into()),
+            v3_fields: None,
         };
 
         // Instantiate CachedState
@@ -484,7 +696,7 @@ mod tests {
     }
 
     #[test]
-    fn test_apply_invoke_entrypoint_not_found_should_fail() {
+    fn test_apply_invoke_entrypoint_not_found_should_revert() {
         let internal_invoke_function = InternalInvokeFunction {
             contract_address: Address(0.into()),
             entry_point_selector: (*EXECUTE_ENTRY_POINT_SELECTOR).clone(),
@@ -497,6 +709,7 @@ mod tests {
             signature: Vec::new(),
             max_fee: 0,
             nonce: Some(0.into()),
+            v3_fields: None,
         };
 
         // Instantiate CachedState
@@ -525,13 +738,15 @@ mod tests {
             .set_contract_class(&class_hash, &contract_class)
             .unwrap();
 
-        let expected_error =
-            internal_invoke_function.apply(&mut state, &StarknetGeneralConfig::default());
+        let result = internal_invoke_function
+            .apply(&mut state, &StarknetGeneralConfig::default())
+            .unwrap();
 
-        assert!(expected_error.is_err());
+        assert!(result.is_reverted());
+        assert_eq!(result.call_info, None);
         assert_matches!(
-            expected_error.unwrap_err(),
-            TransactionError::EntryPointNotFound
+            result.revert_error.as_ref().map(RevertError::root_cause),
+            Some(TransactionError::EntryPointNotFound)
         );
     }
 
@@ -549,6 +764,7 @@ mod tests {
             signature: Vec::new(),
             max_fee: 0,
             nonce: None,
+            v3_fields: None,
         };
 
         // Instantiate CachedState
@@ -588,8 +804,7 @@ mod tests {
     }
 
     #[test]
-    // Test fee calculation is done correctly but payment to sequencer fails due to been WIP.
-    fn test_execute_invoke_fee_payment_to_sequencer_should_fail() {
+    fn test_execute_invoke_without_balance_should_fail() {
         let internal_invoke_function = InternalInvokeFunction {
             contract_address: Address(0.into()),
             entry_point_selector: Felt252::from_str_radix(
@@ -606,6 +821,7 @@ mod tests {
             signature: Vec::new(),
             max_fee: 1000,
             nonce: Some(0.into()),
+            v3_fields: None,
         };
 
         // Instantiate CachedState
@@ -642,13 +858,19 @@ mod tests {
         ]);
 
         let expected_error = internal_invoke_function.execute(&mut state, &config);
-        let error_msg = "Fee transfer failure".to_string();
-        assert!(expected_error.is_err());
-        assert_matches!(expected_error.unwrap_err(), TransactionError::FeeError(msg) if msg == error_msg);
+        assert_matches!(
+            expected_error.unwrap_err(),
+            TransactionError::InsufficientBalance(balance, 1000) if balance == 0_u32.into()
+        );
+        // The transaction is rejected before running, so the nonce is left untouched
+        assert_eq!(
+            state.get_nonce_at(&Address(0.into())).unwrap(),
+            Felt252::zero()
+        );
     }
 
     #[test]
-    fn test_execute_invoke_actual_fee_exceeded_max_fee_should_fail() {
+    fn test_execute_invoke_max_fee_below_minimal_fee_should_fail() {
         let internal_invoke_function = InternalInvokeFunction {
             contract_address: Address(0.into()),
             entry_point_selector: Felt252::from_str_radix(
@@ -665,6 +887,7 @@ mod tests {
             signature: Vec::new(),
             max_fee: 1000,
             nonce: Some(0.into()),
+            v3_fields: None,
         };
 
         // Instantiate CachedState
@@ -699,7 +922,81 @@ mod tests {
             (String::from("pedersen_builtin"), 16.into()),
             (String::from("range_check_builtin"), 70.into()),
         ]);
-        config.starknet_os_config.gas_price = 1;
+        config.block_info.gas_price = 1;
+
+        let minimal_fee = estimate_minimal_fee(&config, TransactionType::InvokeFunction).unwrap();
+        assert!(minimal_fee > 1000);
+
+        let expected_error = internal_invoke_function.execute(&mut state, &config);
+        assert_matches!(
+            expected_error.unwrap_err(),
+            TransactionError::MaxFeeTooLow(1000, fee) if fee == minimal_fee
+        );
+    }
+
+    #[test]
+    fn test_execute_invoke_actual_fee_exceeded_max_fee_should_fail() {
+        let mut config = StarknetGeneralConfig::default();
+        config.cairo_resource_fee_weights = HashMap::from([
+            (String::from("l1_gas_usage"), 0.into()),
+            (String::from("pedersen_builtin"), 16.into()),
+            (String::from("range_check_builtin"), 70.into()),
+        ]);
+        config.block_info.gas_price = 1;
+
+        // The max fee passes the pre-execution check, but the execution writes to storage, which
+        // the minimal fee doesn't account for.
+        let max_fee = estimate_minimal_fee(&config, TransactionType::InvokeFunction).unwrap();
+        let internal_invoke_function = InternalInvokeFunction {
+            contract_address: Address(0.into()),
+            entry_point_selector: Felt252::from_bytes_be(&calculate_sn_keccak(b"write_and_read")),
+            entry_point_type: EntryPointType::External,
+            calldata: Vec::new(),
+            tx_type: TransactionType::InvokeFunction,
+            version: 1,
+            validate_entry_point_selector: 0.into(),
+            hash_value: 0.into(),
+            signature: Vec::new(),
+            max_fee,
+            nonce: Some(0.into()),
+            v3_fields: None,
+        };
+
+        // Instantiate CachedState
+        let mut state_reader = InMemoryStateReader::default();
+        // Set contract_class
+        let class_hash = [1; 32];
+        let contract_class =
+            ContractClass::try_from(PathBuf::from("starknet_programs/storage.json")).unwrap();
+        // Set contact_state
+        let contract_address = Address(0.into());
+        let nonce = Felt252::zero();
+
+        state_reader
+            .address_to_class_hash_mut()
+            .insert(contract_address.clone(), class_hash);
+        state_reader
+            .address_to_nonce
+            .insert(contract_address.clone(), nonce);
+
+        let mut state = CachedState::new(state_reader.clone(), None);
+
+        // Initialize state.contract_classes
+        state.set_contract_classes(HashMap::new()).unwrap();
+
+        state
+            .set_contract_class(&class_hash, &contract_class)
+            .unwrap();
+
+        // Fund the account with its max fee
+        let (balance_key, _) = get_erc20_balance_var_addresses(&contract_address).unwrap();
+        state.set_storage_at(
+            &(
+                config.starknet_os_config.fee_token_address.clone(),
+                balance_key,
+            ),
+            max_fee.into(),
+        );
 
         let expected_error = internal_invoke_function.execute(&mut state, &config);
         let error_msg = "Actual fee exceeded max fee.".to_string();
@@ -725,6 +1022,7 @@ mod tests {
             signature: Vec::new(),
             max_fee: 0,
             nonce: Some(0.into()),
+            v3_fields: None,
         };
 
         // Instantiate CachedState
@@ -785,6 +1083,7 @@ mod tests {
             signature: Vec::new(),
             max_fee: 0,
             nonce: None,
+            v3_fields: None,
         };
 
         // Instantiate CachedState
@@ -817,10 +1116,7 @@ mod tests {
             internal_invoke_function.execute(&mut state, &StarknetGeneralConfig::default());
 
         assert!(expected_error.is_err());
-        assert_matches!(
-            expected_error.unwrap_err(),
-            TransactionError::InvalidTxContext
-        )
+        assert_matches!(expected_error.unwrap_err(), TransactionError::MissingNonce)
     }
 
     #[test]
@@ -896,4 +1192,28 @@ mod tests {
             TransactionError::InvokeFunctionNonZeroMissingNonce
         )
     }
+
+    #[test]
+    fn validate_unsupported_version_should_fail() {
+        let internal_invoke_function = InternalInvokeFunction {
+            contract_address: Address(0.into()),
+            entry_point_selector: (*EXECUTE_ENTRY_POINT_SELECTOR).clone(),
+            entry_point_type: EntryPointType::External,
+            calldata: Vec::new(),
+            tx_type: TransactionType::InvokeFunction,
+            version: 2,
+            validate_entry_point_selector: 0.into(),
+            hash_value: 0.into(),
+            signature: Vec::new(),
+            max_fee: 0,
+            nonce: Some(0.into()),
+            v3_fields: None,
+        };
+        let mut state = CachedState::new(InMemoryStateReader::default(), None);
+
+        let result =
+            internal_invoke_function.validate(&mut state, &StarknetGeneralConfig::default());
+
+        assert_matches!(result, Err(TransactionError::StarknetError(_)));
+    }
 }
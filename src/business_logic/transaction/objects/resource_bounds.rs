@@ -0,0 +1,148 @@
+use crate::business_logic::transaction::error::TransactionError;
+use cairo_vm::felt::Felt252;
+use num_traits::Pow;
+
+/// Layer in which the data of a transaction's nonce or fee updates is published.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DataAvailabilityMode {
+    #[default]
+    L1,
+    L2,
+}
+
+impl DataAvailabilityMode {
+    pub fn to_felt(self) -> Felt252 {
+        match self {
+            DataAvailabilityMode::L1 => Felt252::from(0),
+            DataAvailabilityMode::L2 => Felt252::from(1),
+        }
+    }
+}
+
+/// Maximum amount of a resource a transaction may consume, and the maximum price it is willing
+/// to pay for each unit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceBounds {
+    pub max_amount: u64,
+    pub max_price_per_unit: u128,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceBoundsMapping {
+    pub l1_gas: ResourceBounds,
+    pub l2_gas: ResourceBounds,
+}
+
+/// Fields introduced by version 3 transactions, which replace `max_fee` and are paid in STRK.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct V3TransactionFields {
+    pub resource_bounds: ResourceBoundsMapping,
+    /// Part of the transaction hash only: like on Starknet, where tips are not enabled yet, it is
+    /// neither charged nor checked against the balance of the fee payer.
+    pub tip: u64,
+    pub paymaster_data: Vec<Felt252>,
+    pub nonce_data_availability_mode: DataAvailabilityMode,
+    pub fee_data_availability_mode: DataAvailabilityMode,
+    /// Only used by invoke and declare transactions.
+    pub account_deployment_data: Vec<Felt252>,
+}
+
+impl V3TransactionFields {
+    /// Returns the highest fee the transaction may be charged, i.e. its L1 gas bounds.
+    /// Saturates at `u128::MAX`.
+    pub fn max_fee(&self) -> u128 {
+        let l1_gas = &self.resource_bounds.l1_gas;
+        (l1_gas.max_amount as u128).saturating_mul(l1_gas.max_price_per_unit)
+    }
+
+    /// Fails if the L1 gas price is above the price the transaction is willing to pay.
+    pub fn check_l1_gas_price(&self, gas_price: u128) -> Result<(), TransactionError> {
+        let max_price_per_unit = self.resource_bounds.l1_gas.max_price_per_unit;
+        if gas_price > max_price_per_unit {
+            return Err(TransactionError::MaxL1GasPriceTooLow(
+                max_price_per_unit,
+                gas_price,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the elements hashed into the `tip` and resource bounds part of the transaction
+    /// hash. Each bound is packed as `resource_name << 192 | max_amount << 128 | max_price`.
+    pub(crate) fn fee_fields(&self) -> Vec<Felt252> {
+        vec![
+            Felt252::from(self.tip),
+            pack_resource_bounds(b"L1_GAS", &self.resource_bounds.l1_gas),
+            pack_resource_bounds(b"L2_GAS", &self.resource_bounds.l2_gas),
+        ]
+    }
+
+    /// Returns both data availability modes packed as `nonce_mode << 32 | fee_mode`.
+    pub(crate) fn data_availability_modes(&self) -> Felt252 {
+        self.nonce_data_availability_mode.to_felt() * Felt252::new(2).pow(32_u32)
+            + self.fee_data_availability_mode.to_felt()
+    }
+}
+
+fn pack_resource_bounds(resource_name: &[u8], bounds: &ResourceBounds) -> Felt252 {
+    Felt252::from_bytes_be(resource_name) * Felt252::new(2).pow(192_u32)
+        + Felt252::from(bounds.max_amount) * Felt252::new(2).pow(128_u32)
+        + Felt252::from(bounds.max_price_per_unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+
+    #[test]
+    fn max_fee_is_bounded_by_l1_gas() {
+        let fields = V3TransactionFields {
+            resource_bounds: ResourceBoundsMapping {
+                l1_gas: ResourceBounds {
+                    max_amount: 10,
+                    max_price_per_unit: 7,
+                },
+                l2_gas: ResourceBounds {
+                    max_amount: 1000,
+                    max_price_per_unit: 1000,
+                },
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(fields.max_fee(), 70);
+        assert!(fields.check_l1_gas_price(7).is_ok());
+        assert_matches!(
+            fields.check_l1_gas_price(8),
+            Err(TransactionError::MaxL1GasPriceTooLow(7, 8))
+        );
+    }
+
+    #[test]
+    fn max_fee_saturates() {
+        let fields = V3TransactionFields {
+            resource_bounds: ResourceBoundsMapping {
+                l1_gas: ResourceBounds {
+                    max_amount: u64::MAX,
+                    max_price_per_unit: u128::MAX,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(fields.max_fee(), u128::MAX);
+    }
+
+    #[test]
+    fn data_availability_modes_are_packed() {
+        let fields = V3TransactionFields {
+            nonce_data_availability_mode: DataAvailabilityMode::L2,
+            fee_data_availability_mode: DataAvailabilityMode::L1,
+            ..Default::default()
+        };
+
+        assert_eq!(fields.data_availability_modes(), Felt252::from(1_u64 << 32));
+    }
+}
@@ -1,3 +1,5 @@
+use cairo_vm::felt::Felt252;
+
 use crate::{
     business_logic::{
         execution::objects::TransactionExecutionInfo,
@@ -9,36 +11,103 @@ use crate::{
 
 use super::{
     error::TransactionError,
-    objects::{internal_deploy::InternalDeploy, internal_invoke_function::InternalInvokeFunction},
+    objects::{
+        internal_declare::InternalDeclare, internal_deploy::InternalDeploy,
+        internal_deploy_account::InternalDeployAccount,
+        internal_invoke_function::InternalInvokeFunction, resource_bounds::V3TransactionFields,
+    },
 };
 
 pub enum Transaction {
+    Declare(InternalDeclare),
     Deploy(InternalDeploy),
+    DeployAccount(InternalDeployAccount),
     InvokeFunction(InternalInvokeFunction),
 }
 
 impl Transaction {
     pub fn contract_hash(&self) -> ClassHash {
         match self {
+            Transaction::Declare(tx) => tx.class_hash,
             Transaction::Deploy(tx) => tx.contract_hash,
+            Transaction::DeployAccount(tx) => *tx.class_hash(),
             _ => [0; 32],
         }
     }
 
     pub fn contract_address(&self) -> Address {
         match self {
+            Transaction::Declare(tx) => tx.sender_address.clone(),
             Transaction::Deploy(tx) => tx.contract_address.clone(),
+            Transaction::DeployAccount(tx) => tx.contract_address().clone(),
             Transaction::InvokeFunction(tx) => tx.contract_address().clone(),
         }
     }
 
+    pub fn hash_value(&self) -> Felt252 {
+        match self {
+            Transaction::Declare(tx) => tx.hash_value.clone(),
+            Transaction::Deploy(tx) => tx.hash_value.clone(),
+            Transaction::DeployAccount(tx) => tx.hash_value().clone(),
+            Transaction::InvokeFunction(tx) => tx.hash_value().clone(),
+        }
+    }
+
+    /// Returns the nonce of the sender, or `None` for transactions that don't have one,
+    /// i.e. deploy and version 0 invoke transactions.
+    pub fn nonce(&self) -> Option<Felt252> {
+        match self {
+            Transaction::Declare(tx) => Some(tx.nonce.clone()),
+            Transaction::Deploy(_) => None,
+            Transaction::DeployAccount(tx) => Some(tx.nonce().clone()),
+            Transaction::InvokeFunction(tx) => tx.nonce().clone(),
+        }
+    }
+
+    pub fn max_fee(&self) -> u128 {
+        match self {
+            Transaction::Declare(tx) => tx.max_fee,
+            Transaction::Deploy(_) => 0,
+            Transaction::DeployAccount(tx) => *tx.max_fee(),
+            Transaction::InvokeFunction(tx) => *tx.max_fee(),
+        }
+    }
+
+    /// Returns the fields of version 3 transactions, which are priced by their resource bounds.
+    pub fn v3_fields(&self) -> Option<&V3TransactionFields> {
+        match self {
+            Transaction::Declare(tx) => tx.v3_fields.as_ref(),
+            Transaction::Deploy(_) => None,
+            Transaction::DeployAccount(tx) => tx.v3_fields().as_ref(),
+            Transaction::InvokeFunction(tx) => tx.v3_fields().as_ref(),
+        }
+    }
+
+    /// Runs only the account validation of the transaction over `state`, discarding its writes,
+    /// see the `validate` method of each transaction type. Deploy transactions have no account,
+    /// so there is nothing to validate.
+    pub fn validate<S: State + StateReader>(
+        &self,
+        state: &mut S,
+        general_config: &StarknetGeneralConfig,
+    ) -> Result<TransactionExecutionInfo, TransactionError> {
+        match self {
+            Transaction::Declare(tx) => tx.validate(state, general_config),
+            Transaction::Deploy(_) => Ok(TransactionExecutionInfo::default()),
+            Transaction::DeployAccount(tx) => tx.validate(state, general_config),
+            Transaction::InvokeFunction(tx) => tx.validate(state, general_config),
+        }
+    }
+
     pub fn execute<S: Clone + State + StateReader>(
         &self,
         state: &mut S,
         general_config: &StarknetGeneralConfig,
     ) -> Result<TransactionExecutionInfo, TransactionError> {
         match self {
+            Transaction::Declare(tx) => tx.execute(state, general_config),
             Transaction::Deploy(tx) => tx.execute(state, general_config),
+            Transaction::DeployAccount(tx) => tx.execute(state, general_config),
             Transaction::InvokeFunction(tx) => tx.execute(state, general_config),
         }
     }
@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AbiError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Invalid ABI: {0}")]
+    InvalidAbi(#[from] serde_json::Error),
+    #[error("Function `{0}` is not in the ABI")]
+    FunctionNotFound(String),
+    #[error("Type `{0}` is not in the ABI")]
+    UnknownType(String),
+    #[error("Invalid type `{0}`")]
+    InvalidType(String),
+    #[error("Array `{0}` has no length parameter before it")]
+    ArrayWithoutLength(String),
+    #[error("Expected {expected} arguments, got {got}")]
+    ArgumentCount { expected: usize, got: usize },
+    #[error("Invalid value for type `{0}`: {1}")]
+    InvalidValue(String, String),
+    #[error("The return data is shorter than the outputs of the function")]
+    MissingRetdata,
+    #[error("The return data has {0} values more than the outputs of the function")]
+    ExtraRetdata(usize),
+}
@@ -1,3 +1,4 @@
+pub mod abi_errors;
 pub mod contract_address_errors;
 pub mod state_errors;
 pub mod syscall_handler_errors;
@@ -1,4 +1,5 @@
 use super::state_errors::StateError;
+use crate::{business_logic::transaction::error::TransactionError, utils::Address};
 use cairo_vm::{
     types::errors::math_errors::MathError,
     vm::errors::{
@@ -11,14 +12,21 @@ use thiserror::Error;
 pub enum SyscallHandlerError {
     #[error("Unknown syscall: {0}")]
     UnknownSyscall(String),
-    #[error("Couldn't execute syscall: {0}")]
-    ExecutionError(String),
+    #[error("Call to contract {} failed: {source}", .contract_address.0)]
+    NestedCall {
+        contract_address: Address,
+        source: Box<TransactionError>,
+    },
     #[error("Couldn't convert Felt to usize")]
     FeltToUsizeFail,
     #[error("Couldn't convert Felt to u64")]
     FeltToU64Fail,
+    #[error("Couldn't convert Felt to u128")]
+    FeltToU128Fail,
     #[error("Couldn't compute hash")]
     FailToComputeHash,
+    #[error("Declare transactions of version 2 must have a compiled class hash")]
+    MissingCompiledClassHash,
     #[error("Expected DesployRequestStruct")]
     ExpectedDeployRequestStruct,
     #[error("Expected GetCallerAddressRequest")]
@@ -43,6 +51,14 @@ pub enum SyscallHandlerError {
     ListIsEmpty,
     #[error("{0} should be None")]
     ShouldBeNone(String),
+    #[error("Event has {0} keys, exceeding the limit of {1}")]
+    EventKeysLimitExceeded(usize, usize),
+    #[error("Event has {0} data elements, exceeding the limit of {1}")]
+    EventDataLimitExceeded(usize, usize),
+    #[error("Exceeded the limit of {0} events per call")]
+    EventsPerCallLimitExceeded(usize),
+    #[error("L2-to-L1 message payload has {0} elements, exceeding the limit of {1}")]
+    L2ToL1PayloadLimitExceeded(usize, usize),
     #[error("Unexpected construct retdata")]
     UnexpectedConstructorRetdata,
     #[error("Key not found")]
@@ -1,5 +1,6 @@
 use super::{
     syscall_handler::{SyscallHandler, SyscallHandlerPostRun},
+    syscall_hooks::SyscallHooks,
     syscall_info::get_syscall_size_from_name,
     syscall_request::*,
 };
@@ -58,9 +59,13 @@ impl<'a, T: State + StateReader> BusinessLogicSyscallHandler<'a, T> {
         resources_manager: ExecutionResourcesManager,
         caller_address: Address,
         contract_address: Address,
-        general_config: StarknetGeneralConfig,
+        mut general_config: StarknetGeneralConfig,
         syscall_ptr: Relocatable,
     ) -> Self {
+        if let Some(syscall_hooks) = general_config.syscall_hooks.clone() {
+            syscall_hooks.apply_block_info(&mut general_config.block_info);
+        }
+
         let events = Vec::new();
         let read_only_segments = Vec::new();
         let l2_to_l1_messages = Vec::new();
@@ -93,6 +98,11 @@ impl<'a, T: State + StateReader> BusinessLogicSyscallHandler<'a, T> {
         )
     }
 
+    /// Runs `f` over the syscall hooks of the current execution, if any.
+    fn with_syscall_hooks<R>(&self, f: impl FnOnce(&dyn SyscallHooks) -> R) -> Option<R> {
+        self.general_config.syscall_hooks.as_deref().map(f)
+    }
+
     /// Increments the syscall count for a given `syscall_name` by 1.
     fn increment_syscall_count(&mut self, syscall_name: &str) {
         self.resources_manager
@@ -175,7 +185,7 @@ impl<'a, T: State + StateReader> BusinessLogicSyscallHandler<'a, T> {
         contract_address: &Address,
         class_hash_bytes: ClassHash,
         constructor_calldata: Vec<Felt252>,
-    ) -> Result<(), StateError> {
+    ) -> Result<(), SyscallHandlerError> {
         let contract_class = self
             .starknet_storage_state
             .state
@@ -183,10 +193,12 @@ impl<'a, T: State + StateReader> BusinessLogicSyscallHandler<'a, T> {
         let constructor_entry_points = contract_class
             .entry_points_by_type()
             .get(&EntryPointType::Constructor)
-            .ok_or(ContractClassError::NoneEntryPointType)?;
+            .ok_or(StateError::ContractClass(
+                ContractClassError::NoneEntryPointType,
+            ))?;
         if constructor_entry_points.is_empty() {
             if !constructor_calldata.is_empty() {
-                return Err(StateError::ConstructorCalldataEmpty());
+                return Err(StateError::ConstructorCalldataEmpty().into());
             }
 
             let call_info = CallInfo::empty_constructor_call(
@@ -209,16 +221,28 @@ impl<'a, T: State + StateReader> BusinessLogicSyscallHandler<'a, T> {
             None,
         );
 
-        let _call_info = call
-            .execute(
-                self.starknet_storage_state.state,
-                &self.general_config,
-                &mut self.resources_manager,
-                &self.tx_execution_context,
-            )
-            .map_err(|_| StateError::ExecutionEntryPoint())?;
+        self.execute_nested_call(contract_address, call)?;
         Ok(())
     }
+
+    /// Executes a nested call to `contract_address`. Its state changes are only applied if it
+    /// succeeds; otherwise, the error that made it fail is kept as the source of the returned one.
+    fn execute_nested_call(
+        &mut self,
+        contract_address: &Address,
+        call: ExecutionEntryPoint,
+    ) -> Result<CallInfo, SyscallHandlerError> {
+        call.execute(
+            self.starknet_storage_state.state,
+            &self.general_config,
+            &mut self.resources_manager,
+            &self.tx_execution_context,
+        )
+        .map_err(|error| SyscallHandlerError::NestedCall {
+            contract_address: contract_address.clone(),
+            source: Box::new(error),
+        })
+    }
 }
 
 impl<'a, T> Borrow<T> for BusinessLogicSyscallHandler<'a, T>
@@ -255,9 +279,30 @@ where
 
         let keys_len = request.keys_len;
         let data_len = request.data_len;
+        if keys_len > self.general_config.event_keys_max_len {
+            return Err(SyscallHandlerError::EventKeysLimitExceeded(
+                keys_len,
+                self.general_config.event_keys_max_len,
+            ));
+        }
+        if data_len > self.general_config.event_data_max_len {
+            return Err(SyscallHandlerError::EventDataLimitExceeded(
+                data_len,
+                self.general_config.event_data_max_len,
+            ));
+        }
+        if self.events.len() >= self.general_config.max_n_events_per_call {
+            return Err(SyscallHandlerError::EventsPerCallLimitExceeded(
+                self.general_config.max_n_events_per_call,
+            ));
+        }
+
         let order = self.tx_execution_context.n_emitted_events;
         let keys: Vec<Felt252> = get_integer_range(vm, request.keys, keys_len)?;
         let data: Vec<Felt252> = get_integer_range(vm, request.data, data_len)?;
+        self.with_syscall_hooks(|syscall_hooks| {
+            syscall_hooks.on_event(&self.contract_address, &keys, &data)
+        });
         self.events.push(OrderedEvent::new(order, keys, data));
 
         // Update events count.
@@ -396,8 +441,34 @@ where
             }
         }
 
+        if call_type == CallType::Call {
+            if let Some(retdata) = self
+                .with_syscall_hooks(|syscall_hooks| {
+                    syscall_hooks.mocked_call(&contract_address, &function_selector)
+                })
+                .flatten()
+            {
+                // The callee is not executed, but the call is still recorded so that the
+                // internal calls keep matching the calls the contract made.
+                self.internal_calls.push(CallInfo {
+                    calldata: call_data,
+                    retdata: retdata.clone(),
+                    ..CallInfo::empty(
+                        contract_address,
+                        caller_address,
+                        None,
+                        Some(call_type),
+                        Some(entry_point_type),
+                        Some(function_selector),
+                        None,
+                    )
+                });
+                return Ok(retdata);
+            }
+        }
+
         let entry_point = ExecutionEntryPoint::new(
-            contract_address,
+            contract_address.clone(),
             call_data,
             function_selector,
             caller_address,
@@ -406,20 +477,11 @@ where
             class_hash,
         );
 
-        entry_point
-            .execute(
-                self.starknet_storage_state.state,
-                &self.general_config,
-                &mut self.resources_manager,
-                &self.tx_execution_context,
-            )
-            .map(|x| {
-                let retdata = x.retdata.clone();
-                self.internal_calls.push(x);
+        let call_info = self.execute_nested_call(&contract_address, entry_point)?;
+        let retdata = call_info.retdata.clone();
+        self.internal_calls.push(call_info);
 
-                retdata
-            })
-            .map_err(|e| SyscallHandlerError::ExecutionError(e.to_string()))
+        Ok(retdata)
     }
 
     fn get_block_info(&self) -> &BlockInfo {
@@ -465,6 +527,13 @@ where
             return Err(SyscallHandlerError::ExpectedSendMessageToL1);
         };
 
+        if request.payload_size > self.general_config.l2_to_l1_payload_max_len {
+            return Err(SyscallHandlerError::L2ToL1PayloadLimitExceeded(
+                request.payload_size,
+                self.general_config.l2_to_l1_payload_max_len,
+            ));
+        }
+
         let payload = get_integer_range(vm, request.payload_ptr, request.payload_size)?;
 
         self.l2_to_l1_messages.push(OrderedL2ToL1Message::new(
@@ -485,7 +554,8 @@ where
         if let Some(ptr) = &self.tx_info_ptr {
             return Ok(ptr.try_into()?);
         }
-        let tx = self.tx_execution_context.clone();
+        let mut tx = self.tx_execution_context.clone();
+        self.with_syscall_hooks(|syscall_hooks| syscall_hooks.apply_tx_info(&mut tx));
 
         let signature_data: Vec<MaybeRelocatable> =
             tx.signature.iter().map(|num| num.into()).collect();
@@ -709,6 +779,93 @@ mod tests {
         )
     }
 
+    #[test]
+    fn emit_event_above_limits_should_fail() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall = BusinessLogicSyscallHandler::default_with(&mut state);
+        *syscall.general_config.event_keys_max_len_mut() = 1;
+        let mut vm = vm!();
+
+        add_segments!(vm, 3);
+        memory_insert!(
+            vm,
+            [
+                ((1, 0), 0),
+                ((1, 1), 2),
+                ((1, 2), (2, 0)),
+                ((1, 3), 0),
+                ((1, 4), (2, 0)),
+                ((2, 0), 1),
+                ((2, 1), 2)
+            ]
+        );
+
+        assert_matches!(
+            syscall.emit_event(&vm, relocatable!(1, 0)),
+            Err(SyscallHandlerError::EventKeysLimitExceeded(2, 1))
+        );
+        assert!(syscall.events.is_empty());
+    }
+
+    #[test]
+    fn emit_event_above_events_per_call_limit_should_fail() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall = BusinessLogicSyscallHandler::default_with(&mut state);
+        *syscall.general_config.max_n_events_per_call_mut() = 1;
+        let mut vm = vm!();
+
+        add_segments!(vm, 3);
+        memory_insert!(
+            vm,
+            [
+                ((1, 0), 0),
+                ((1, 1), 0),
+                ((1, 2), (2, 0)),
+                ((1, 3), 0),
+                ((1, 4), (2, 0)),
+                ((1, 5), 0),
+                ((1, 6), 0),
+                ((1, 7), (2, 0)),
+                ((1, 8), 0),
+                ((1, 9), (2, 0))
+            ]
+        );
+
+        assert_matches!(syscall.emit_event(&vm, relocatable!(1, 0)), Ok(()));
+        assert_matches!(
+            syscall.emit_event(&vm, relocatable!(1, 5)),
+            Err(SyscallHandlerError::EventsPerCallLimitExceeded(1))
+        );
+    }
+
+    #[test]
+    fn send_message_to_l1_above_payload_limit_should_fail() {
+        let mut state = CachedState::<InMemoryStateReader>::default();
+        let mut syscall = BusinessLogicSyscallHandler::default_with(&mut state);
+        *syscall.general_config.l2_to_l1_payload_max_len_mut() = 2;
+        let mut vm = vm!();
+
+        add_segments!(vm, 3);
+        memory_insert!(
+            vm,
+            [
+                ((1, 0), 0),
+                ((1, 1), 1),
+                ((1, 2), 3),
+                ((1, 3), (2, 0)),
+                ((2, 0), 1),
+                ((2, 1), 2),
+                ((2, 2), 3)
+            ]
+        );
+
+        assert_matches!(
+            syscall.send_message_to_l1(&vm, relocatable!(1, 0)),
+            Err(SyscallHandlerError::L2ToL1PayloadLimitExceeded(3, 2))
+        );
+        assert!(syscall.l2_to_l1_messages.is_empty());
+    }
+
     #[test]
     fn test_storage_read_empty() {
         let mut state = CachedState::<InMemoryStateReader>::default();
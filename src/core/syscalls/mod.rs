@@ -2,6 +2,7 @@ pub mod business_logic_syscall_handler;
 pub mod hint_code;
 pub mod other_syscalls;
 pub mod syscall_handler;
+pub mod syscall_hooks;
 pub mod syscall_info;
 pub mod syscall_request;
 pub mod syscall_response;
@@ -369,6 +369,9 @@ pub(crate) trait SyscallHandlerPostRun {
 pub(crate) struct SyscallHintProcessor<H: SyscallHandler> {
     pub(crate) builtin_hint_processor: BuiltinHintProcessor,
     pub(crate) syscall_handler: H,
+    /// The error of the syscall that aborted the run, if any. The VM only keeps its message,
+    /// so it is stored here to be surfaced with its structure once the run fails.
+    pub(crate) syscall_error: Option<SyscallHandlerError>,
 }
 
 impl<H> SyscallHintProcessor<H>
@@ -379,6 +382,7 @@ where
         SyscallHintProcessor {
             builtin_hint_processor: BuiltinHintProcessor::new_empty(),
             syscall_handler,
+            syscall_error: None,
         }
     }
 
@@ -494,7 +498,11 @@ impl<H: SyscallHandler> HintProcessor for SyscallHintProcessor<H> {
                         HintError::UnknownHint(hint_code)
                     }
 
-                    e => HintError::CustomHint(e.to_string()),
+                    e => {
+                        let message = e.to_string();
+                        self.syscall_error = Some(e);
+                        HintError::CustomHint(message)
+                    }
                 })?;
         }
         Ok(())
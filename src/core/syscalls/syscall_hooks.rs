@@ -0,0 +1,39 @@
+use crate::{
+    business_logic::{
+        execution::objects::TransactionExecutionContext, state::state_api_objects::BlockInfo,
+    },
+    utils::Address,
+};
+use cairo_vm::felt::Felt252;
+use std::{fmt::Debug, sync::Arc};
+
+/// Hooks shared between every syscall handler of an execution, so that they also apply to
+/// nested calls.
+pub type SharedSyscallHooks = Arc<dyn SyscallHooks>;
+
+/// Lets the embedder override what the syscall handler reports to contracts, e.g. the cheatcodes
+/// of `testing::cheatcodes`. Every hook defaults to leaving the execution untouched.
+pub trait SyscallHooks: Debug + Send + Sync {
+    /// Returns the caller address to report to a user call to `contract_address`, instead of
+    /// the actual one. Called once per targeted call, so the override may be consumed.
+    fn take_caller_address(&self, _contract_address: &Address) -> Option<Address> {
+        None
+    }
+
+    fn apply_block_info(&self, _block_info: &mut BlockInfo) {}
+
+    fn apply_tx_info(&self, _tx_execution_context: &mut TransactionExecutionContext) {}
+
+    /// Returns the return data of a `call_contract` to `contract_address` with `selector` that
+    /// must not be executed.
+    fn mocked_call(
+        &self,
+        _contract_address: &Address,
+        _selector: &Felt252,
+    ) -> Option<Vec<Felt252>> {
+        None
+    }
+
+    /// Called for every event emitted by a contract.
+    fn on_event(&self, _from_address: &Address, _keys: &[Felt252], _data: &[Felt252]) {}
+}
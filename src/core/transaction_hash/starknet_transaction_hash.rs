@@ -1,15 +1,29 @@
 use crate::{
+    business_logic::transaction::objects::resource_bounds::V3TransactionFields,
     core::{
         contract_address::starknet_contract_address::compute_class_hash,
         errors::syscall_handler_errors::SyscallHandlerError,
     },
     definitions::constants::CONSTRUCTOR_ENTRY_POINT_SELECTOR,
-    hash_utils::compute_hash_on_elements,
+    hash_utils::{compute_hash_on_elements, compute_poseidon_hash_on_elements},
     services::api::contract_class::ContractClass,
     utils::Address,
 };
 use cairo_vm::felt::{felt_str, Felt252};
-use num_traits::Zero;
+use lazy_static::lazy_static;
+use num_traits::{One, ToPrimitive, Zero};
+
+lazy_static! {
+    /// Added to the version of transactions sent only to be estimated or simulated, so that
+    /// their signature can't be used to submit them.
+    pub static ref QUERY_VERSION_BASE: Felt252 = Felt252::from(u128::MAX) + Felt252::one();
+}
+
+/// Returns the version hashed by a transaction of the given version sent only to be estimated
+/// or simulated.
+pub fn query_version(version: u64) -> Felt252 {
+    QUERY_VERSION_BASE.clone() + Felt252::from(version)
+}
 
 #[derive(Debug)]
 pub enum TransactionHashPrefix {
@@ -52,11 +66,11 @@ impl TransactionHashPrefix {
 #[allow(clippy::too_many_arguments)]
 pub fn calculate_transaction_hash_common(
     tx_hash_prefix: TransactionHashPrefix,
-    version: u64,
+    version: Felt252,
     contract_address: &Address,
     entry_point_selector: Felt252,
     calldata: &[Felt252],
-    max_fee: u64,
+    max_fee: u128,
     chain_id: Felt252,
     additional_data: &[Felt252],
 ) -> Result<Felt252, SyscallHandlerError> {
@@ -64,7 +78,7 @@ pub fn calculate_transaction_hash_common(
 
     let mut data_to_hash: Vec<Felt252> = vec![
         tx_hash_prefix.get_prefix(),
-        version.into(),
+        version,
         contract_address.0.clone(),
         entry_point_selector,
         calldata_hash,
@@ -85,7 +99,7 @@ pub fn calculate_deploy_transaction_hash(
 ) -> Result<Felt252, SyscallHandlerError> {
     calculate_transaction_hash_common(
         TransactionHashPrefix::Deploy,
-        version,
+        version.into(),
         contract_address,
         CONSTRUCTOR_ENTRY_POINT_SELECTOR.clone(),
         constructor_calldata,
@@ -97,11 +111,11 @@ pub fn calculate_deploy_transaction_hash(
 
 #[allow(clippy::too_many_arguments)]
 pub fn calculate_deploy_account_transaction_hash(
-    version: u64,
+    version: Felt252,
     contract_address: &Address,
     class_hash: Felt252,
     constructor_calldata: &[Felt252],
-    max_fee: u64,
+    max_fee: u128,
     nonce: Felt252,
     salt: Felt252,
     chain_id: Felt252,
@@ -121,21 +135,35 @@ pub fn calculate_deploy_account_transaction_hash(
     )
 }
 
+/// Calculates the hash of a declare transaction. Version 0 hashes the class hash as additional
+/// data, while later versions hash it as calldata along with the nonce, and version 2 also
+/// commits to the `compiled_class_hash`. The version may be a query version, see
+/// `query_version`.
 pub fn calculate_declare_transaction_hash(
     contract_class: &ContractClass,
+    compiled_class_hash: Option<&Felt252>,
     chain_id: Felt252,
     sender_address: &Address,
-    max_fee: u64,
-    version: u64,
+    max_fee: u128,
+    version: Felt252,
     nonce: Felt252,
 ) -> Result<Felt252, SyscallHandlerError> {
     let class_hash =
         compute_class_hash(contract_class).map_err(|_| SyscallHandlerError::FailToComputeHash)?;
 
-    let (calldata, additional_data) = if version > 0 {
-        (Vec::new(), vec![class_hash])
+    let base_version = if version >= *QUERY_VERSION_BASE {
+        version.clone() - QUERY_VERSION_BASE.clone()
     } else {
-        (vec![class_hash], vec![nonce])
+        version.clone()
+    };
+    let (calldata, additional_data) = match base_version.to_u64() {
+        Some(0) => (Vec::new(), vec![class_hash]),
+        Some(1) => (vec![class_hash], vec![nonce]),
+        _ => {
+            let compiled_class_hash =
+                compiled_class_hash.ok_or(SyscallHandlerError::MissingCompiledClassHash)?;
+            (vec![class_hash], vec![nonce, compiled_class_hash.clone()])
+        }
     };
 
     calculate_transaction_hash_common(
@@ -150,6 +178,104 @@ pub fn calculate_declare_transaction_hash(
     )
 }
 
+/// Returns the leading elements of a version 3 transaction hash, shared by every transaction
+/// type:
+///    1. A prefix that depends on the transaction type.
+///    2. The transaction's version.
+///    3. Sender (or deployed contract) address.
+///    4. A Poseidon hash of the tip and the resource bounds.
+///    5. A Poseidon hash of the paymaster data.
+///    6. The network's chain ID.
+///    7. The transaction's nonce.
+///    8. The nonce and fee data availability modes.
+fn v3_transaction_hash_common_fields(
+    tx_hash_prefix: TransactionHashPrefix,
+    address: &Address,
+    chain_id: Felt252,
+    nonce: Felt252,
+    v3_fields: &V3TransactionFields,
+) -> Result<Vec<Felt252>, SyscallHandlerError> {
+    Ok(vec![
+        tx_hash_prefix.get_prefix(),
+        3.into(),
+        address.0.clone(),
+        compute_poseidon_hash_on_elements(&v3_fields.fee_fields())?,
+        compute_poseidon_hash_on_elements(&v3_fields.paymaster_data)?,
+        chain_id,
+        nonce,
+        v3_fields.data_availability_modes(),
+    ])
+}
+
+pub fn calculate_invoke_v3_transaction_hash(
+    sender_address: &Address,
+    calldata: &[Felt252],
+    chain_id: Felt252,
+    nonce: Felt252,
+    v3_fields: &V3TransactionFields,
+) -> Result<Felt252, SyscallHandlerError> {
+    let mut data_to_hash = v3_transaction_hash_common_fields(
+        TransactionHashPrefix::Invoke,
+        sender_address,
+        chain_id,
+        nonce,
+        v3_fields,
+    )?;
+    data_to_hash.push(compute_poseidon_hash_on_elements(
+        &v3_fields.account_deployment_data,
+    )?);
+    data_to_hash.push(compute_poseidon_hash_on_elements(calldata)?);
+
+    compute_poseidon_hash_on_elements(&data_to_hash)
+}
+
+pub fn calculate_declare_v3_transaction_hash(
+    class_hash: Felt252,
+    compiled_class_hash: Felt252,
+    sender_address: &Address,
+    chain_id: Felt252,
+    nonce: Felt252,
+    v3_fields: &V3TransactionFields,
+) -> Result<Felt252, SyscallHandlerError> {
+    let mut data_to_hash = v3_transaction_hash_common_fields(
+        TransactionHashPrefix::Declare,
+        sender_address,
+        chain_id,
+        nonce,
+        v3_fields,
+    )?;
+    data_to_hash.push(compute_poseidon_hash_on_elements(
+        &v3_fields.account_deployment_data,
+    )?);
+    data_to_hash.push(class_hash);
+    data_to_hash.push(compiled_class_hash);
+
+    compute_poseidon_hash_on_elements(&data_to_hash)
+}
+
+pub fn calculate_deploy_account_v3_transaction_hash(
+    contract_address: &Address,
+    class_hash: Felt252,
+    constructor_calldata: &[Felt252],
+    salt: Felt252,
+    chain_id: Felt252,
+    nonce: Felt252,
+    v3_fields: &V3TransactionFields,
+) -> Result<Felt252, SyscallHandlerError> {
+    let mut data_to_hash = v3_transaction_hash_common_fields(
+        TransactionHashPrefix::DeployAccount,
+        contract_address,
+        chain_id,
+        nonce,
+        v3_fields,
+    )?;
+    data_to_hash.push(compute_poseidon_hash_on_elements(constructor_calldata)?);
+    data_to_hash.push(class_hash);
+    data_to_hash.push(salt);
+
+    compute_poseidon_hash_on_elements(&data_to_hash)
+}
+
 #[cfg(test)]
 mod tests {
     use coverage_helper::test;
@@ -159,7 +285,7 @@ mod tests {
     #[test]
     fn calculate_transaction_hash_common_test() {
         let tx_hash_prefix = TransactionHashPrefix::Declare;
-        let version = 0;
+        let version = 0.into();
         let contract_address = Address(42.into());
         let entry_point_selector = 100.into();
         let calldata = vec![540.into(), 338.into()];
@@ -186,4 +312,45 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn v3_transaction_hash_commits_to_resource_bounds_and_tip() {
+        use crate::business_logic::transaction::objects::resource_bounds::{
+            ResourceBounds, ResourceBoundsMapping,
+        };
+
+        let sender_address = Address(42.into());
+        let calldata = vec![540.into(), 338.into()];
+        let v3_fields = V3TransactionFields {
+            resource_bounds: ResourceBoundsMapping {
+                l1_gas: ResourceBounds {
+                    max_amount: 100,
+                    max_price_per_unit: 10,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let hash = |v3_fields: &V3TransactionFields| {
+            calculate_invoke_v3_transaction_hash(
+                &sender_address,
+                &calldata,
+                1.into(),
+                0.into(),
+                v3_fields,
+            )
+            .unwrap()
+        };
+
+        let with_tip = V3TransactionFields {
+            tip: 1,
+            ..v3_fields.clone()
+        };
+        let mut with_other_bounds = v3_fields.clone();
+        with_other_bounds.resource_bounds.l1_gas.max_amount = 101;
+
+        assert_eq!(hash(&v3_fields), hash(&v3_fields.clone()));
+        assert_ne!(hash(&v3_fields), hash(&with_tip));
+        assert_ne!(hash(&v3_fields), hash(&with_other_bounds));
+    }
 }
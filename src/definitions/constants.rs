@@ -44,17 +44,29 @@ lazy_static! {
             "4c07059285c2607d528a4c5220ef1f64d8f01273c23cfd9dec68759f61b544",
             16
         )),
-        gas_price: 0,
+        strk_fee_token_address: DEFAULT_STRK_FEE_TOKEN_ADDRESS.clone(),
     };
+    pub static ref DEFAULT_STRK_FEE_TOKEN_ADDRESS: Address = Address(felt_str!(
+        "4718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d",
+        16
+    ));
+    /// Canonical address of the Universal Deployer Contract.
+    pub static ref UDC_ADDRESS: Address = Address(felt_str!(
+        "41a78e741e5af2fec34b695679bc6891742439f7afb8484ecd7766661ad02bf",
+        16
+    ));
 }
 
-pub const DEFAULT_GAS_PRICE: u64 = 100_000_000_000; // 100 * 10**9
+pub const DEFAULT_GAS_PRICE: u128 = 100_000_000_000; // 100 * 10**9
 pub const DEFAULT_CONTRACT_STORAGE_COMMITMENT_TREE_HEIGHT: u64 = 251;
 pub const DEFAULT_GLOBAL_STATE_COMMITMENT_TREE_HEIGHT: u64 = 251;
 pub const DEFAULT_INVOKE_TX_MAX_N_STEPS: u64 = 1000000;
 pub const DEFAULT_VALIDATE_MAX_N_STEPS: u64 = 1000000;
+pub const DEFAULT_EVENT_KEYS_MAX_LEN: usize = 50;
+pub const DEFAULT_EVENT_DATA_MAX_LEN: usize = 300;
+pub const DEFAULT_MAX_N_EVENTS_PER_CALL: usize = 1000;
+pub const DEFAULT_L2_TO_L1_PAYLOAD_MAX_LEN: usize = 300;
 
-pub const DECLARE_VERSION: u64 = 2;
 pub const TRANSACTION_VERSION: u64 = 1;
 
 lazy_static! {
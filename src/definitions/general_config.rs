@@ -1,12 +1,18 @@
-use crate::{business_logic::state::state_api_objects::BlockInfo, utils::Address};
+use crate::{
+    business_logic::{state::state_api_objects::BlockInfo, transaction::fee::FeePayerPolicy},
+    core::syscalls::syscall_hooks::SharedSyscallHooks,
+    utils::Address,
+};
 use cairo_vm::felt::Felt252;
 use getset::{CopyGetters, Getters, MutGetters};
 use std::collections::HashMap;
 
 use super::constants::{
     DEFAULT_CAIRO_RESOURCE_FEE_WEIGHTS, DEFAULT_CONTRACT_STORAGE_COMMITMENT_TREE_HEIGHT,
+    DEFAULT_EVENT_DATA_MAX_LEN, DEFAULT_EVENT_KEYS_MAX_LEN,
     DEFAULT_GLOBAL_STATE_COMMITMENT_TREE_HEIGHT, DEFAULT_INVOKE_TX_MAX_N_STEPS,
-    DEFAULT_SEQUENCER_ADDRESS, DEFAULT_STARKNET_OS_CONFIG, DEFAULT_VALIDATE_MAX_N_STEPS,
+    DEFAULT_L2_TO_L1_PAYLOAD_MAX_LEN, DEFAULT_MAX_N_EVENTS_PER_CALL, DEFAULT_SEQUENCER_ADDRESS,
+    DEFAULT_STARKNET_OS_CONFIG, DEFAULT_STRK_FEE_TOKEN_ADDRESS, DEFAULT_VALIDATE_MAX_N_STEPS,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -39,15 +45,17 @@ pub struct StarknetOsConfig {
     pub(crate) chain_id: StarknetChainId,
     #[get = "pub"]
     pub(crate) fee_token_address: Address,
-    pub(crate) gas_price: u64,
+    /// Token in which version 3 transactions pay their fees.
+    #[getset(get = "pub", get_mut = "pub")]
+    pub(crate) strk_fee_token_address: Address,
 }
 
 impl StarknetOsConfig {
-    pub fn new(chain_id: StarknetChainId, fee_token_address: Address, gas_price: u64) -> Self {
+    pub fn new(chain_id: StarknetChainId, fee_token_address: Address) -> Self {
         StarknetOsConfig {
             chain_id,
             fee_token_address,
-            gas_price,
+            strk_fee_token_address: DEFAULT_STRK_FEE_TOKEN_ADDRESS.clone(),
         }
     }
 }
@@ -70,10 +78,28 @@ pub struct StarknetGeneralConfig {
     pub(crate) cairo_resource_fee_weights: HashMap<String, f64>,
     #[get_copy = "pub"]
     pub(crate) invoke_tx_max_n_steps: u64,
-    #[get_copy = "pub"]
+    #[getset(get_copy = "pub", get_mut = "pub")]
     pub(crate) validate_max_n_steps: u64,
     #[getset(get = "pub", get_mut = "pub")]
     pub(crate) block_info: BlockInfo,
+    /// Maximum number of keys of an emitted event.
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    pub(crate) event_keys_max_len: usize,
+    /// Maximum number of data elements of an emitted event.
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    pub(crate) event_data_max_len: usize,
+    /// Maximum number of events emitted by a single call.
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    pub(crate) max_n_events_per_call: usize,
+    /// Maximum payload length of an L2-to-L1 message.
+    #[getset(get_copy = "pub", get_mut = "pub")]
+    pub(crate) l2_to_l1_payload_max_len: usize,
+    /// Decides which account is debited the fee of each transaction.
+    #[getset(get = "pub", get_mut = "pub")]
+    pub(crate) fee_payer_policy: FeePayerPolicy,
+    /// Hooks applied by the syscall handler, e.g. testing cheatcodes.
+    #[getset(get = "pub", get_mut = "pub")]
+    pub(crate) syscall_hooks: Option<SharedSyscallHooks>,
 }
 
 impl StarknetGeneralConfig {
@@ -94,6 +120,12 @@ impl StarknetGeneralConfig {
             cairo_resource_fee_weights,
             validate_max_n_steps,
             block_info,
+            event_keys_max_len: DEFAULT_EVENT_KEYS_MAX_LEN,
+            event_data_max_len: DEFAULT_EVENT_DATA_MAX_LEN,
+            max_n_events_per_call: DEFAULT_MAX_N_EVENTS_PER_CALL,
+            l2_to_l1_payload_max_len: DEFAULT_L2_TO_L1_PAYLOAD_MAX_LEN,
+            fee_payer_policy: FeePayerPolicy::default(),
+            syscall_hooks: None,
         }
     }
 }
@@ -109,6 +141,12 @@ impl Default for StarknetGeneralConfig {
             cairo_resource_fee_weights: DEFAULT_CAIRO_RESOURCE_FEE_WEIGHTS.clone(),
             validate_max_n_steps: DEFAULT_VALIDATE_MAX_N_STEPS,
             block_info: BlockInfo::empty(DEFAULT_SEQUENCER_ADDRESS.clone()),
+            event_keys_max_len: DEFAULT_EVENT_KEYS_MAX_LEN,
+            event_data_max_len: DEFAULT_EVENT_DATA_MAX_LEN,
+            max_n_events_per_call: DEFAULT_MAX_N_EVENTS_PER_CALL,
+            l2_to_l1_payload_max_len: DEFAULT_L2_TO_L1_PAYLOAD_MAX_LEN,
+            fee_payer_policy: FeePayerPolicy::default(),
+            syscall_hooks: None,
         }
     }
 }
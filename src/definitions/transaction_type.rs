@@ -1,4 +1,7 @@
-#[derive(Debug, PartialEq, Copy, Clone, Eq, Hash)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Copy, Clone, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum TransactionType {
     Declare,
     Deploy,
@@ -0,0 +1,390 @@
+use super::{
+    devnet_state::DevnetState, dump::merged, error::DevnetError, subscriptions::TransactionStatus,
+};
+use crate::{
+    business_logic::{
+        execution::objects::Event,
+        fact_state::in_memory_state_reader::InMemoryStateReader,
+        state::{cached_state::CachedState, state_api_objects::BlockInfo},
+    },
+    core::errors::syscall_handler_errors::SyscallHandlerError,
+    hash_utils::compute_hash_on_elements,
+    testing::starknet_state::StarknetState,
+};
+use cairo_vm::felt::Felt252;
+use num_traits::Zero;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of latest blocks whose end state is kept, so that the blocks after them can be
+/// aborted. Older states are dropped to bound the memory used, since each one is a full copy.
+pub const MAX_KEPT_BLOCK_STATES: usize = 16;
+
+/// When the devnet closes the pending block and opens a new one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlockGeneration {
+    /// After each executed transaction.
+    #[default]
+    Transaction,
+    /// Only when `create_block` is called.
+    Demand,
+}
+
+/// A block closed by the devnet.
+#[derive(Clone, Debug)]
+pub struct Block {
+    pub block_info: BlockInfo,
+    pub block_hash: Felt252,
+    /// Hash of the block before it, or 0 for the first block.
+    pub parent_block_hash: Felt252,
+    /// Commitment to the state at the end of the block, see `state_commitment`.
+    pub state_root: Felt252,
+    /// Hashes of the transactions of the block, in execution order.
+    pub transaction_hashes: Vec<Felt252>,
+    /// State at the end of the block, restored when the blocks after it are aborted. Only the
+    /// latest `MAX_KEPT_BLOCK_STATES` blocks have it, and blocks loaded from a dump don't.
+    pub(crate) state: Option<StarknetState>,
+}
+
+impl DevnetState {
+    /// Closes the pending block with the transactions executed in it, and opens the next one
+    /// with the current time. Returns the closed block.
+    pub fn create_block(&mut self) -> &Block {
+        let block_info = self.starknet_state.general_config.block_info().clone();
+        let transaction_hashes: Vec<_> = self
+            .receipts()
+            .filter(|receipt| receipt.block_number == block_info.block_number)
+            .map(|receipt| receipt.transaction_hash.clone())
+            .collect();
+        let events: Vec<_> = self
+            .receipts()
+            .filter(|receipt| receipt.block_number == block_info.block_number)
+            .flat_map(|receipt| &receipt.events)
+            .collect();
+        let parent_block_hash = self
+            .blocks
+            .last()
+            .map(|block| block.block_hash.clone())
+            .unwrap_or_else(Felt252::zero);
+        let (state_root, block_hash) = state_commitment(&self.starknet_state.state)
+            .and_then(|state_root| {
+                let block_hash = block_hash(
+                    &block_info,
+                    &parent_block_hash,
+                    &state_root,
+                    &transaction_hashes,
+                    &events,
+                )?;
+                Ok((state_root, block_hash))
+            })
+            .expect("felts are valid field elements");
+        self.subscriptions.notify_new_head(&block_info);
+        for tx_hash in &transaction_hashes {
+            self.subscriptions
+                .notify_transaction_status(tx_hash, TransactionStatus::AcceptedOnL2);
+        }
+        self.blocks.push(Block {
+            block_info,
+            block_hash,
+            parent_block_hash,
+            state_root,
+            transaction_hashes,
+            state: Some(self.starknet_state.clone()),
+        });
+        if let Some(index) = self.blocks.len().checked_sub(MAX_KEPT_BLOCK_STATES + 1) {
+            self.blocks[index].state = None;
+        }
+
+        let timestamp = self.now();
+        let next_block_info = self.starknet_state.general_config.block_info_mut();
+        next_block_info.block_number += 1;
+        next_block_info.block_timestamp = timestamp;
+        &self.blocks[self.blocks.len() - 1]
+    }
+
+    /// Discards the blocks from `starting_block_number` on, along with the pending block, the
+    /// receipts of their transactions and the messages to L2 they handled, and restores the state
+    /// at the end of the block before them, which must be one of the latest
+    /// `MAX_KEPT_BLOCK_STATES` blocks. The time and gas price of the devnet are kept. Returns the
+    /// numbers of the aborted blocks.
+    pub fn abort_blocks(&mut self, starting_block_number: u64) -> Result<Vec<u64>, DevnetError> {
+        let pending_block_number = self.block_number();
+        if starting_block_number == 0 || starting_block_number >= pending_block_number {
+            return Err(DevnetError::BlockNotAbortable(starting_block_number));
+        }
+        let mut starknet_state = self
+            .block(starting_block_number - 1)
+            .and_then(|block| block.state.clone())
+            .ok_or(DevnetError::BlockNotAbortable(starting_block_number))?;
+
+        let timestamp = self.now();
+        let gas_price = self.starknet_state.general_config.block_info().gas_price;
+        let block_info = starknet_state.general_config.block_info_mut();
+        block_info.block_number = starting_block_number;
+        block_info.block_timestamp = timestamp;
+        block_info.gas_price = gas_price;
+        self.starknet_state = starknet_state;
+        self.blocks.truncate(starting_block_number as usize);
+        self.discard_receipts_from(starting_block_number);
+        self.postman.discard_from(
+            starting_block_number,
+            self.starknet_state.l2_to_l1_messages_log.len(),
+        );
+
+        Ok((starting_block_number..pending_block_number).collect())
+    }
+
+    /// Moves the time of the devnet forward, starting with the pending block.
+    pub fn increase_time(&mut self, seconds: u64) {
+        self.time_offset += seconds as i64;
+        let block_info = self.starknet_state.general_config.block_info_mut();
+        block_info.block_timestamp += seconds;
+    }
+
+    /// Sets the timestamp of the pending block. The time of the following blocks goes on
+    /// from it.
+    pub fn set_time(&mut self, timestamp: u64) {
+        self.time_offset = timestamp as i64 - system_time() as i64;
+        self.starknet_state
+            .general_config
+            .block_info_mut()
+            .block_timestamp = timestamp;
+    }
+
+    /// Sets the gas price of the pending block and the following ones.
+    pub fn set_gas_price(&mut self, gas_price: u128) {
+        self.starknet_state
+            .general_config
+            .block_info_mut()
+            .gas_price = gas_price;
+    }
+
+    pub fn set_block_generation(&mut self, block_generation: BlockGeneration) {
+        self.block_generation = block_generation;
+    }
+
+    pub fn block(&self, block_number: u64) -> Option<&Block> {
+        self.blocks.get(block_number as usize)
+    }
+
+    pub fn block_by_hash(&self, block_hash: &Felt252) -> Option<&Block> {
+        self.blocks
+            .iter()
+            .find(|block| block.block_hash == *block_hash)
+    }
+
+    /// Returns the closed blocks, from the first one.
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    /// Returns the number of the last closed block, if any.
+    pub fn latest_block_number(&self) -> Option<u64> {
+        self.blocks
+            .last()
+            .map(|block| block.block_info.block_number)
+    }
+
+    /// Whether the state of a block is the current one: it is the pending block, or a closed
+    /// block with no transaction executed after it.
+    pub fn has_current_state(&self, block_number: u64) -> bool {
+        block_number <= self.block_number()
+            && self
+                .receipts()
+                .all(|receipt| receipt.block_number <= block_number)
+    }
+
+    /// Current time of the devnet, in seconds since the Unix epoch.
+    fn now(&self) -> u64 {
+        (system_time() as i64 + self.time_offset).max(0) as u64
+    }
+}
+
+/// Commitment to a state: a hash chain of its contracts sorted by address, each one hashed with
+/// its class hash, nonce and a hash chain of its storage. Unlike the Patricia root of Starknet,
+/// it only identifies the states of this devnet.
+fn state_commitment(
+    state: &CachedState<InMemoryStateReader>,
+) -> Result<Felt252, SyscallHandlerError> {
+    let nonces = merged(
+        &state.state_reader.address_to_nonce,
+        &state.cache.nonce_writes,
+    );
+    let storage = merged(
+        &state.state_reader.address_to_storage,
+        &state.cache.storage_writes,
+    );
+    let contracts = merged(
+        &state.state_reader.address_to_class_hash,
+        &state.cache.class_hash_writes,
+    )
+    .into_iter()
+    .filter(|(_, class_hash)| *class_hash != [0; 32])
+    .map(|(address, class_hash)| {
+        let storage_entries: Vec<_> = storage
+            .range((address.clone(), [0; 32])..=(address.clone(), [u8::MAX; 32]))
+            .flat_map(|((_, key), value)| [Felt252::from_bytes_be(key), value.clone()])
+            .collect();
+        compute_hash_on_elements(&[
+            address.0.clone(),
+            Felt252::from_bytes_be(&class_hash),
+            nonces.get(&address).cloned().unwrap_or_else(Felt252::zero),
+            compute_hash_on_elements(&storage_entries)?,
+        ])
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+
+    compute_hash_on_elements(&contracts)
+}
+
+/// Hashes a block the way Starknet does, with hash chains in place of its Patricia
+/// commitments to the transactions and events.
+fn block_hash(
+    block_info: &BlockInfo,
+    parent_block_hash: &Felt252,
+    state_root: &Felt252,
+    transaction_hashes: &[Felt252],
+    events: &[&Event],
+) -> Result<Felt252, SyscallHandlerError> {
+    let event_hashes = events
+        .iter()
+        .map(|event| {
+            compute_hash_on_elements(&[
+                event.from_address.0.clone(),
+                compute_hash_on_elements(&event.keys)?,
+                compute_hash_on_elements(&event.data)?,
+            ])
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    compute_hash_on_elements(&[
+        block_info.block_number.into(),
+        state_root.clone(),
+        block_info.sequencer_address.0.clone(),
+        block_info.block_timestamp.into(),
+        transaction_hashes.len().into(),
+        compute_hash_on_elements(transaction_hashes)?,
+        event_hashes.len().into(),
+        compute_hash_on_elements(&event_hashes)?,
+        Felt252::zero(),
+        Felt252::zero(),
+        parent_block_hash.clone(),
+    ])
+}
+
+fn system_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+
+    #[test]
+    fn created_blocks_advance_the_block_info() {
+        let mut devnet = DevnetState::default();
+        devnet.set_time(1000);
+        devnet.set_gas_price(7);
+
+        let block = devnet.create_block();
+        assert_eq!(block.block_info.block_number, 0);
+        assert_eq!(block.block_info.block_timestamp, 1000);
+        assert!(block.transaction_hashes.is_empty());
+
+        let block_info = devnet.starknet_state.general_config.block_info();
+        assert_eq!(block_info.block_number, 1);
+        assert!(block_info.block_timestamp >= 1000);
+        assert_eq!(block_info.gas_price, 7);
+        assert_eq!(devnet.latest_block_number(), Some(0));
+    }
+
+    #[test]
+    fn blocks_are_chained_by_hash() {
+        let mut devnet = DevnetState::default();
+        devnet.set_time(1000);
+
+        let first_hash = devnet.create_block().block_hash.clone();
+        let second = devnet.create_block();
+
+        assert_eq!(second.parent_block_hash, first_hash);
+        assert_ne!(second.block_hash, first_hash);
+        let second_hash = second.block_hash.clone();
+        assert_eq!(
+            devnet
+                .block_by_hash(&second_hash)
+                .map(|block| block.block_info.block_number),
+            Some(1)
+        );
+        assert!(devnet.block_by_hash(&Felt252::zero()).is_none());
+    }
+
+    #[test]
+    fn increase_time_moves_the_pending_block() {
+        let mut devnet = DevnetState::default();
+        devnet.set_time(1000);
+        devnet.increase_time(500);
+
+        assert_eq!(
+            devnet
+                .starknet_state
+                .general_config
+                .block_info()
+                .block_timestamp,
+            1500
+        );
+        devnet.create_block();
+        assert!(
+            devnet
+                .starknet_state
+                .general_config
+                .block_info()
+                .block_timestamp
+                >= 1500
+        );
+    }
+
+    #[test]
+    fn aborted_blocks_are_discarded() {
+        let mut devnet = DevnetState::default();
+        devnet.create_block();
+        devnet.create_block();
+        devnet.create_block();
+        devnet.set_gas_price(7);
+
+        assert_matches!(
+            devnet.abort_blocks(0),
+            Err(DevnetError::BlockNotAbortable(0))
+        );
+        assert_matches!(
+            devnet.abort_blocks(3),
+            Err(DevnetError::BlockNotAbortable(3))
+        );
+        assert_eq!(devnet.abort_blocks(1).unwrap(), vec![1, 2]);
+        assert_eq!(devnet.blocks().len(), 1);
+        assert_eq!(devnet.block_number(), 1);
+        assert_eq!(
+            devnet.starknet_state.general_config.block_info().gas_price,
+            7
+        );
+    }
+
+    #[test]
+    fn only_the_latest_blocks_can_be_aborted() {
+        let mut devnet = DevnetState::default();
+        for _ in 0..=MAX_KEPT_BLOCK_STATES {
+            devnet.create_block();
+        }
+
+        assert_matches!(
+            devnet.abort_blocks(1),
+            Err(DevnetError::BlockNotAbortable(1))
+        );
+        assert_eq!(
+            devnet.abort_blocks(2).unwrap().len(),
+            MAX_KEPT_BLOCK_STATES - 1
+        );
+    }
+}
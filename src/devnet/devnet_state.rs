@@ -0,0 +1,314 @@
+use super::{
+    blocks::{Block, BlockGeneration},
+    error::DevnetError,
+    postman::PostmanState,
+    rpc::types::compress_program,
+    subscriptions::{Subscriptions, TransactionStatus},
+};
+use crate::{
+    business_logic::{
+        execution::{
+            execution_entry_point::ExecutionEntryPoint,
+            objects::{
+                Event, L2toL1MessageInfo, TransactionExecutionContext, TransactionExecutionInfo,
+            },
+        },
+        fact_state::state::ExecutionResourcesManager,
+        state::state_api::StateReader,
+        transaction::{fee::calculate_tx_fee, transactions::Transaction},
+    },
+    core::errors::state_errors::StateError,
+    definitions::transaction_type::TransactionType,
+    services::api::contract_class::EntryPointType,
+    testing::starknet_state::StarknetState,
+    utils::{Address, ClassHash},
+};
+use cairo_vm::felt::Felt252;
+use num_traits::Zero;
+use std::{collections::HashMap, fs::File, path::Path};
+
+/// What the devnet records of an executed transaction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionReceipt {
+    pub transaction_hash: Felt252,
+    pub tx_type: TransactionType,
+    pub block_number: u64,
+    /// Address of the deployed contract, for deploy and deploy account transactions.
+    pub contract_address: Option<Address>,
+    pub events: Vec<Event>,
+    pub messages_sent: Vec<L2toL1MessageInfo>,
+    pub execution_info: TransactionExecutionInfo,
+}
+
+/// Fee a transaction would be charged if it was executed on the current state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeEstimate {
+    pub gas_consumed: u128,
+    pub gas_price: u128,
+    pub overall_fee: u128,
+}
+
+// ---------------------------------------------------------------------
+/// State of the devnet: a `StarknetState` along with the receipts of the transactions executed
+/// on it, the blocks closed so far and the definitions of the classes declared through it, which
+/// the state doesn't keep. The block info of the state is the one of the pending block.
+#[derive(Clone, Debug)]
+pub struct DevnetState {
+    pub starknet_state: StarknetState,
+    class_definitions: HashMap<ClassHash, serde_json::Value>,
+    receipts: HashMap<Felt252, TransactionReceipt>,
+    /// Hashes of the executed transactions, in execution order.
+    transaction_hashes: Vec<Felt252>,
+    pub(crate) blocks: Vec<Block>,
+    pub(crate) block_generation: BlockGeneration,
+    /// Seconds added to the system time to get the time of the devnet.
+    pub(crate) time_offset: i64,
+    pub(crate) subscriptions: Subscriptions,
+    /// Messages exchanged with L1.
+    pub postman: PostmanState,
+}
+
+impl DevnetState {
+    pub fn new(starknet_state: StarknetState) -> Self {
+        DevnetState {
+            starknet_state,
+            class_definitions: HashMap::new(),
+            receipts: HashMap::new(),
+            transaction_hashes: Vec::new(),
+            blocks: Vec::new(),
+            block_generation: BlockGeneration::default(),
+            time_offset: 0,
+            subscriptions: Subscriptions::default(),
+            postman: PostmanState::default(),
+        }
+    }
+
+    pub fn block_number(&self) -> u64 {
+        self.starknet_state.general_config.block_info().block_number
+    }
+
+    /// Executes a transaction and records its receipt, closing the pending block when blocks are
+    /// generated per transaction. A rejected transaction leaves no receipt, but the subscriptions
+    /// to its status are notified of the rejection.
+    pub fn add_transaction(
+        &mut self,
+        mut tx: Transaction,
+    ) -> Result<&TransactionReceipt, DevnetError> {
+        let (tx_type, contract_address) = match &tx {
+            Transaction::Declare(_) => (TransactionType::Declare, None),
+            Transaction::Deploy(tx) => (TransactionType::Deploy, Some(tx.contract_address.clone())),
+            Transaction::DeployAccount(tx) => (
+                TransactionType::DeployAccount,
+                Some(tx.contract_address().clone()),
+            ),
+            Transaction::InvokeFunction(_) => (TransactionType::InvokeFunction, None),
+        };
+        let execution_info = match self.starknet_state.execute_tx(&mut tx) {
+            Ok(execution_info) => execution_info,
+            Err(error) => {
+                self.subscriptions
+                    .notify_transaction_status(&tx.hash_value(), TransactionStatus::Rejected);
+                return Err(error.into());
+            }
+        };
+
+        let receipt = TransactionReceipt {
+            transaction_hash: tx.hash_value(),
+            tx_type,
+            block_number: self.block_number(),
+            contract_address,
+            events: execution_info.get_sorted_events()?,
+            messages_sent: execution_info.get_sorted_l2_to_l1_messages()?,
+            execution_info,
+        };
+        Ok(self.add_receipt(receipt))
+    }
+
+    /// Records the receipt of a transaction executed in the pending block, closing the block
+    /// when blocks are generated per transaction.
+    pub(crate) fn add_receipt(&mut self, receipt: TransactionReceipt) -> &TransactionReceipt {
+        let tx_hash = self.record_receipt(receipt).transaction_hash.clone();
+        if self.block_generation == BlockGeneration::Transaction {
+            self.create_block();
+        }
+        &self.receipts[&tx_hash]
+    }
+
+    /// Records the receipt of an executed transaction, after the ones already recorded, and
+    /// notifies the subscriptions of it.
+    pub(crate) fn record_receipt(&mut self, receipt: TransactionReceipt) -> &TransactionReceipt {
+        let tx_hash = receipt.transaction_hash.clone();
+        self.subscriptions
+            .notify_transaction_status(&tx_hash, TransactionStatus::Pending);
+        self.subscriptions
+            .notify_events(&receipt.events, receipt.block_number, &tx_hash);
+        self.transaction_hashes.push(tx_hash.clone());
+        self.receipts.insert(tx_hash.clone(), receipt);
+        &self.receipts[&tx_hash]
+    }
+
+    /// Discards the receipts of the transactions executed in a block and the ones after it, and
+    /// notifies the subscriptions that they were aborted.
+    pub(crate) fn discard_receipts_from(&mut self, block_number: u64) {
+        let receipts = self
+            .transaction_hashes
+            .iter()
+            .filter_map(|tx_hash| self.receipts.get(tx_hash));
+        for receipt in receipts {
+            if receipt.block_number >= block_number {
+                self.subscriptions.notify_transaction_status(
+                    &receipt.transaction_hash,
+                    TransactionStatus::Aborted,
+                );
+            }
+        }
+        self.receipts
+            .retain(|_, receipt| receipt.block_number < block_number);
+        let receipts = &self.receipts;
+        self.transaction_hashes
+            .retain(|tx_hash| receipts.contains_key(tx_hash));
+    }
+
+    /// Executes the transactions one after the other on a copy of the state, and returns the
+    /// fee each one would be charged. Fails if any of them is rejected or reverted.
+    pub fn estimate_fee(&self, txs: Vec<Transaction>) -> Result<Vec<FeeEstimate>, DevnetError> {
+        let mut starknet_state = self.starknet_state.clone();
+        let gas_price = starknet_state.general_config.block_info().gas_price;
+
+        txs.into_iter()
+            .map(|mut tx| {
+                let execution_info = starknet_state
+                    .execute_tx(&mut tx)
+                    .map_err(|error| DevnetError::TransactionFailed(error.to_string()))?;
+                if let Some(revert_error) = execution_info.revert_error {
+                    return Err(DevnetError::TransactionFailed(revert_error.to_string()));
+                }
+
+                let overall_fee = calculate_tx_fee(
+                    &execution_info.actual_resources,
+                    gas_price,
+                    &starknet_state.general_config,
+                )?;
+                Ok(FeeEstimate {
+                    gas_consumed: overall_fee.checked_div(gas_price).unwrap_or(0),
+                    gas_price,
+                    overall_fee,
+                })
+            })
+            .collect()
+    }
+
+    /// Calls an external entry point of a contract without modifying the state. Returns the
+    /// return data of the call.
+    pub fn call(
+        &self,
+        contract_address: Address,
+        entry_point_selector: Felt252,
+        calldata: Vec<Felt252>,
+    ) -> Result<Vec<Felt252>, DevnetError> {
+        let mut state = self.starknet_state.state.clone();
+        deployed_class_hash(&mut state, &contract_address)?;
+
+        let call = ExecutionEntryPoint::new(
+            contract_address,
+            calldata,
+            entry_point_selector,
+            Address(Felt252::zero()),
+            EntryPointType::External,
+            None,
+            None,
+        );
+        let call_info = call
+            .execute(
+                &mut state,
+                &self.starknet_state.general_config,
+                &mut ExecutionResourcesManager::default(),
+                &TransactionExecutionContext::default(),
+            )
+            .map_err(DevnetError::CallFailed)?;
+
+        Ok(call_info.retdata)
+    }
+
+    pub fn class_hash_at(&mut self, contract_address: &Address) -> Result<ClassHash, DevnetError> {
+        deployed_class_hash(&mut self.starknet_state.state, contract_address)
+    }
+
+    pub fn nonce_at(&mut self, contract_address: &Address) -> Result<Felt252, DevnetError> {
+        deployed_class_hash(&mut self.starknet_state.state, contract_address)?;
+        match self.starknet_state.state.get_nonce_at(contract_address) {
+            Ok(nonce) => Ok(nonce),
+            // Contracts deployed without a transaction don't have a nonce yet.
+            Err(StateError::NoneContractState(_)) => Ok(Felt252::zero()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn storage_at(
+        &mut self,
+        contract_address: &Address,
+        key: &Felt252,
+    ) -> Result<Felt252, DevnetError> {
+        deployed_class_hash(&mut self.starknet_state.state, contract_address)?;
+        Ok(self
+            .starknet_state
+            .state
+            .get_storage_at(&(contract_address.clone(), key.to_be_bytes()))?)
+    }
+
+    pub fn receipt(&self, tx_hash: &Felt252) -> Option<&TransactionReceipt> {
+        self.receipts.get(tx_hash)
+    }
+
+    /// Returns the receipts of the executed transactions, in execution order.
+    pub fn receipts(&self) -> impl Iterator<Item = &TransactionReceipt> {
+        self.transaction_hashes
+            .iter()
+            .filter_map(|tx_hash| self.receipts.get(tx_hash))
+    }
+
+    /// Stores the definition of a class as it was sent to the devnet, so it can be returned
+    /// later on.
+    pub fn add_class_definition(&mut self, class_hash: ClassHash, definition: serde_json::Value) {
+        self.class_definitions.insert(class_hash, definition);
+    }
+
+    /// Stores the definition of a class from its compiled contract file, compressing its
+    /// program as declare transactions do.
+    pub fn add_class_definition_from_file(
+        &mut self,
+        class_hash: ClassHash,
+        path: &Path,
+    ) -> Result<(), DevnetError> {
+        let mut definition: serde_json::Value = serde_json::from_reader(File::open(path)?)
+            .map_err(|error| DevnetError::InvalidTransaction(error.to_string()))?;
+        definition["program"] =
+            serde_json::Value::String(compress_program(&definition["program"])?);
+        self.add_class_definition(class_hash, definition);
+        Ok(())
+    }
+
+    pub fn class_definition(&self, class_hash: &ClassHash) -> Option<&serde_json::Value> {
+        self.class_definitions.get(class_hash)
+    }
+}
+
+/// Returns the class hash of a contract, or an error if it isn't deployed.
+fn deployed_class_hash<S: StateReader>(
+    state: &mut S,
+    contract_address: &Address,
+) -> Result<ClassHash, DevnetError> {
+    match state.get_class_hash_at(contract_address) {
+        Ok(class_hash) if class_hash != [0; 32] => Ok(class_hash),
+        Ok(_) | Err(StateError::NoneContractState(_)) => {
+            Err(DevnetError::ContractNotFound(contract_address.clone()))
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+impl Default for DevnetState {
+    fn default() -> Self {
+        DevnetState::new(StarknetState::new(None))
+    }
+}
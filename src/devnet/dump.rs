@@ -0,0 +1,614 @@
+use super::{
+    blocks::Block,
+    devnet_state::{DevnetState, TransactionReceipt},
+    error::DevnetError,
+    postman::{MessageToL2, MockL1Backend, PostmanState},
+    rpc::types::{decompress_program, RpcEvent, RpcFelt, RpcMessageToL1},
+};
+use crate::{
+    business_logic::{
+        execution::objects::{Event, L2toL1MessageInfo, TransactionExecutionInfo},
+        fact_state::in_memory_state_reader::InMemoryStateReader,
+        state::{cached_state::CachedState, state_api_objects::BlockInfo},
+        transaction::error::TransactionError,
+    },
+    definitions::transaction_type::TransactionType,
+    services::api::{contract_class::ContractClass, messages::StarknetMessageToL1},
+    testing::{event_store::StoredEvent, starknet_state::StarknetState},
+    utils::Address,
+};
+use cairo_vm::felt::Felt252;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::BufWriter,
+    path::Path,
+};
+
+/// Version of the dump format. Dumps of any other version are rejected when loaded.
+pub const DUMP_VERSION: u64 = 1;
+
+/// Serialized state of a devnet. Lists are sorted so that dumping the same state twice gives
+/// the same output.
+#[derive(Debug, Serialize, Deserialize)]
+struct Dump {
+    version: u64,
+    /// Info of the pending block.
+    block: DumpedBlock,
+    blocks: Vec<DumpedClosedBlock>,
+    /// Seconds added to the system time to get the time of the devnet.
+    time_offset: i64,
+    classes: Vec<DumpedClass>,
+    contracts: Vec<DumpedContract>,
+    storage: Vec<DumpedStorageEntry>,
+    receipts: Vec<DumpedReceipt>,
+    events: Vec<DumpedEvent>,
+    l2_to_l1_messages: Vec<RpcMessageToL1>,
+    /// Hashes of the L2-to-L1 messages not consumed yet, with the number of pending copies.
+    pending_l2_to_l1_messages: BTreeMap<String, usize>,
+    postman: DumpedPostman,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DumpedBlock {
+    block_number: u64,
+    block_timestamp: u64,
+    gas_price: u128,
+    sequencer_address: RpcFelt,
+    starknet_version: String,
+}
+
+/// Block closed by the devnet. The state at its end isn't dumped, so the blocks loaded from a
+/// dump can't be aborted.
+#[derive(Debug, Serialize, Deserialize)]
+struct DumpedClosedBlock {
+    block: DumpedBlock,
+    block_hash: RpcFelt,
+    parent_block_hash: RpcFelt,
+    state_root: RpcFelt,
+    transaction_hashes: Vec<RpcFelt>,
+}
+
+/// Event emitted by the devnet, with the block and transaction that emitted it.
+#[derive(Debug, Serialize, Deserialize)]
+struct DumpedEvent {
+    block_number: u64,
+    transaction_hash: Option<RpcFelt>,
+    #[serde(flatten)]
+    event: RpcEvent,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DumpedClass {
+    class_hash: RpcFelt,
+    /// Definition of the class, as returned by `starknet_getClass`.
+    definition: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DumpedContract {
+    address: RpcFelt,
+    class_hash: RpcFelt,
+    nonce: Option<RpcFelt>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DumpedStorageEntry {
+    address: RpcFelt,
+    key: RpcFelt,
+    value: RpcFelt,
+}
+
+/// Messaging state of the devnet, along with the queues of its mock L1.
+#[derive(Debug, Serialize, Deserialize)]
+struct DumpedPostman {
+    /// Nonce of the next message sent to L2 on the mock L1.
+    next_l1_nonce: u64,
+    /// Messages sent to L2 on the mock L1 and not flushed yet.
+    messages_to_l2: Vec<DumpedMessageToL2>,
+    /// Messages delivered to the mock L1.
+    messages_to_l1: Vec<RpcMessageToL1>,
+    /// Number of L2-to-L1 messages already sent to L1.
+    n_sent_messages: usize,
+    handled_messages: Vec<DumpedHandledMessage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DumpedMessageToL2 {
+    from_address: RpcFelt,
+    to_address: RpcFelt,
+    entry_point_selector: RpcFelt,
+    payload: Vec<RpcFelt>,
+    nonce: RpcFelt,
+}
+
+/// Nonce of a handled message to L2, with the block of its L1 handler transaction.
+#[derive(Debug, Serialize, Deserialize)]
+struct DumpedHandledMessage {
+    nonce: RpcFelt,
+    block_number: u64,
+}
+
+/// Receipt of a transaction. Only the parts of the execution info that the endpoints return
+/// are kept.
+#[derive(Debug, Serialize, Deserialize)]
+struct DumpedReceipt {
+    transaction_hash: RpcFelt,
+    tx_type: TransactionType,
+    block_number: u64,
+    contract_address: Option<RpcFelt>,
+    events: Vec<RpcEvent>,
+    messages_sent: Vec<RpcMessageToL1>,
+    actual_fee: u128,
+    actual_resources: BTreeMap<String, usize>,
+    revert_error: Option<String>,
+}
+
+impl DevnetState {
+    /// Serializes the state of the devnet: its classes, contracts and their storage, blocks,
+    /// receipts, events, pending L2-to-L1 messages and the state of its postman.
+    pub fn dump(&self) -> Result<serde_json::Value, DevnetError> {
+        let starknet_state = &self.starknet_state;
+        let state = &starknet_state.state;
+        let block_info = starknet_state.general_config.block_info();
+
+        let mut class_hashes = state
+            .state_reader
+            .class_hash_to_contract_class
+            .keys()
+            .chain(state.contract_classes.iter().flat_map(HashMap::keys))
+            .collect::<Vec<_>>();
+        class_hashes.sort();
+        class_hashes.dedup();
+        let classes = class_hashes
+            .into_iter()
+            .map(|class_hash| {
+                let definition = self.class_definition(class_hash).cloned().ok_or_else(|| {
+                    DevnetError::MissingClassDefinition(format!("0x{}", hex::encode(class_hash)))
+                })?;
+                Ok(DumpedClass {
+                    class_hash: RpcFelt(Felt252::from_bytes_be(class_hash)),
+                    definition,
+                })
+            })
+            .collect::<Result<Vec<_>, DevnetError>>()?;
+
+        let nonces = merged(
+            &state.state_reader.address_to_nonce,
+            &state.cache.nonce_writes,
+        );
+        let contracts = merged(
+            &state.state_reader.address_to_class_hash,
+            &state.cache.class_hash_writes,
+        )
+        .into_iter()
+        .filter(|(_, class_hash)| *class_hash != [0; 32])
+        .map(|(address, class_hash)| DumpedContract {
+            nonce: nonces.get(&address).cloned().map(RpcFelt),
+            address: RpcFelt(address.0),
+            class_hash: RpcFelt(Felt252::from_bytes_be(&class_hash)),
+        })
+        .collect();
+        let storage = merged(
+            &state.state_reader.address_to_storage,
+            &state.cache.storage_writes,
+        )
+        .into_iter()
+        .map(|((address, key), value)| DumpedStorageEntry {
+            address: RpcFelt(address.0),
+            key: RpcFelt(Felt252::from_bytes_be(&key)),
+            value: RpcFelt(value),
+        })
+        .collect();
+
+        let dump = Dump {
+            version: DUMP_VERSION,
+            block: block_info.into(),
+            blocks: self
+                .blocks()
+                .iter()
+                .map(|block| DumpedClosedBlock {
+                    block: (&block.block_info).into(),
+                    block_hash: RpcFelt(block.block_hash.clone()),
+                    parent_block_hash: RpcFelt(block.parent_block_hash.clone()),
+                    state_root: RpcFelt(block.state_root.clone()),
+                    transaction_hashes: block
+                        .transaction_hashes
+                        .iter()
+                        .cloned()
+                        .map(RpcFelt)
+                        .collect(),
+                })
+                .collect(),
+            time_offset: self.time_offset,
+            classes,
+            contracts,
+            storage,
+            receipts: self.receipts().map(DumpedReceipt::from).collect(),
+            events: starknet_state
+                .events()
+                .iter()
+                .map(|stored| DumpedEvent {
+                    block_number: stored.block_number,
+                    transaction_hash: stored.transaction_hash.clone().map(RpcFelt),
+                    event: RpcEvent::from(&stored.event),
+                })
+                .collect(),
+            l2_to_l1_messages: starknet_state
+                .l2_to_l1_messages_log
+                .iter()
+                .map(RpcMessageToL1::from)
+                .collect(),
+            pending_l2_to_l1_messages: starknet_state
+                .l2_to_l1_messages
+                .iter()
+                .map(|(message_hash, count)| (hex::encode(message_hash), *count))
+                .collect(),
+            postman: (&self.postman).into(),
+        };
+        serde_json::to_value(dump).map_err(|error| DevnetError::InvalidDump(error.to_string()))
+    }
+
+    /// Replaces the state of the devnet with a dumped one. The configuration of the devnet and
+    /// its block generation mode are kept, apart from its block info. On error, the devnet is
+    /// left untouched.
+    pub fn load(&mut self, dump: serde_json::Value) -> Result<(), DevnetError> {
+        let version = dump
+            .get("version")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or_else(|| DevnetError::InvalidDump("missing version".to_string()))?;
+        if version != DUMP_VERSION {
+            return Err(DevnetError::UnsupportedDumpVersion(version));
+        }
+        let dump: Dump = serde_json::from_value(dump)
+            .map_err(|error| DevnetError::InvalidDump(error.to_string()))?;
+
+        let mut state_reader = InMemoryStateReader::default();
+        for class in &dump.classes {
+            state_reader.class_hash_to_contract_class.insert(
+                class.class_hash.0.to_be_bytes(),
+                parse_class(&class.definition)?,
+            );
+        }
+        for contract in dump.contracts {
+            let address = Address(contract.address.0);
+            if let Some(nonce) = contract.nonce {
+                state_reader
+                    .address_to_nonce
+                    .insert(address.clone(), nonce.0);
+            }
+            state_reader
+                .address_to_class_hash
+                .insert(address, contract.class_hash.0.to_be_bytes());
+        }
+        for entry in dump.storage {
+            state_reader.address_to_storage.insert(
+                (Address(entry.address.0), entry.key.0.to_be_bytes()),
+                entry.value.0,
+            );
+        }
+
+        let mut general_config = self.starknet_state.general_config.clone();
+        *general_config.block_info_mut() = dump.block.into();
+        let mut starknet_state = StarknetState::new_with_states(
+            Some(general_config),
+            CachedState::new(state_reader, Some(HashMap::new())),
+        );
+        for dumped in dump.events {
+            starknet_state.events.push(StoredEvent {
+                event: event(dumped.event),
+                block_number: dumped.block_number,
+                transaction_hash: dumped.transaction_hash.map(|hash| hash.0),
+            });
+        }
+        starknet_state.l2_to_l1_messages_log = dump
+            .l2_to_l1_messages
+            .into_iter()
+            .map(message_to_l1)
+            .collect();
+        if dump.postman.n_sent_messages > starknet_state.l2_to_l1_messages_log.len() {
+            return Err(DevnetError::InvalidDump(
+                "more L2-to-L1 messages sent to L1 than logged".to_string(),
+            ));
+        }
+        starknet_state.l2_to_l1_messages = dump
+            .pending_l2_to_l1_messages
+            .into_iter()
+            .map(|(message_hash, count)| {
+                let message_hash = hex::decode(&message_hash).map_err(|_| {
+                    DevnetError::InvalidDump(format!("invalid message hash: {message_hash}"))
+                })?;
+                Ok((message_hash, count))
+            })
+            .collect::<Result<_, DevnetError>>()?;
+
+        let mut devnet = DevnetState::new(starknet_state);
+        for class in dump.classes {
+            devnet.add_class_definition(class.class_hash.0.to_be_bytes(), class.definition);
+        }
+        for receipt in dump.receipts {
+            devnet.record_receipt(receipt.into());
+        }
+        devnet.blocks = dump
+            .blocks
+            .into_iter()
+            .map(|block| Block {
+                block_info: block.block.into(),
+                block_hash: block.block_hash.0,
+                parent_block_hash: block.parent_block_hash.0,
+                state_root: block.state_root.0,
+                transaction_hashes: felts(block.transaction_hashes),
+                state: None,
+            })
+            .collect();
+        devnet.block_generation = self.block_generation;
+        devnet.time_offset = dump.time_offset;
+        devnet.subscriptions = std::mem::take(&mut self.subscriptions);
+        devnet.postman = dump.postman.into();
+
+        *self = devnet;
+        Ok(())
+    }
+
+    /// Dumps the state of the devnet to a file.
+    pub fn dump_to_file(&self, path: &Path) -> Result<(), DevnetError> {
+        let dump = self.dump()?;
+        serde_json::to_writer(BufWriter::new(File::create(path)?), &dump)
+            .map_err(|error| DevnetError::InvalidDump(error.to_string()))
+    }
+
+    /// Loads a state dumped to a file with `dump_to_file`.
+    pub fn load_from_file(&mut self, path: &Path) -> Result<(), DevnetError> {
+        let dump = serde_json::from_reader(File::open(path)?)
+            .map_err(|error| DevnetError::InvalidDump(error.to_string()))?;
+        self.load(dump)
+    }
+}
+
+impl From<&BlockInfo> for DumpedBlock {
+    fn from(block_info: &BlockInfo) -> Self {
+        DumpedBlock {
+            block_number: block_info.block_number,
+            block_timestamp: block_info.block_timestamp,
+            gas_price: block_info.gas_price,
+            sequencer_address: RpcFelt(block_info.sequencer_address.0.clone()),
+            starknet_version: block_info.starknet_version.clone(),
+        }
+    }
+}
+
+impl From<DumpedBlock> for BlockInfo {
+    fn from(block: DumpedBlock) -> Self {
+        BlockInfo {
+            block_number: block.block_number,
+            block_timestamp: block.block_timestamp,
+            gas_price: block.gas_price,
+            sequencer_address: Address(block.sequencer_address.0),
+            starknet_version: block.starknet_version,
+        }
+    }
+}
+
+impl From<&TransactionReceipt> for DumpedReceipt {
+    fn from(receipt: &TransactionReceipt) -> Self {
+        let execution_info = &receipt.execution_info;
+        DumpedReceipt {
+            transaction_hash: RpcFelt(receipt.transaction_hash.clone()),
+            tx_type: receipt.tx_type,
+            block_number: receipt.block_number,
+            contract_address: receipt
+                .contract_address
+                .as_ref()
+                .map(|address| RpcFelt(address.0.clone())),
+            events: receipt.events.iter().map(Into::into).collect(),
+            messages_sent: receipt.messages_sent.iter().map(Into::into).collect(),
+            actual_fee: execution_info.actual_fee,
+            actual_resources: execution_info
+                .actual_resources
+                .clone()
+                .into_iter()
+                .collect(),
+            revert_error: execution_info
+                .revert_error
+                .as_ref()
+                .map(ToString::to_string),
+        }
+    }
+}
+
+impl From<DumpedReceipt> for TransactionReceipt {
+    fn from(receipt: DumpedReceipt) -> Self {
+        let mut execution_info = TransactionExecutionInfo::new(
+            None,
+            None,
+            None,
+            receipt.actual_fee,
+            receipt.actual_resources.into_iter().collect(),
+            Some(receipt.tx_type),
+        );
+        // Only the message of the error is dumped.
+        execution_info.revert_error = receipt
+            .revert_error
+            .map(|message| TransactionError::StarknetError(message).into());
+
+        TransactionReceipt {
+            transaction_hash: receipt.transaction_hash.0,
+            tx_type: receipt.tx_type,
+            block_number: receipt.block_number,
+            contract_address: receipt.contract_address.map(|address| Address(address.0)),
+            events: receipt.events.into_iter().map(event).collect(),
+            messages_sent: receipt
+                .messages_sent
+                .into_iter()
+                .map(|message| L2toL1MessageInfo {
+                    from_address: Address(message.from_address.0),
+                    to_address: Address(message.to_address.0),
+                    payload: felts(message.payload),
+                })
+                .collect(),
+            execution_info,
+        }
+    }
+}
+
+impl From<&PostmanState> for DumpedPostman {
+    fn from(postman: &PostmanState) -> Self {
+        let mut handled_messages = postman
+            .handled_nonces
+            .iter()
+            .map(|(nonce, block_number)| DumpedHandledMessage {
+                nonce: RpcFelt(nonce.clone()),
+                block_number: *block_number,
+            })
+            .collect::<Vec<_>>();
+        handled_messages
+            .sort_by_key(|handled| (handled.block_number, handled.nonce.0.to_biguint()));
+
+        let mock_l1 = &postman.mock_l1;
+        DumpedPostman {
+            next_l1_nonce: mock_l1.next_nonce,
+            messages_to_l2: mock_l1
+                .messages_to_l2
+                .iter()
+                .map(|message| DumpedMessageToL2 {
+                    from_address: RpcFelt(message.from_address.clone()),
+                    to_address: RpcFelt(message.to_address.0.clone()),
+                    entry_point_selector: RpcFelt(message.entry_point_selector.clone()),
+                    payload: message.payload.iter().cloned().map(RpcFelt).collect(),
+                    nonce: RpcFelt(message.nonce.clone()),
+                })
+                .collect(),
+            messages_to_l1: mock_l1.messages_to_l1.iter().map(Into::into).collect(),
+            n_sent_messages: postman.n_sent_messages,
+            handled_messages,
+        }
+    }
+}
+
+impl From<DumpedPostman> for PostmanState {
+    fn from(postman: DumpedPostman) -> Self {
+        PostmanState {
+            mock_l1: MockL1Backend {
+                next_nonce: postman.next_l1_nonce,
+                messages_to_l2: postman
+                    .messages_to_l2
+                    .into_iter()
+                    .map(|message| MessageToL2 {
+                        from_address: message.from_address.0,
+                        to_address: Address(message.to_address.0),
+                        entry_point_selector: message.entry_point_selector.0,
+                        payload: felts(message.payload),
+                        nonce: message.nonce.0,
+                    })
+                    .collect(),
+                messages_to_l1: postman
+                    .messages_to_l1
+                    .into_iter()
+                    .map(message_to_l1)
+                    .collect(),
+            },
+            n_sent_messages: postman.n_sent_messages,
+            handled_nonces: postman
+                .handled_messages
+                .into_iter()
+                .map(|handled| (handled.nonce.0, handled.block_number))
+                .collect(),
+        }
+    }
+}
+
+/// Returns the entries of a state reader map overwritten by the writes of the cache, sorted.
+pub(super) fn merged<K: Clone + Ord, V: Clone>(
+    values: &HashMap<K, V>,
+    writes: &HashMap<K, V>,
+) -> BTreeMap<K, V> {
+    values
+        .iter()
+        .chain(writes.iter())
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+fn parse_class(definition: &serde_json::Value) -> Result<ContractClass, DevnetError> {
+    let mut definition = definition.clone();
+    if let Some(serde_json::Value::String(compressed)) = definition.get("program") {
+        definition["program"] = decompress_program(compressed)?;
+    }
+    ContractClass::try_from(definition.to_string().as_str())
+        .map_err(|error| DevnetError::InvalidDump(error.to_string()))
+}
+
+fn event(event: RpcEvent) -> Event {
+    Event {
+        from_address: Address(event.from_address.0),
+        keys: felts(event.keys),
+        data: felts(event.data),
+    }
+}
+
+fn message_to_l1(message: RpcMessageToL1) -> StarknetMessageToL1 {
+    StarknetMessageToL1::new(
+        Address(message.from_address.0),
+        Address(message.to_address.0),
+        felts(message.payload),
+    )
+}
+
+fn felts(values: Vec<RpcFelt>) -> Vec<Felt252> {
+    values.into_iter().map(|felt| felt.0).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+    use serde_json::json;
+
+    #[test]
+    fn dumps_of_other_versions_are_rejected() {
+        let mut devnet = DevnetState::default();
+        let mut dump = devnet.dump().unwrap();
+        dump["version"] = json!(DUMP_VERSION + 1);
+
+        assert_matches!(
+            devnet.load(dump),
+            Err(DevnetError::UnsupportedDumpVersion(version)) if version == DUMP_VERSION + 1
+        );
+        assert_matches!(
+            devnet.load(json!({"classes": []})),
+            Err(DevnetError::InvalidDump(_))
+        );
+    }
+
+    #[test]
+    fn invalid_message_hashes_are_rejected() {
+        let mut devnet = DevnetState::default();
+        let mut dump = devnet.dump().unwrap();
+        dump["pending_l2_to_l1_messages"] = json!({"0g": 1});
+
+        assert_matches!(devnet.load(dump), Err(DevnetError::InvalidDump(_)));
+    }
+
+    #[test]
+    fn postman_state_round_trips() {
+        let mut devnet = DevnetState::default();
+        let message = devnet.postman.mock_l1.send_message_to_l2(
+            1.into(),
+            Address(2.into()),
+            3.into(),
+            vec![4.into()],
+        );
+        devnet.postman.handled_nonces.insert(7.into(), 0);
+
+        let mut loaded = DevnetState::default();
+        loaded.load(devnet.dump().unwrap()).unwrap();
+        assert_eq!(loaded.postman.mock_l1.messages_to_l2, vec![message]);
+        assert_eq!(loaded.postman.mock_l1.next_nonce, 1);
+        assert_eq!(loaded.postman.handled_nonces, devnet.postman.handled_nonces);
+
+        let mut dump = devnet.dump().unwrap();
+        dump["postman"]["n_sent_messages"] = json!(1);
+        assert_matches!(loaded.load(dump), Err(DevnetError::InvalidDump(_)));
+    }
+}
@@ -0,0 +1,43 @@
+use crate::{
+    business_logic::transaction::error::TransactionError, core::errors::state_errors::StateError,
+    testing::starknet_state_error::StarknetStateError, utils::Address,
+};
+use thiserror::Error;
+
+/// Errors of the devnet operations, shared by its endpoints. Each endpoint maps them to its own
+/// error codes.
+#[derive(Debug, Error)]
+pub enum DevnetError {
+    #[error("Contract {0:?} is not deployed")]
+    ContractNotFound(Address),
+    #[error("Block not found")]
+    BlockNotFound,
+    #[error("Block {0} can't be aborted")]
+    BlockNotAbortable(u64),
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+    #[error("Transaction version {0} is not supported")]
+    UnsupportedTxVersion(u64),
+    #[error("Call failed: {0}")]
+    CallFailed(TransactionError),
+    #[error("Transaction failed: {0}")]
+    TransactionFailed(String),
+    #[error("Class {0} has no definition to dump")]
+    MissingClassDefinition(String),
+    #[error("Invalid dump: {0}")]
+    InvalidDump(String),
+    #[error("Dump version {0} is not supported")]
+    UnsupportedDumpVersion(u64),
+    #[error("A message to L2 with nonce {0} was already handled")]
+    MessageNonceUsed(String),
+    #[error("Minting {0} would take a balance or the total supply beyond the Uint256 range")]
+    MintOverflow(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Transaction(#[from] TransactionError),
+    #[error(transparent)]
+    StarknetState(#[from] StarknetStateError),
+    #[error(transparent)]
+    State(#[from] StateError),
+}
@@ -0,0 +1,97 @@
+use crate::{
+    business_logic::transaction::error::TransactionError, devnet::error::DevnetError,
+    testing::starknet_state_error::StarknetStateError,
+};
+use thiserror::Error;
+
+/// Errors of the gateway and feeder gateway endpoints. Each one has the code given to it by the
+/// Starknet gateway.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum GatewayError {
+    #[error("Malformed request: {0}")]
+    MalformedRequest(String),
+    #[error("Requested contract address {0} is not deployed")]
+    UninitializedContract(String),
+    #[error("Block not found")]
+    BlockNotFound,
+    #[error("{0}")]
+    TransactionFailed(String),
+    #[error("{0}")]
+    InvalidTransactionNonce(String),
+    #[error("{0}")]
+    ClassAlreadyDeclared(String),
+    #[error("{0}")]
+    InsufficientMaxFee(String),
+    #[error("{0}")]
+    InsufficientAccountBalance(String),
+    #[error("{0}")]
+    ValidateFailure(String),
+    #[error("Transaction version {0} is not supported")]
+    InvalidTransactionVersion(u64),
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl GatewayError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            GatewayError::MalformedRequest(_) => "StarkErrorCode.MALFORMED_REQUEST",
+            GatewayError::UninitializedContract(_) => "StarknetErrorCode.UNINITIALIZED_CONTRACT",
+            GatewayError::BlockNotFound => "StarknetErrorCode.BLOCK_NOT_FOUND",
+            GatewayError::TransactionFailed(_) => "StarknetErrorCode.TRANSACTION_FAILED",
+            GatewayError::InvalidTransactionNonce(_) => {
+                "StarknetErrorCode.INVALID_TRANSACTION_NONCE"
+            }
+            GatewayError::ClassAlreadyDeclared(_) => "StarknetErrorCode.CLASS_ALREADY_DECLARED",
+            GatewayError::InsufficientMaxFee(_) => "StarknetErrorCode.INSUFFICIENT_MAX_FEE",
+            GatewayError::InsufficientAccountBalance(_) => {
+                "StarknetErrorCode.INSUFFICIENT_ACCOUNT_BALANCE"
+            }
+            GatewayError::ValidateFailure(_) => "StarknetErrorCode.VALIDATE_FAILURE",
+            GatewayError::InvalidTransactionVersion(_) => {
+                "StarknetErrorCode.INVALID_TRANSACTION_VERSION"
+            }
+            GatewayError::Internal(_) => "StarkErrorCode.UNEXPECTED_FAILURE",
+        }
+    }
+}
+
+/// Maps the error of a rejected transaction, as the RPC endpoint does.
+impl From<TransactionError> for GatewayError {
+    fn from(error: TransactionError) -> Self {
+        let message = error.to_string();
+        match error {
+            TransactionError::InvalidTransactionNonce(..) => {
+                GatewayError::InvalidTransactionNonce(message)
+            }
+            TransactionError::MaxFeeTooLow(..) => GatewayError::InsufficientMaxFee(message),
+            TransactionError::InsufficientBalance(..) => {
+                GatewayError::InsufficientAccountBalance(message)
+            }
+            TransactionError::ClassAlreadyDeclared(_) => {
+                GatewayError::ClassAlreadyDeclared(message)
+            }
+            _ => GatewayError::ValidateFailure(message),
+        }
+    }
+}
+
+impl From<DevnetError> for GatewayError {
+    fn from(error: DevnetError) -> Self {
+        match error {
+            DevnetError::ContractNotFound(address) => {
+                GatewayError::UninitializedContract(format!("0x{}", address.0.to_str_radix(16)))
+            }
+            DevnetError::BlockNotFound => GatewayError::BlockNotFound,
+            DevnetError::InvalidTransaction(message) => GatewayError::MalformedRequest(message),
+            DevnetError::UnsupportedTxVersion(version) => {
+                GatewayError::InvalidTransactionVersion(version)
+            }
+            DevnetError::CallFailed(error) => GatewayError::TransactionFailed(error.to_string()),
+            DevnetError::TransactionFailed(message) => GatewayError::TransactionFailed(message),
+            DevnetError::Transaction(error) => error.into(),
+            DevnetError::StarknetState(StarknetStateError::Transaction(error)) => error.into(),
+            error => GatewayError::Internal(error.to_string()),
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use super::{
+    error::GatewayError,
+    types::{
+        transaction_type_name, FeederQuery, GatewayCall, GatewayFeeEstimate, GatewayFelt,
+        GatewayTransaction, GatewayTransactionReceipt,
+    },
+};
+use crate::{
+    definitions::general_config::StarknetChainId,
+    devnet::{
+        devnet_state::DevnetState,
+        rpc::types::{BroadcastedTransaction, RpcFelt},
+    },
+    utils::Address,
+};
+use cairo_vm::felt::Felt252;
+use num_traits::Zero;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+
+// ---------------------------------------------------------------------
+//  Gateway
+// ---------------------------------------------------------------------
+
+/// Handles `/gateway/add_transaction`: executes the transaction and answers with its hash,
+/// along with the declared class hash or the deployed account address.
+pub fn add_transaction(devnet: &mut DevnetState, body: &[u8]) -> Result<Value, GatewayError> {
+    let tx: GatewayTransaction = parse_body(body)?;
+    let chain_id = chain_id(devnet);
+
+    match BroadcastedTransaction::from(tx) {
+        BroadcastedTransaction::Declare(tx) => {
+            let (tx, definition) = tx.into_transaction(chain_id)?;
+            let class_hash = tx.contract_hash();
+            let tx_hash = devnet.add_transaction(tx)?.transaction_hash.clone();
+            devnet.add_class_definition(class_hash, definition);
+            Ok(json!({
+                "code": "TRANSACTION_RECEIVED",
+                "transaction_hash": RpcFelt(tx_hash),
+                "class_hash": RpcFelt(Felt252::from_bytes_be(&class_hash)),
+            }))
+        }
+        BroadcastedTransaction::DeployAccount(tx) => {
+            let tx = tx.into_transaction(chain_id)?;
+            let address = tx.contract_address();
+            let tx_hash = devnet.add_transaction(tx)?.transaction_hash.clone();
+            Ok(json!({
+                "code": "TRANSACTION_RECEIVED",
+                "transaction_hash": RpcFelt(tx_hash),
+                "address": RpcFelt(address.0),
+            }))
+        }
+        tx => {
+            let tx = tx.into_transaction(chain_id)?;
+            let tx_hash = devnet.add_transaction(tx)?.transaction_hash.clone();
+            Ok(json!({
+                "code": "TRANSACTION_RECEIVED",
+                "transaction_hash": RpcFelt(tx_hash),
+            }))
+        }
+    }
+}
+
+// ---------------------------------------------------------------------
+//  Feeder gateway
+// ---------------------------------------------------------------------
+
+/// Handles `/feeder_gateway/call_contract`.
+pub fn call_contract(
+    devnet: &DevnetState,
+    query: &FeederQuery,
+    body: &[u8],
+) -> Result<Value, GatewayError> {
+    check_block(devnet, query)?;
+    let call: GatewayCall = parse_body(body)?;
+
+    let retdata = devnet.call(
+        Address(call.contract_address.0),
+        call.entry_point_selector.0,
+        call.calldata.into_iter().map(|felt| felt.0).collect(),
+    )?;
+    Ok(json!({ "result": retdata.into_iter().map(RpcFelt).collect::<Vec<_>>() }))
+}
+
+/// Handles `/feeder_gateway/estimate_fee`.
+pub fn estimate_fee(
+    devnet: &DevnetState,
+    query: &FeederQuery,
+    body: &[u8],
+) -> Result<Value, GatewayError> {
+    check_block(devnet, query)?;
+    let tx: GatewayTransaction = parse_body(body)?;
+
+    let tx = BroadcastedTransaction::from(tx).into_transaction(chain_id(devnet))?;
+    let estimate = devnet
+        .estimate_fee(vec![tx])?
+        .pop()
+        .ok_or_else(|| GatewayError::Internal("missing fee estimate".to_string()))?;
+    to_value(GatewayFeeEstimate::from(estimate))
+}
+
+/// Handles `/feeder_gateway/get_transaction_receipt`. Unknown transactions have the
+/// `NOT_RECEIVED` status, as in the gateway.
+pub fn get_transaction_receipt(
+    devnet: &DevnetState,
+    query: &FeederQuery,
+) -> Result<Value, GatewayError> {
+    let tx_hash = query_felt(&query.transaction_hash, "transactionHash")?;
+
+    let receipt = match devnet.receipt(&tx_hash) {
+        Some(receipt) => receipt,
+        None => {
+            return Ok(json!({
+                "status": "NOT_RECEIVED",
+                "transaction_hash": RpcFelt(tx_hash),
+            }))
+        }
+    };
+    let index = devnet
+        .receipts()
+        .filter(|other| other.block_number == receipt.block_number)
+        .position(|other| other.transaction_hash == tx_hash)
+        .unwrap_or(0);
+    let block_hash = devnet
+        .block(receipt.block_number)
+        .map(|block| &block.block_hash);
+    to_value(GatewayTransactionReceipt::new(receipt, index, block_hash))
+}
+
+/// Handles `/feeder_gateway/get_class_hash_at`.
+pub fn get_class_hash_at(
+    devnet: &mut DevnetState,
+    query: &FeederQuery,
+) -> Result<Value, GatewayError> {
+    check_block(devnet, query)?;
+    let contract_address = query_felt(&query.contract_address, "contractAddress")?;
+
+    let class_hash = devnet.class_hash_at(&Address(contract_address))?;
+    to_value(RpcFelt(Felt252::from_bytes_be(&class_hash)))
+}
+
+/// Handles `/feeder_gateway/get_storage_at`.
+pub fn get_storage_at(
+    devnet: &mut DevnetState,
+    query: &FeederQuery,
+) -> Result<Value, GatewayError> {
+    check_block(devnet, query)?;
+    let contract_address = query_felt(&query.contract_address, "contractAddress")?;
+    let key = query_felt(&query.key, "key")?;
+
+    let value = devnet.storage_at(&Address(contract_address), &key)?;
+    to_value(RpcFelt(value))
+}
+
+/// Handles `/feeder_gateway/get_block`. Queries without a block refer to the latest block.
+pub fn get_block(devnet: &DevnetState, query: &FeederQuery) -> Result<Value, GatewayError> {
+    let block_number = match query.block_number.as_deref() {
+        None | Some("latest") if query.block_hash.is_none() => devnet
+            .latest_block_number()
+            .ok_or(GatewayError::BlockNotFound)?,
+        _ => query_block_number(devnet, query)?,
+    };
+    block(devnet, block_number)
+}
+
+/// Returns a block in the feeder gateway format: a closed block, or the pending one, which
+/// has no hash nor state root yet.
+pub fn block(devnet: &DevnetState, block_number: u64) -> Result<Value, GatewayError> {
+    let closed_block = devnet.block(block_number);
+    let (block_info, status, parent_block_hash) = match closed_block {
+        Some(block) => (
+            &block.block_info,
+            "ACCEPTED_ON_L2",
+            block.parent_block_hash.clone(),
+        ),
+        None if block_number == devnet.block_number() => (
+            devnet.starknet_state.general_config.block_info(),
+            "PENDING",
+            devnet
+                .blocks()
+                .last()
+                .map(|block| block.block_hash.clone())
+                .unwrap_or_else(Felt252::zero),
+        ),
+        None => return Err(GatewayError::BlockNotFound),
+    };
+    let block_hash = closed_block.map(|block| &block.block_hash);
+
+    let receipts = devnet
+        .receipts()
+        .filter(|receipt| receipt.block_number == block_number)
+        .enumerate()
+        .map(|(index, receipt)| GatewayTransactionReceipt::new(receipt, index, block_hash))
+        .collect::<Vec<_>>();
+    let transactions = devnet
+        .receipts()
+        .filter(|receipt| receipt.block_number == block_number)
+        .map(|receipt| {
+            json!({
+                "transaction_hash": RpcFelt(receipt.transaction_hash.clone()),
+                "type": transaction_type_name(receipt.tx_type),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut block = json!({
+        "block_number": block_number,
+        "status": status,
+        "parent_block_hash": RpcFelt(parent_block_hash),
+        "gas_price": RpcFelt::from(block_info.gas_price),
+        "timestamp": block_info.block_timestamp,
+        "sequencer_address": RpcFelt(block_info.sequencer_address.0.clone()),
+        "starknet_version": block_info.starknet_version,
+        "transactions": transactions,
+        "transaction_receipts": receipts,
+    });
+    if let Some(closed_block) = closed_block {
+        block["block_hash"] = json!(RpcFelt(closed_block.block_hash.clone()));
+        block["state_root"] = json!(RpcFelt(closed_block.state_root.clone()));
+    }
+    Ok(block)
+}
+
+// ---------------------------------------------------------------------
+//  Helpers
+// ---------------------------------------------------------------------
+
+fn parse_body<T: DeserializeOwned>(body: &[u8]) -> Result<T, GatewayError> {
+    serde_json::from_slice(body).map_err(|error| GatewayError::MalformedRequest(error.to_string()))
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, GatewayError> {
+    serde_json::to_value(value).map_err(|error| GatewayError::Internal(error.to_string()))
+}
+
+fn query_felt(value: &Option<String>, name: &str) -> Result<Felt252, GatewayError> {
+    let value = value
+        .as_ref()
+        .ok_or_else(|| GatewayError::MalformedRequest(format!("missing parameter `{name}`")))?;
+    serde_json::from_value::<GatewayFelt>(Value::String(value.clone()))
+        .map(|felt| felt.0)
+        .map_err(|error| GatewayError::MalformedRequest(error.to_string()))
+}
+
+fn chain_id(devnet: &DevnetState) -> StarknetChainId {
+    *devnet
+        .starknet_state
+        .general_config
+        .starknet_os_config()
+        .chain_id()
+}
+
+/// Returns the number of the block of the query, given by its hash or number. Queries without a
+/// block refer to the pending block.
+fn query_block_number(devnet: &DevnetState, query: &FeederQuery) -> Result<u64, GatewayError> {
+    if query.block_hash.is_some() {
+        let block_hash = query_felt(&query.block_hash, "blockHash")?;
+        return devnet
+            .block_by_hash(&block_hash)
+            .map(|block| block.block_info.block_number)
+            .ok_or(GatewayError::BlockNotFound);
+    }
+    match query.block_number.as_deref() {
+        None | Some("pending") => Ok(devnet.block_number()),
+        Some("latest") => devnet
+            .latest_block_number()
+            .ok_or(GatewayError::BlockNotFound),
+        Some(block_number) => match block_number.parse() {
+            Ok(block_number) if block_number <= devnet.block_number() => Ok(block_number),
+            _ => Err(GatewayError::BlockNotFound),
+        },
+    }
+}
+
+/// Checks that the state of the block of the query is the current one, the only state that is
+/// queried.
+fn check_block(devnet: &DevnetState, query: &FeederQuery) -> Result<(), GatewayError> {
+    match query_block_number(devnet, query)? {
+        block_number if devnet.has_current_state(block_number) => Ok(()),
+        _ => Err(GatewayError::BlockNotFound),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+
+    #[test]
+    fn only_the_current_state_is_found() {
+        let mut devnet = DevnetState::default();
+        let query = |block_number: &str| FeederQuery {
+            block_number: Some(block_number.to_string()),
+            ..Default::default()
+        };
+
+        assert_matches!(check_block(&devnet, &FeederQuery::default()), Ok(()));
+        assert_matches!(
+            check_block(&devnet, &query("latest")),
+            Err(GatewayError::BlockNotFound)
+        );
+        assert_matches!(
+            get_block(&devnet, &FeederQuery::default()),
+            Err(GatewayError::BlockNotFound)
+        );
+        assert_matches!(
+            get_block(&devnet, &query("0")),
+            Ok(block) if block["status"] == json!("PENDING")
+        );
+
+        devnet.create_block();
+        assert_matches!(check_block(&devnet, &query("latest")), Ok(()));
+        assert_matches!(check_block(&devnet, &query("0")), Ok(()));
+        assert_matches!(
+            check_block(&devnet, &query("2")),
+            Err(GatewayError::BlockNotFound)
+        );
+        assert_matches!(
+            get_block(&devnet, &FeederQuery::default()),
+            Ok(block) if block["block_number"] == json!(0) && block["transactions"] == json!([])
+        );
+    }
+
+    #[test]
+    fn blocks_are_found_by_hash() {
+        let mut devnet = DevnetState::default();
+        let block_hash = devnet.create_block().block_hash.clone();
+        let query = |block_hash: &Felt252| FeederQuery {
+            block_hash: Some(format!("0x{}", block_hash.to_str_radix(16))),
+            ..Default::default()
+        };
+
+        let block = get_block(&devnet, &query(&block_hash)).unwrap();
+        assert_eq!(block["block_number"], json!(0));
+        assert_eq!(block["block_hash"], json!(RpcFelt(block_hash)));
+        assert_eq!(block["parent_block_hash"], json!("0x0"));
+        assert!(block["state_root"].is_string());
+        assert_matches!(
+            get_block(&devnet, &query(&1.into())),
+            Err(GatewayError::BlockNotFound)
+        );
+        assert_matches!(
+            get_block(&devnet, &FeederQuery { block_number: Some("pending".to_string()), ..Default::default() }),
+            Ok(block) if block.get("block_hash").is_none()
+        );
+    }
+
+    #[test]
+    fn undeployed_contracts_are_uninitialized() {
+        let mut devnet = DevnetState::default();
+        let query = FeederQuery {
+            contract_address: Some("0x1".to_string()),
+            ..Default::default()
+        };
+
+        let error = get_class_hash_at(&mut devnet, &query).unwrap_err();
+
+        assert_eq!(error.code(), "StarknetErrorCode.UNINITIALIZED_CONTRACT");
+        assert_eq!(
+            get_storage_at(&mut devnet, &query),
+            Err(GatewayError::MalformedRequest(
+                "missing parameter `key`".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn unknown_transactions_are_not_received() {
+        let devnet = DevnetState::default();
+        let query = FeederQuery {
+            transaction_hash: Some("0x2".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            get_transaction_receipt(&devnet, &query),
+            Ok(json!({"status": "NOT_RECEIVED", "transaction_hash": "0x2"}))
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use crate::{
+    definitions::transaction_type::TransactionType,
+    devnet::{
+        devnet_state::{FeeEstimate, TransactionReceipt},
+        rpc::types::{
+            BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction,
+            BroadcastedInvokeTransaction, BroadcastedTransaction, RpcEvent, RpcFelt,
+            RpcMessageToL1,
+        },
+    },
+};
+use cairo_vm::felt::Felt252;
+use num_traits::Num;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+
+/// A felt as the gateway writes it: a hex string prefixed with `0x`. Both hex and decimal
+/// strings are accepted, since the gateway sends calldata and signatures in decimal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GatewayFelt(pub Felt252);
+
+impl Serialize for GatewayFelt {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RpcFelt(self.0.clone()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GatewayFelt {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        match value.strip_prefix("0x") {
+            Some(digits) => Felt252::from_str_radix(digits, 16),
+            None => Felt252::from_str_radix(&value, 10),
+        }
+        .map(GatewayFelt)
+        .map_err(|_| serde::de::Error::custom(format!("invalid felt: {value}")))
+    }
+}
+
+impl From<GatewayFelt> for RpcFelt {
+    fn from(felt: GatewayFelt) -> Self {
+        RpcFelt(felt.0)
+    }
+}
+
+fn rpc_felts(values: Vec<GatewayFelt>) -> Vec<RpcFelt> {
+    values.into_iter().map(Into::into).collect()
+}
+
+// ---------------------------------------------------------------------
+//  Requests
+// ---------------------------------------------------------------------
+
+/// Query parameters of the feeder gateway endpoints. Each endpoint reads the ones it needs.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeederQuery {
+    /// Either a block number or one of the `latest` and `pending` tags.
+    pub block_number: Option<String>,
+    pub block_hash: Option<String>,
+    pub contract_address: Option<String>,
+    pub key: Option<String>,
+    pub transaction_hash: Option<String>,
+}
+
+/// A transaction of any type, as sent to `add_transaction` and `estimate_fee`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum GatewayTransaction {
+    #[serde(rename = "INVOKE_FUNCTION")]
+    InvokeFunction(GatewayInvokeTransaction),
+    #[serde(rename = "DECLARE")]
+    Declare(GatewayDeclareTransaction),
+    #[serde(rename = "DEPLOY_ACCOUNT")]
+    DeployAccount(GatewayDeployAccountTransaction),
+}
+
+impl From<GatewayTransaction> for BroadcastedTransaction {
+    fn from(tx: GatewayTransaction) -> Self {
+        match tx {
+            GatewayTransaction::InvokeFunction(tx) => BroadcastedTransaction::Invoke(tx.into()),
+            GatewayTransaction::Declare(tx) => BroadcastedTransaction::Declare(tx.into()),
+            GatewayTransaction::DeployAccount(tx) => {
+                BroadcastedTransaction::DeployAccount(tx.into())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GatewayInvokeTransaction {
+    pub sender_address: GatewayFelt,
+    pub calldata: Vec<GatewayFelt>,
+    pub max_fee: GatewayFelt,
+    pub version: GatewayFelt,
+    #[serde(default)]
+    pub signature: Vec<GatewayFelt>,
+    pub nonce: GatewayFelt,
+}
+
+impl From<GatewayInvokeTransaction> for BroadcastedInvokeTransaction {
+    fn from(tx: GatewayInvokeTransaction) -> Self {
+        BroadcastedInvokeTransaction {
+            sender_address: tx.sender_address.into(),
+            calldata: rpc_felts(tx.calldata),
+            max_fee: tx.max_fee.into(),
+            version: tx.version.into(),
+            signature: rpc_felts(tx.signature),
+            nonce: tx.nonce.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GatewayDeclareTransaction {
+    pub sender_address: GatewayFelt,
+    pub max_fee: GatewayFelt,
+    pub version: GatewayFelt,
+    #[serde(default)]
+    pub signature: Vec<GatewayFelt>,
+    pub nonce: GatewayFelt,
+    pub contract_class: serde_json::Value,
+}
+
+impl From<GatewayDeclareTransaction> for BroadcastedDeclareTransaction {
+    fn from(tx: GatewayDeclareTransaction) -> Self {
+        BroadcastedDeclareTransaction {
+            sender_address: tx.sender_address.into(),
+            max_fee: tx.max_fee.into(),
+            version: tx.version.into(),
+            signature: rpc_felts(tx.signature),
+            nonce: tx.nonce.into(),
+            contract_class: tx.contract_class,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct GatewayDeployAccountTransaction {
+    pub max_fee: GatewayFelt,
+    pub version: GatewayFelt,
+    #[serde(default)]
+    pub signature: Vec<GatewayFelt>,
+    pub nonce: GatewayFelt,
+    pub contract_address_salt: GatewayFelt,
+    pub constructor_calldata: Vec<GatewayFelt>,
+    pub class_hash: GatewayFelt,
+}
+
+impl From<GatewayDeployAccountTransaction> for BroadcastedDeployAccountTransaction {
+    fn from(tx: GatewayDeployAccountTransaction) -> Self {
+        BroadcastedDeployAccountTransaction {
+            max_fee: tx.max_fee.into(),
+            version: tx.version.into(),
+            signature: rpc_felts(tx.signature),
+            nonce: tx.nonce.into(),
+            contract_address_salt: tx.contract_address_salt.into(),
+            constructor_calldata: rpc_felts(tx.constructor_calldata),
+            class_hash: tx.class_hash.into(),
+        }
+    }
+}
+
+/// Body of `call_contract`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct GatewayCall {
+    pub contract_address: GatewayFelt,
+    pub entry_point_selector: GatewayFelt,
+    pub calldata: Vec<GatewayFelt>,
+}
+
+// ---------------------------------------------------------------------
+//  Responses
+// ---------------------------------------------------------------------
+
+/// Name the gateway gives to a transaction type.
+pub fn transaction_type_name(tx_type: TransactionType) -> &'static str {
+    match tx_type {
+        TransactionType::Declare => "DECLARE",
+        TransactionType::Deploy => "DEPLOY",
+        TransactionType::DeployAccount => "DEPLOY_ACCOUNT",
+        TransactionType::InitializeBlockInfo => "INITIALIZE_BLOCK_INFO",
+        TransactionType::InvokeFunction => "INVOKE_FUNCTION",
+        TransactionType::L1Handler => "L1_HANDLER",
+    }
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct GatewayFeeEstimate {
+    pub overall_fee: u128,
+    pub unit: &'static str,
+    pub gas_price: u128,
+    pub gas_usage: u128,
+}
+
+impl From<FeeEstimate> for GatewayFeeEstimate {
+    fn from(estimate: FeeEstimate) -> Self {
+        GatewayFeeEstimate {
+            overall_fee: estimate.overall_fee,
+            unit: "wei",
+            gas_price: estimate.gas_price,
+            gas_usage: estimate.gas_consumed,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct GatewayExecutionResources {
+    pub n_steps: usize,
+    pub n_memory_holes: usize,
+    pub builtin_instance_counter: HashMap<String, usize>,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct GatewayTransactionReceipt {
+    pub status: &'static str,
+    pub execution_status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_error: Option<String>,
+    /// Not set for transactions of the pending block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<RpcFelt>,
+    pub block_number: u64,
+    pub transaction_hash: RpcFelt,
+    pub transaction_index: usize,
+    pub l2_to_l1_messages: Vec<RpcMessageToL1>,
+    pub events: Vec<RpcEvent>,
+    pub actual_fee: RpcFelt,
+    pub execution_resources: GatewayExecutionResources,
+}
+
+impl GatewayTransactionReceipt {
+    /// Builds the receipt of a transaction, given its position in its block and the hash of
+    /// the block once closed.
+    pub fn new(
+        receipt: &TransactionReceipt,
+        transaction_index: usize,
+        block_hash: Option<&Felt252>,
+    ) -> Self {
+        let execution_info = &receipt.execution_info;
+        let mut builtin_instance_counter = execution_info.actual_resources.clone();
+        let n_steps = builtin_instance_counter.remove("n_steps").unwrap_or(0);
+        let n_memory_holes = builtin_instance_counter
+            .remove("n_memory_holes")
+            .unwrap_or(0);
+        // The L1 gas usage is charged for, but it isn't a Cairo resource.
+        builtin_instance_counter.remove("l1_gas_usage");
+
+        GatewayTransactionReceipt {
+            status: "ACCEPTED_ON_L2",
+            execution_status: if execution_info.is_reverted() {
+                "REVERTED"
+            } else {
+                "SUCCEEDED"
+            },
+            revert_error: execution_info
+                .revert_error
+                .as_ref()
+                .map(ToString::to_string),
+            block_hash: block_hash.cloned().map(RpcFelt),
+            block_number: receipt.block_number,
+            transaction_hash: RpcFelt(receipt.transaction_hash.clone()),
+            transaction_index,
+            l2_to_l1_messages: receipt.messages_sent.iter().map(Into::into).collect(),
+            events: receipt.events.iter().map(Into::into).collect(),
+            actual_fee: execution_info.actual_fee.into(),
+            execution_resources: GatewayExecutionResources {
+                n_steps,
+                n_memory_holes,
+                builtin_instance_counter,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+    use serde_json::json;
+
+    #[test]
+    fn gateway_felts_accept_hex_and_decimal() {
+        let felts: Vec<GatewayFelt> = serde_json::from_value(json!(["0x1f", "31"])).unwrap();
+
+        assert_eq!(felts, vec![GatewayFelt(31.into()), GatewayFelt(31.into())]);
+        assert_eq!(serde_json::to_value(&felts[1]).unwrap(), json!("0x1f"));
+        assert!(serde_json::from_value::<GatewayFelt>(json!("0xzz")).is_err());
+    }
+
+    #[test]
+    fn transactions_are_tagged_by_type() {
+        let tx = json!({
+            "type": "INVOKE_FUNCTION",
+            "sender_address": "0x1",
+            "calldata": ["2", "3"],
+            "max_fee": "0x0",
+            "version": "0x1",
+            "nonce": "0x0",
+        });
+
+        let tx = serde_json::from_value::<GatewayTransaction>(tx).unwrap();
+
+        assert_matches!(
+            BroadcastedTransaction::from(tx),
+            BroadcastedTransaction::Invoke(tx) if tx.calldata == vec![RpcFelt(2.into()), RpcFelt(3.into())]
+        );
+    }
+}
@@ -0,0 +1,9 @@
+pub mod blocks;
+pub mod devnet_state;
+pub mod dump;
+pub mod error;
+pub mod gateway;
+pub mod postman;
+pub mod predeployed;
+pub mod rpc;
+pub mod subscriptions;
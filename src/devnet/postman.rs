@@ -0,0 +1,478 @@
+use super::{
+    devnet_state::{DevnetState, TransactionReceipt},
+    error::DevnetError,
+};
+use crate::{
+    business_logic::{
+        execution::{
+            execution_entry_point::ExecutionEntryPoint,
+            objects::{TransactionExecutionContext, TransactionExecutionInfo},
+        },
+        fact_state::state::ExecutionResourcesManager,
+        transaction::error::TransactionError,
+    },
+    core::transaction_hash::starknet_transaction_hash::{
+        calculate_transaction_hash_common, TransactionHashPrefix,
+    },
+    definitions::transaction_type::TransactionType,
+    services::api::{contract_class::EntryPointType, messages::StarknetMessageToL1},
+    utils::Address,
+};
+use cairo_vm::felt::Felt252;
+use num_traits::{ToPrimitive, Zero};
+use std::{collections::HashMap, fmt::Debug};
+
+/// Version of the L1 handler transactions.
+const L1_HANDLER_VERSION: u64 = 0;
+
+/// Message sent from L1 to a contract on L2, where it is handled by one of its L1 handlers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageToL2 {
+    /// Address of the sender on L1.
+    pub from_address: Felt252,
+    pub to_address: Address,
+    /// Selector of the L1 handler.
+    pub entry_point_selector: Felt252,
+    pub payload: Vec<Felt252>,
+    /// Nonce given to the message on L1, which makes its transaction hash unique.
+    pub nonce: Felt252,
+}
+
+/// L1 side of the messaging: where the messages to L2 come from, and where the messages sent
+/// from L2 are delivered to.
+pub trait L1Backend: Debug + Send {
+    /// Takes the messages sent to L2 on L1 since the last call.
+    fn take_messages_to_l2(&mut self) -> Result<Vec<MessageToL2>, DevnetError>;
+
+    /// Puts back messages taken but not handled, so that the next call takes them again before
+    /// the ones sent since.
+    fn requeue_messages_to_l2(&mut self, messages: Vec<MessageToL2>) -> Result<(), DevnetError>;
+
+    /// Makes the messages sent from L2 available on L1.
+    fn send_messages_to_l1(&mut self, messages: &[StarknetMessageToL1]) -> Result<(), DevnetError>;
+}
+
+/// In-process L1, so that messaging can be tested without an L1 node. Messages to L2 are queued
+/// with `send_message_to_l2` until the next flush.
+#[derive(Clone, Debug, Default)]
+pub struct MockL1Backend {
+    pub(super) next_nonce: u64,
+    pub(super) messages_to_l2: Vec<MessageToL2>,
+    pub(super) messages_to_l1: Vec<StarknetMessageToL1>,
+}
+
+impl MockL1Backend {
+    /// Queues a message to L2, giving it the next nonce. Returns the queued message.
+    pub fn send_message_to_l2(
+        &mut self,
+        from_address: Felt252,
+        to_address: Address,
+        entry_point_selector: Felt252,
+        payload: Vec<Felt252>,
+    ) -> MessageToL2 {
+        let message = MessageToL2 {
+            from_address,
+            to_address,
+            entry_point_selector,
+            payload,
+            nonce: self.next_nonce.into(),
+        };
+        self.next_nonce += 1;
+        self.messages_to_l2.push(message.clone());
+        message
+    }
+
+    /// Returns the messages delivered from L2, in the order they were sent.
+    pub fn messages_to_l1(&self) -> &[StarknetMessageToL1] {
+        &self.messages_to_l1
+    }
+}
+
+impl L1Backend for MockL1Backend {
+    fn take_messages_to_l2(&mut self) -> Result<Vec<MessageToL2>, DevnetError> {
+        Ok(std::mem::take(&mut self.messages_to_l2))
+    }
+
+    fn requeue_messages_to_l2(&mut self, messages: Vec<MessageToL2>) -> Result<(), DevnetError> {
+        self.messages_to_l2.splice(0..0, messages);
+        Ok(())
+    }
+
+    fn send_messages_to_l1(&mut self, messages: &[StarknetMessageToL1]) -> Result<(), DevnetError> {
+        self.messages_to_l1.extend_from_slice(messages);
+        Ok(())
+    }
+}
+
+/// Messages exchanged with L1 by a flush.
+#[derive(Debug)]
+pub struct FlushedMessages {
+    pub messages_to_l1: Vec<StarknetMessageToL1>,
+    /// Messages to L2 handled by the flush.
+    pub messages_to_l2: Vec<MessageToL2>,
+    /// Hashes of the L1 handler transactions of the messages to L2.
+    pub generated_l2_transactions: Vec<Felt252>,
+    /// Error of the message to L2 the flush stopped at. That message and the ones after it are
+    /// put back in the queue of the backend.
+    pub error: Option<DevnetError>,
+}
+
+/// Messaging state of the devnet, kept along with the rest of its state so that it is dumped,
+/// loaded and aborted with it.
+#[derive(Clone, Debug, Default)]
+pub struct PostmanState {
+    /// L1 used by the postman when it isn't given another backend.
+    pub mock_l1: MockL1Backend,
+    /// Number of L2-to-L1 messages of the devnet already sent to L1.
+    pub(super) n_sent_messages: usize,
+    /// Nonces of the handled messages to L2, with the number of the block of their L1 handler
+    /// transaction.
+    pub(super) handled_nonces: HashMap<Felt252, u64>,
+}
+
+impl PostmanState {
+    /// Forgets the messages to L2 handled from a block on, and the messages to L1 sent after the
+    /// first `n_kept_messages` ones, after the blocks they were exchanged in were aborted.
+    pub(crate) fn discard_from(&mut self, block_number: u64, n_kept_messages: usize) {
+        self.handled_nonces
+            .retain(|_, handled_in| *handled_in < block_number);
+        self.n_sent_messages = self.n_sent_messages.min(n_kept_messages);
+    }
+}
+
+/// Carries messages between the devnet and an L1 backend, by default the mock L1 of the devnet
+/// state.
+#[derive(Debug, Default)]
+pub struct Postman {
+    backend: Option<Box<dyn L1Backend>>,
+}
+
+impl Postman {
+    pub fn new(backend: Box<dyn L1Backend>) -> Self {
+        Postman {
+            backend: Some(backend),
+        }
+    }
+
+    /// Sends to L1 the messages sent from L2 since the last flush, and handles on L2 the
+    /// messages sent to it on L1, in order. Fails only if the backend does; a message to L2 that
+    /// can't be handled stops the flush, which returns what it handled before it.
+    pub fn flush(&mut self, devnet: &mut DevnetState) -> Result<FlushedMessages, DevnetError> {
+        let messages_log = &devnet.starknet_state.l2_to_l1_messages_log;
+        let n_messages = messages_log.len();
+        let messages_to_l1 = messages_log[devnet.postman.n_sent_messages..].to_vec();
+        let backend = self.backend(&mut devnet.postman.mock_l1);
+        backend.send_messages_to_l1(&messages_to_l1)?;
+        devnet.postman.n_sent_messages = n_messages;
+        let mut messages_to_l2 = backend.take_messages_to_l2()?;
+
+        let mut generated_l2_transactions = Vec::new();
+        let mut error = None;
+        for (index, message) in messages_to_l2.iter().enumerate() {
+            match devnet.handle_message_to_l2(message) {
+                Ok(receipt) => generated_l2_transactions.push(receipt.transaction_hash.clone()),
+                Err(handling_error) => {
+                    error = Some((index, handling_error));
+                    break;
+                }
+            }
+        }
+        let error = match error {
+            Some((index, error)) => {
+                let unhandled = messages_to_l2.split_off(index);
+                self.backend(&mut devnet.postman.mock_l1)
+                    .requeue_messages_to_l2(unhandled)?;
+                Some(error)
+            }
+            None => None,
+        };
+
+        Ok(FlushedMessages {
+            messages_to_l1,
+            messages_to_l2,
+            generated_l2_transactions,
+            error,
+        })
+    }
+
+    /// Returns the backend of the postman, or the given mock L1 if it has none.
+    fn backend<'a>(&'a mut self, mock_l1: &'a mut MockL1Backend) -> &'a mut dyn L1Backend {
+        match &mut self.backend {
+            Some(backend) => backend.as_mut(),
+            None => mock_l1,
+        }
+    }
+}
+
+impl DevnetState {
+    /// Executes the L1 handler transaction of a message sent from L1, and records its receipt as
+    /// the one of any transaction. A failed handler leaves no receipt, and a message whose nonce
+    /// was already handled is rejected.
+    pub fn handle_message_to_l2(
+        &mut self,
+        message: &MessageToL2,
+    ) -> Result<&TransactionReceipt, DevnetError> {
+        if self.postman.handled_nonces.contains_key(&message.nonce) {
+            return Err(DevnetError::MessageNonceUsed(format!(
+                "0x{}",
+                message.nonce.to_str_radix(16)
+            )));
+        }
+        self.class_hash_at(&message.to_address)?;
+        let mut calldata = vec![message.from_address.clone()];
+        calldata.extend_from_slice(&message.payload);
+
+        let general_config = &self.starknet_state.general_config;
+        let transaction_hash = calculate_transaction_hash_common(
+            TransactionHashPrefix::L1Handler,
+            L1_HANDLER_VERSION.into(),
+            &message.to_address,
+            message.entry_point_selector.clone(),
+            &calldata,
+            0,
+            general_config.starknet_os_config().chain_id().to_felt(),
+            &[message.nonce.clone()],
+        )
+        .map_err(TransactionError::from)?;
+        let execution_context = TransactionExecutionContext::new(
+            Address(Felt252::zero()),
+            transaction_hash.clone(),
+            Vec::new(),
+            0,
+            message.nonce.clone(),
+            general_config.invoke_tx_max_n_steps(),
+            L1_HANDLER_VERSION,
+        );
+        let call = ExecutionEntryPoint::new(
+            message.to_address.clone(),
+            calldata,
+            message.entry_point_selector.clone(),
+            Address(Felt252::zero()),
+            EntryPointType::L1Handler,
+            None,
+            None,
+        );
+
+        // The call runs in a layer over the state, which keeps its writes only if it succeeds.
+        let call_info = call
+            .execute(
+                &mut self.starknet_state.state,
+                general_config,
+                &mut ExecutionResourcesManager::default(),
+                &execution_context,
+            )
+            .map_err(|error| DevnetError::TransactionFailed(error.to_string()))?;
+        let execution_info = TransactionExecutionInfo::new(
+            None,
+            Some(call_info),
+            None,
+            0,
+            HashMap::new(),
+            Some(TransactionType::L1Handler),
+        );
+        let events = execution_info.get_sorted_events()?;
+        let messages_sent = execution_info.get_sorted_l2_to_l1_messages()?;
+        self.starknet_state.add_messages(&messages_sent);
+
+        let block_number = self.block_number();
+        self.starknet_state.events.add_events(
+            &events,
+            block_number,
+            Some(transaction_hash.clone()),
+        );
+        self.postman
+            .handled_nonces
+            .insert(message.nonce.clone(), block_number);
+        // The mock L1 gives the following nonces, as the messages to L2 share the nonces of L1.
+        if let Some(nonce) = message.nonce.to_u64() {
+            let next_nonce = &mut self.postman.mock_l1.next_nonce;
+            *next_nonce = (*next_nonce).max(nonce.saturating_add(1));
+        }
+        Ok(self.add_receipt(TransactionReceipt {
+            transaction_hash,
+            tx_type: TransactionType::L1Handler,
+            block_number,
+            contract_address: None,
+            events,
+            messages_sent,
+            execution_info,
+        }))
+    }
+
+    /// Consumes on L1 a message sent from L2. Returns the hash of the message.
+    pub fn consume_message_from_l2(
+        &mut self,
+        message: &StarknetMessageToL1,
+    ) -> Result<Vec<u8>, DevnetError> {
+        let message_hash = message.get_hash();
+        self.starknet_state
+            .consume_message_hash(message_hash.clone())?;
+        Ok(message_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        services::api::contract_class::ContractClass,
+        testing::starknet_state_error::StarknetStateError, utils::calculate_sn_keccak,
+    };
+    use coverage_helper::test;
+    use num_traits::Num;
+    use std::path::PathBuf;
+
+    /// Address of the L1 contract that the `deposit` handler of the l1l2 contract accepts.
+    const L1_CONTRACT_ADDRESS: &str = "8359E4B0152ed5A731162D3c7B0D8D56edB165A0";
+
+    /// Returns a devnet with a closed block and the l1l2 contract deployed, along with a deposit
+    /// message to the contract queued on the mock L1.
+    fn setup_deposit() -> (DevnetState, MessageToL2) {
+        let mut devnet = DevnetState::default();
+        let contract_class =
+            ContractClass::try_from(PathBuf::from("starknet_programs/l1l2.json")).unwrap();
+        let (address, _) = devnet
+            .starknet_state
+            .deploy(contract_class, vec![], Address(1.into()))
+            .unwrap();
+        devnet.create_block();
+
+        let message = devnet.postman.mock_l1.send_message_to_l2(
+            Felt252::from_str_radix(L1_CONTRACT_ADDRESS, 16).unwrap(),
+            address,
+            Felt252::from_bytes_be(&calculate_sn_keccak(b"deposit")),
+            vec![1.into(), 10.into()],
+        );
+        (devnet, message)
+    }
+
+    #[test]
+    fn flush_sends_the_new_messages_to_l1() {
+        let mut devnet = DevnetState::default();
+        let message =
+            StarknetMessageToL1::new(Address(1.into()), Address(2.into()), vec![3.into()]);
+        devnet
+            .starknet_state
+            .l2_to_l1_messages_log
+            .push(message.clone());
+        let mut postman = Postman::default();
+
+        let flushed = postman.flush(&mut devnet).unwrap();
+        assert_eq!(flushed.messages_to_l1.len(), 1);
+        assert_eq!(flushed.messages_to_l1[0].get_hash(), message.get_hash());
+        assert!(flushed.messages_to_l2.is_empty());
+
+        let flushed = postman.flush(&mut devnet).unwrap();
+        assert!(flushed.messages_to_l1.is_empty());
+    }
+
+    #[test]
+    fn flush_handles_the_messages_queued_on_the_mock_l1() {
+        let (mut devnet, message) = setup_deposit();
+
+        let flushed = Postman::default().flush(&mut devnet).unwrap();
+        assert_eq!(flushed.messages_to_l2, vec![message.clone()]);
+        assert_eq!(flushed.generated_l2_transactions.len(), 1);
+        assert!(devnet
+            .receipt(&flushed.generated_l2_transactions[0])
+            .is_some());
+
+        let flushed = Postman::default().flush(&mut devnet).unwrap();
+        assert!(flushed.messages_to_l2.is_empty());
+    }
+
+    #[test]
+    fn reused_nonces_are_rejected_until_aborted() {
+        let (mut devnet, message) = setup_deposit();
+        let block_number = devnet.block_number();
+        devnet.handle_message_to_l2(&message).unwrap();
+
+        assert_matches!(
+            devnet.handle_message_to_l2(&message),
+            Err(DevnetError::MessageNonceUsed(nonce)) if nonce == "0x0"
+        );
+        // The message is still queued on the mock L1, with the nonce already handled, so the
+        // flush puts it back.
+        let flushed = Postman::default().flush(&mut devnet).unwrap();
+        assert!(flushed.messages_to_l2.is_empty());
+        assert_matches!(flushed.error, Some(DevnetError::MessageNonceUsed(_)));
+        assert_eq!(devnet.postman.mock_l1.messages_to_l2, vec![message.clone()]);
+
+        devnet.abort_blocks(block_number).unwrap();
+        let flushed = Postman::default().flush(&mut devnet).unwrap();
+        assert_eq!(flushed.messages_to_l2, vec![message]);
+        assert!(flushed.error.is_none());
+    }
+
+    #[test]
+    fn messages_to_undeployed_contracts_fail() {
+        let mut devnet = DevnetState::default();
+        let mut backend = MockL1Backend::default();
+        let message = backend.send_message_to_l2(1.into(), Address(2.into()), 3.into(), vec![]);
+        assert_eq!(message.nonce, Felt252::zero());
+        let mut postman = Postman::new(Box::new(backend));
+
+        let flushed = postman.flush(&mut devnet).unwrap();
+        assert!(flushed.generated_l2_transactions.is_empty());
+        assert_matches!(
+            flushed.error,
+            Some(DevnetError::ContractNotFound(address)) if address == Address(2.into())
+        );
+        assert_matches!(
+            devnet.handle_message_to_l2(&message),
+            Err(DevnetError::ContractNotFound(_))
+        );
+    }
+
+    #[test]
+    fn flush_keeps_the_messages_handled_before_a_failure() {
+        let (mut devnet, deposit) = setup_deposit();
+        let undeliverable = devnet.postman.mock_l1.send_message_to_l2(
+            1.into(),
+            Address(2.into()),
+            3.into(),
+            vec![],
+        );
+        let next_deposit = devnet.postman.mock_l1.send_message_to_l2(
+            deposit.from_address.clone(),
+            deposit.to_address.clone(),
+            deposit.entry_point_selector.clone(),
+            deposit.payload.clone(),
+        );
+
+        let flushed = Postman::default().flush(&mut devnet).unwrap();
+        assert_eq!(flushed.messages_to_l2, vec![deposit]);
+        assert_eq!(flushed.generated_l2_transactions.len(), 1);
+        assert_matches!(flushed.error, Some(DevnetError::ContractNotFound(_)));
+        assert_eq!(
+            devnet.postman.mock_l1.messages_to_l2,
+            vec![undeliverable, next_deposit]
+        );
+    }
+
+    #[test]
+    fn consumed_messages_must_have_been_sent() {
+        let mut devnet = DevnetState::default();
+        let message =
+            StarknetMessageToL1::new(Address(1.into()), Address(2.into()), vec![3.into()]);
+
+        assert_matches!(
+            devnet.consume_message_from_l2(&message),
+            Err(DevnetError::StarknetState(
+                StarknetStateError::InvalidMessageHash
+            ))
+        );
+        devnet
+            .starknet_state
+            .l2_to_l1_messages
+            .insert(message.get_hash(), 1);
+        assert_eq!(
+            devnet.consume_message_from_l2(&message).unwrap(),
+            message.get_hash()
+        );
+        assert_matches!(
+            devnet.consume_message_from_l2(&message),
+            Err(DevnetError::StarknetState(_))
+        );
+    }
+}
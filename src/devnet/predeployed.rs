@@ -0,0 +1,213 @@
+use super::{devnet_state::DevnetState, error::DevnetError};
+use crate::{
+    business_logic::{
+        state::state_api::{State, StateReader},
+        transaction::fee::{get_fee_token_balance, FeeType},
+    },
+    core::contract_address::starknet_contract_address::compute_class_hash,
+    services::api::contract_class::ContractClass,
+    testing::{
+        accounts::{Account, KeyPair, ACCOUNT_CONTRACT_PATH},
+        starknet_state_error::StarknetStateError,
+    },
+    utils::{
+        get_erc20_balance_var_addresses, get_storage_var_address, split_uint256, Address, ClassHash,
+    },
+};
+use cairo_vm::felt::Felt252;
+use num_bigint::BigUint;
+use num_traits::One;
+use std::path::{Path, PathBuf};
+
+/// Compiled ERC20 contract deployed as the fee token.
+pub const FEE_TOKEN_CONTRACT_PATH: &str = "starknet_programs/ERC20.json";
+
+/// Accounts and fee token deployed when the devnet starts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PredeployConfig {
+    /// Number of accounts to deploy.
+    pub accounts: usize,
+    /// Seed of the key pair of the first account. The following accounts use the next seeds.
+    pub seed: u64,
+    /// Fee token balance each account starts with.
+    pub initial_balance: BigUint,
+}
+
+impl Default for PredeployConfig {
+    fn default() -> Self {
+        PredeployConfig {
+            accounts: 10,
+            seed: 0,
+            initial_balance: BigUint::from(10_u128.pow(21)),
+        }
+    }
+}
+
+impl DevnetState {
+    /// Deploys the fee token and the accounts of the config, funding each account with its
+    /// initial balance. Returns the accounts.
+    pub fn predeploy(&mut self, config: &PredeployConfig) -> Result<Vec<Account>, DevnetError> {
+        self.predeploy_fee_token()?;
+        self.predeclare(Path::new(ACCOUNT_CONTRACT_PATH))?;
+
+        (config.seed..)
+            .take(config.accounts)
+            .map(|seed| {
+                let key_pair = KeyPair::from_seed(seed)?;
+                let account = Account::predeploy(&mut self.starknet_state, key_pair)?;
+                self.mint(account.address(), &config.initial_balance)?;
+                Ok(account)
+            })
+            .collect()
+    }
+
+    /// Deploys the ERC20 contract at the fee token address of the config, without executing
+    /// any transaction.
+    pub fn predeploy_fee_token(&mut self) -> Result<(), DevnetError> {
+        let fee_token_address = self.fee_token_address();
+        let class_hash = self.predeclare(Path::new(FEE_TOKEN_CONTRACT_PATH))?;
+
+        self.starknet_state
+            .state
+            .deploy_contract(fee_token_address, class_hash)?;
+        Ok(())
+    }
+
+    /// Adds `amount` to the fee token balance of an address, and to the total supply of the
+    /// token. Returns the new balance. Fails without minting if either would not fit in a
+    /// Uint256.
+    pub fn mint(&mut self, address: &Address, amount: &BigUint) -> Result<BigUint, DevnetError> {
+        let balance = self.account_balance(address)? + amount;
+        let (balance_low_key, balance_high_key) =
+            get_erc20_balance_var_addresses(address).map_err(StarknetStateError::from)?;
+
+        let total_supply_var =
+            get_storage_var_address("ERC20_total_supply", &[]).map_err(StarknetStateError::from)?;
+        let total_supply_low_key = total_supply_var.to_be_bytes();
+        let total_supply_high_key = (total_supply_var + Felt252::one()).to_be_bytes();
+        let total_supply =
+            self.fee_token_uint256(total_supply_low_key, total_supply_high_key)? + amount;
+
+        if balance.bits() > 256 || total_supply.bits() > 256 {
+            return Err(DevnetError::MintOverflow(amount.to_string()));
+        }
+        self.set_fee_token_uint256(balance_low_key, balance_high_key, &balance);
+        self.set_fee_token_uint256(total_supply_low_key, total_supply_high_key, &total_supply);
+
+        Ok(balance)
+    }
+
+    /// Returns the fee token balance of an address.
+    pub fn account_balance(&mut self, address: &Address) -> Result<BigUint, DevnetError> {
+        Ok(get_fee_token_balance(
+            &mut self.starknet_state.state,
+            &self.starknet_state.general_config,
+            FeeType::Eth,
+            address,
+        )?)
+    }
+
+    /// Declares the class of a compiled contract without executing any transaction, keeping
+    /// its definition. Returns its class hash.
+    fn predeclare(&mut self, path: &Path) -> Result<ClassHash, DevnetError> {
+        let contract_class =
+            ContractClass::try_from(PathBuf::from(path)).map_err(StarknetStateError::from)?;
+        let class_hash = compute_class_hash(&contract_class)
+            .map_err(StarknetStateError::from)?
+            .to_be_bytes();
+
+        self.starknet_state
+            .state
+            .set_contract_class(&class_hash, &contract_class)?;
+        self.add_class_definition_from_file(class_hash, path)?;
+        Ok(class_hash)
+    }
+
+    fn fee_token_uint256(
+        &mut self,
+        low_key: ClassHash,
+        high_key: ClassHash,
+    ) -> Result<BigUint, DevnetError> {
+        let fee_token_address = self.fee_token_address();
+        let state = &mut self.starknet_state.state;
+        let low = state.get_storage_at(&(fee_token_address.clone(), low_key))?;
+        let high = state.get_storage_at(&(fee_token_address, high_key))?;
+        Ok(low.to_biguint() + (high.to_biguint() << 128))
+    }
+
+    fn set_fee_token_uint256(&mut self, low_key: ClassHash, high_key: ClassHash, value: &BigUint) {
+        let fee_token_address = self.fee_token_address();
+        let (low, high) = split_uint256(value);
+        let state = &mut self.starknet_state.state;
+        state.set_storage_at(&(fee_token_address.clone(), low_key), low);
+        state.set_storage_at(&(fee_token_address, high_key), high);
+    }
+
+    fn fee_token_address(&self) -> Address {
+        FeeType::Eth.fee_token_address(&self.starknet_state.general_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+
+    #[test]
+    fn mint_adds_to_the_balance() {
+        let mut devnet = DevnetState::default();
+        let address = Address(1.into());
+        let amount = BigUint::one() << 130;
+
+        assert_eq!(devnet.mint(&address, &amount).unwrap(), amount);
+        assert_eq!(
+            devnet.mint(&address, &BigUint::one()).unwrap(),
+            amount.clone() + 1_u32
+        );
+        assert_eq!(devnet.account_balance(&address).unwrap(), amount + 1_u32);
+        assert_eq!(
+            devnet.account_balance(&Address(2.into())).unwrap(),
+            BigUint::default()
+        );
+    }
+
+    #[test]
+    fn mint_is_bounded_to_uint256() {
+        let mut devnet = DevnetState::default();
+        let max = (BigUint::one() << 256) - 1_u32;
+
+        assert_eq!(devnet.mint(&Address(1.into()), &max).unwrap(), max);
+        // The balance of another address would fit, but not the total supply.
+        assert_matches!(
+            devnet.mint(&Address(2.into()), &BigUint::one()),
+            Err(DevnetError::MintOverflow(_))
+        );
+        assert_eq!(
+            devnet.account_balance(&Address(2.into())).unwrap(),
+            BigUint::default()
+        );
+        assert_eq!(devnet.account_balance(&Address(1.into())).unwrap(), max);
+    }
+
+    #[test]
+    fn predeployed_accounts_are_funded() {
+        let mut devnet = DevnetState::default();
+        let config = PredeployConfig {
+            accounts: 2,
+            seed: 7,
+            initial_balance: BigUint::from(1000_u32),
+        };
+
+        let accounts = devnet.predeploy(&config).unwrap();
+
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[1].key_pair(), &KeyPair::from_seed(8).unwrap());
+        for account in &accounts {
+            assert_eq!(
+                devnet.account_balance(account.address()).unwrap(),
+                BigUint::from(1000_u32)
+            );
+        }
+        assert!(devnet.dump().is_ok());
+    }
+}
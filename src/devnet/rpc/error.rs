@@ -0,0 +1,117 @@
+use crate::{
+    business_logic::transaction::error::TransactionError, devnet::error::DevnetError,
+    testing::starknet_state_error::StarknetStateError,
+};
+use thiserror::Error;
+
+/// Errors of the JSON-RPC endpoint. Each one has the code given to it by the JSON-RPC 2.0 or
+/// the Starknet JSON-RPC specification.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RpcError {
+    #[error("Parse error")]
+    ParseError,
+    #[error("Invalid request")]
+    InvalidRequest,
+    #[error("Method not found")]
+    MethodNotFound,
+    #[error("Invalid params: {0}")]
+    InvalidParams(String),
+    #[error("Internal error: {0}")]
+    Internal(String),
+    #[error("Contract not found")]
+    ContractNotFound,
+    #[error("Block not found")]
+    BlockNotFound,
+    #[error("Class hash not found")]
+    ClassHashNotFound,
+    #[error("Transaction hash not found")]
+    TxnHashNotFound,
+    #[error("There are no blocks")]
+    NoBlocks,
+    #[error("Requested page size is too big")]
+    PageSizeTooBig,
+    #[error("The supplied continuation token is invalid or unknown")]
+    InvalidContinuationToken,
+    #[error("Contract error: {0}")]
+    ContractError(String),
+    #[error("Class already declared")]
+    ClassAlreadyDeclared,
+    #[error("Invalid transaction nonce")]
+    InvalidTransactionNonce,
+    #[error("Max fee is smaller than the minimal transaction cost")]
+    InsufficientMaxFee,
+    #[error("Account balance is smaller than the transaction's max_fee")]
+    InsufficientAccountBalance,
+    #[error("Account validation failed: {0}")]
+    ValidationFailure(String),
+    #[error("The transaction version is not supported")]
+    UnsupportedTxVersion,
+    #[error("Invalid subscription id")]
+    InvalidSubscriptionId,
+}
+
+impl RpcError {
+    pub fn code(&self) -> i64 {
+        match self {
+            RpcError::ParseError => -32700,
+            RpcError::InvalidRequest => -32600,
+            RpcError::MethodNotFound => -32601,
+            RpcError::InvalidParams(_) => -32602,
+            RpcError::Internal(_) => -32603,
+            RpcError::ContractNotFound => 20,
+            RpcError::BlockNotFound => 24,
+            RpcError::ClassHashNotFound => 28,
+            RpcError::TxnHashNotFound => 29,
+            RpcError::PageSizeTooBig => 31,
+            RpcError::NoBlocks => 32,
+            RpcError::InvalidContinuationToken => 33,
+            RpcError::ContractError(_) => 40,
+            RpcError::ClassAlreadyDeclared => 51,
+            RpcError::InvalidTransactionNonce => 52,
+            RpcError::InsufficientMaxFee => 53,
+            RpcError::InsufficientAccountBalance => 54,
+            RpcError::ValidationFailure(_) => 55,
+            RpcError::UnsupportedTxVersion => 61,
+            RpcError::InvalidSubscriptionId => 66,
+        }
+    }
+}
+
+/// Maps the error of a rejected transaction. Failed executions are reverted instead of
+/// rejected, so the remaining errors come from the validation of the transaction.
+impl From<TransactionError> for RpcError {
+    fn from(error: TransactionError) -> Self {
+        match error {
+            TransactionError::InvalidTransactionNonce(..) => RpcError::InvalidTransactionNonce,
+            TransactionError::MaxFeeTooLow(..) => RpcError::InsufficientMaxFee,
+            TransactionError::InsufficientBalance(..) => RpcError::InsufficientAccountBalance,
+            TransactionError::ClassAlreadyDeclared(_) => RpcError::ClassAlreadyDeclared,
+            error => RpcError::ValidationFailure(error.to_string()),
+        }
+    }
+}
+
+impl From<StarknetStateError> for RpcError {
+    fn from(error: StarknetStateError) -> Self {
+        match error {
+            StarknetStateError::Transaction(error) => error.into(),
+            error => RpcError::Internal(error.to_string()),
+        }
+    }
+}
+
+impl From<DevnetError> for RpcError {
+    fn from(error: DevnetError) -> Self {
+        match error {
+            DevnetError::ContractNotFound(_) => RpcError::ContractNotFound,
+            DevnetError::BlockNotFound => RpcError::BlockNotFound,
+            DevnetError::InvalidTransaction(message) => RpcError::InvalidParams(message),
+            DevnetError::UnsupportedTxVersion(_) => RpcError::UnsupportedTxVersion,
+            DevnetError::CallFailed(error) => RpcError::ContractError(error.to_string()),
+            DevnetError::TransactionFailed(message) => RpcError::ContractError(message),
+            DevnetError::Transaction(error) => error.into(),
+            DevnetError::StarknetState(error) => error.into(),
+            error => RpcError::Internal(error.to_string()),
+        }
+    }
+}
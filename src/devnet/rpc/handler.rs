@@ -0,0 +1,610 @@
+use super::{
+    error::RpcError,
+    types::{
+        BlockId, BlockTag, BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction,
+        BroadcastedInvokeTransaction, BroadcastedTransaction, EmittedEvent, EventFilter,
+        EventSubscriptionFilter, EventsChunk, FunctionCall, RpcBlockHeader, RpcFeeEstimate,
+        RpcFelt, RpcTransactionReceipt, RpcTransactionStatus,
+    },
+};
+use crate::{
+    definitions::general_config::StarknetChainId,
+    devnet::{
+        devnet_state::DevnetState,
+        subscriptions::{Notification, Subscription, SubscriptionId},
+    },
+    testing::event_store::EventQuery,
+    utils::Address,
+};
+use cairo_vm::felt::Felt252;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Maximum number of events returned by a single `starknet_getEvents` request.
+pub const MAX_EVENTS_CHUNK_SIZE: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcErrorObject>,
+}
+
+impl RpcResponse {
+    fn new(id: Value, result: Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(error) => (
+                None,
+                Some(RpcErrorObject {
+                    code: error.code(),
+                    message: error.to_string(),
+                }),
+            ),
+        };
+
+        RpcResponse {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+/// Handles the body of a JSON-RPC 2.0 request, which holds either a single request or a batch
+/// of them. Returns the response to send back, if any, since notifications (requests without an
+/// `id`) aren't answered.
+pub fn handle_rpc_body(devnet: &mut DevnetState, body: &[u8]) -> Option<Value> {
+    handle_body(devnet, body, None)
+}
+
+/// Handles the body of a JSON-RPC 2.0 request received through a WebSocket connection, where the
+/// subscription methods are available too. `subscriptions` are the ones opened through the
+/// connection, the only ones it can close.
+pub fn handle_ws_body(
+    devnet: &mut DevnetState,
+    body: &[u8],
+    subscriptions: &mut Vec<SubscriptionId>,
+) -> Option<Value> {
+    handle_body(devnet, body, Some(subscriptions))
+}
+
+/// Returns the JSON-RPC notification sent to a subscription.
+pub fn subscription_notification(id: SubscriptionId, notification: &Notification) -> Value {
+    let (method, result) = match notification {
+        Notification::NewHead(block_info) => (
+            "starknet_subscriptionNewHeads",
+            json!(RpcBlockHeader::from(block_info)),
+        ),
+        Notification::Event(event) => (
+            "starknet_subscriptionEvents",
+            json!(EmittedEvent::from(event)),
+        ),
+        Notification::TransactionStatus {
+            transaction_hash,
+            status,
+        } => (
+            "starknet_subscriptionTransactionStatus",
+            json!(RpcTransactionStatus {
+                transaction_hash: RpcFelt(transaction_hash.clone()),
+                finality_status: status.as_str(),
+            }),
+        ),
+    };
+    json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": {"subscription_id": id, "result": result},
+    })
+}
+
+fn handle_body(
+    devnet: &mut DevnetState,
+    body: &[u8],
+    mut subscriptions: Option<&mut Vec<SubscriptionId>>,
+) -> Option<Value> {
+    match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Array(requests)) if !requests.is_empty() => {
+            let responses: Vec<Value> = requests
+                .into_iter()
+                .filter_map(|request| {
+                    handle_rpc_request(devnet, request, subscriptions.as_deref_mut())
+                })
+                .collect();
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        Ok(Value::Array(_)) => Some(error_response(RpcError::InvalidRequest)),
+        Ok(request) => handle_rpc_request(devnet, request, subscriptions),
+        Err(_) => Some(error_response(RpcError::ParseError)),
+    }
+}
+
+fn handle_rpc_request(
+    devnet: &mut DevnetState,
+    request: Value,
+    subscriptions: Option<&mut Vec<SubscriptionId>>,
+) -> Option<Value> {
+    let is_notification = request.get("id").is_none();
+    let request = match serde_json::from_value::<RpcRequest>(request) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        _ => return Some(error_response(RpcError::InvalidRequest)),
+    };
+
+    let result = match subscriptions {
+        Some(subscriptions) if is_subscription_method(&request.method) => {
+            dispatch_subscription(devnet, &request.method, request.params, subscriptions)
+        }
+        _ => dispatch(devnet, &request.method, request.params),
+    };
+    (!is_notification).then(|| json!(RpcResponse::new(request.id, result)))
+}
+
+fn error_response(error: RpcError) -> Value {
+    json!(RpcResponse::new(Value::Null, Err(error)))
+}
+
+fn dispatch(devnet: &mut DevnetState, method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "starknet_chainId" => to_value(RpcFelt(chain_id(devnet).to_felt())),
+        "starknet_blockNumber" => to_value(devnet.latest_block_number().ok_or(RpcError::NoBlocks)?),
+        "starknet_call" => {
+            let (request, block_id): (FunctionCall, BlockId) =
+                parse_params(params, &["request", "block_id"])?;
+            check_block_id(devnet, &block_id)?;
+            let retdata = devnet.call(
+                Address(request.contract_address.0),
+                request.entry_point_selector.0,
+                request.calldata.into_iter().map(|felt| felt.0).collect(),
+            )?;
+            to_value(retdata.into_iter().map(RpcFelt).collect::<Vec<_>>())
+        }
+        "starknet_getStorageAt" => {
+            let (contract_address, key, block_id): (RpcFelt, RpcFelt, BlockId) =
+                parse_params(params, &["contract_address", "key", "block_id"])?;
+            check_block_id(devnet, &block_id)?;
+            let value = devnet.storage_at(&Address(contract_address.0), &key.0)?;
+            to_value(RpcFelt(value))
+        }
+        "starknet_getNonce" => {
+            let (block_id, contract_address): (BlockId, RpcFelt) =
+                parse_params(params, &["block_id", "contract_address"])?;
+            check_block_id(devnet, &block_id)?;
+            let nonce = devnet.nonce_at(&Address(contract_address.0))?;
+            to_value(RpcFelt(nonce))
+        }
+        "starknet_getClassHashAt" => {
+            let (block_id, contract_address): (BlockId, RpcFelt) =
+                parse_params(params, &["block_id", "contract_address"])?;
+            check_block_id(devnet, &block_id)?;
+            let class_hash = devnet.class_hash_at(&Address(contract_address.0))?;
+            to_value(RpcFelt(Felt252::from_bytes_be(&class_hash)))
+        }
+        "starknet_getClass" => {
+            let (block_id, class_hash): (BlockId, RpcFelt) =
+                parse_params(params, &["block_id", "class_hash"])?;
+            check_block_id(devnet, &block_id)?;
+            devnet
+                .class_definition(&class_hash.0.to_be_bytes())
+                .cloned()
+                .ok_or(RpcError::ClassHashNotFound)
+        }
+        "starknet_addInvokeTransaction" => {
+            let (tx,): (BroadcastedInvokeTransaction,) =
+                parse_params(params, &["invoke_transaction"])?;
+            let tx = tx.into_transaction(chain_id(devnet))?;
+            let receipt = devnet.add_transaction(tx)?;
+            Ok(json!({ "transaction_hash": RpcFelt(receipt.transaction_hash.clone()) }))
+        }
+        "starknet_addDeclareTransaction" => {
+            let (tx,): (BroadcastedDeclareTransaction,) =
+                parse_params(params, &["declare_transaction"])?;
+            let (tx, definition) = tx.into_transaction(chain_id(devnet))?;
+            let class_hash = tx.contract_hash();
+            let receipt = devnet.add_transaction(tx)?;
+            let tx_hash = RpcFelt(receipt.transaction_hash.clone());
+            devnet.add_class_definition(class_hash, definition);
+            Ok(json!({
+                "transaction_hash": tx_hash,
+                "class_hash": RpcFelt(Felt252::from_bytes_be(&class_hash)),
+            }))
+        }
+        "starknet_addDeployAccountTransaction" => {
+            let (tx,): (BroadcastedDeployAccountTransaction,) =
+                parse_params(params, &["deploy_account_transaction"])?;
+            let tx = tx.into_transaction(chain_id(devnet))?;
+            let contract_address = tx.contract_address();
+            let receipt = devnet.add_transaction(tx)?;
+            Ok(json!({
+                "transaction_hash": RpcFelt(receipt.transaction_hash.clone()),
+                "contract_address": RpcFelt(contract_address.0),
+            }))
+        }
+        "starknet_estimateFee" => {
+            let (request, block_id): (Vec<BroadcastedTransaction>, BlockId) =
+                parse_params(params, &["request", "block_id"])?;
+            check_block_id(devnet, &block_id)?;
+            let chain_id = chain_id(devnet);
+            let txs = request
+                .into_iter()
+                .map(|tx| tx.into_transaction(chain_id))
+                .collect::<Result<Vec<_>, _>>()?;
+            let estimates = devnet.estimate_fee(txs)?;
+            to_value(
+                estimates
+                    .into_iter()
+                    .map(RpcFeeEstimate::from)
+                    .collect::<Vec<_>>(),
+            )
+        }
+        "starknet_getTransactionReceipt" => {
+            let (transaction_hash,): (RpcFelt,) = parse_params(params, &["transaction_hash"])?;
+            let receipt = devnet
+                .receipt(&transaction_hash.0)
+                .ok_or(RpcError::TxnHashNotFound)?;
+            to_value(RpcTransactionReceipt::from(receipt))
+        }
+        "starknet_getEvents" => {
+            let (filter,): (EventFilter,) = parse_params(params, &["filter"])?;
+            to_value(get_events(devnet, &filter)?)
+        }
+        _ => Err(RpcError::MethodNotFound),
+    }
+}
+
+fn is_subscription_method(method: &str) -> bool {
+    method.starts_with("starknet_subscribe") || method == "starknet_unsubscribe"
+}
+
+fn dispatch_subscription(
+    devnet: &mut DevnetState,
+    method: &str,
+    params: Value,
+    subscriptions: &mut Vec<SubscriptionId>,
+) -> Result<Value, RpcError> {
+    let subscription = match method {
+        "starknet_subscribeNewHeads" => Subscription::NewHeads,
+        "starknet_subscribeEvents" => {
+            let filter: EventSubscriptionFilter = match params {
+                Value::Null => EventSubscriptionFilter::default(),
+                params => serde_json::from_value(params)
+                    .map_err(|error| RpcError::InvalidParams(error.to_string()))?,
+            };
+            Subscription::Events {
+                address: filter.from_address.map(|address| Address(address.0)),
+                keys: filter
+                    .keys
+                    .into_iter()
+                    .map(|keys| keys.into_iter().map(|key| key.0).collect())
+                    .collect(),
+            }
+        }
+        "starknet_subscribeTransactionStatus" => {
+            let (transaction_hash,): (RpcFelt,) = parse_params(params, &["transaction_hash"])?;
+            Subscription::TransactionStatus {
+                transaction_hash: transaction_hash.0,
+            }
+        }
+        "starknet_unsubscribe" => {
+            let (id,): (SubscriptionId,) = parse_params(params, &["subscription_id"])?;
+            let position = subscriptions
+                .iter()
+                .position(|subscription_id| *subscription_id == id)
+                .ok_or(RpcError::InvalidSubscriptionId)?;
+            subscriptions.remove(position);
+            return to_value(devnet.unsubscribe(id));
+        }
+        _ => return Err(RpcError::MethodNotFound),
+    };
+
+    let id = devnet.subscribe(subscription);
+    subscriptions.push(id);
+    to_value(id)
+}
+
+/// Parses the params of a method, given either by position or by name. `names` are the names
+/// of the params, in the order of the positions.
+fn parse_params<T: DeserializeOwned>(params: Value, names: &[&str]) -> Result<T, RpcError> {
+    let invalid_params = |error: serde_json::Error| RpcError::InvalidParams(error.to_string());
+
+    match params {
+        Value::Array(values) if values.len() == names.len() => {
+            serde_json::from_value(Value::Array(values)).map_err(invalid_params)
+        }
+        Value::Object(mut values) if values.len() == names.len() => names
+            .iter()
+            .map(|name| {
+                values
+                    .remove(*name)
+                    .ok_or_else(|| RpcError::InvalidParams(format!("missing param `{name}`")))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(|values| {
+                serde_json::from_value(Value::Array(values)).map_err(invalid_params)
+            }),
+        _ => Err(RpcError::InvalidParams(format!(
+            "expected the params {names:?}"
+        ))),
+    }
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|error| RpcError::Internal(error.to_string()))
+}
+
+fn chain_id(devnet: &DevnetState) -> StarknetChainId {
+    *devnet
+        .starknet_state
+        .general_config
+        .starknet_os_config()
+        .chain_id()
+}
+
+/// Returns the number of the block, or an error if the devnet doesn't have it.
+fn block_number(devnet: &DevnetState, block_id: &BlockId) -> Result<u64, RpcError> {
+    match block_id {
+        BlockId::Tag(BlockTag::Latest) => {
+            devnet.latest_block_number().ok_or(RpcError::BlockNotFound)
+        }
+        BlockId::Tag(BlockTag::Pending) => Ok(devnet.block_number()),
+        BlockId::Number { block_number } if *block_number <= devnet.block_number() => {
+            Ok(*block_number)
+        }
+        BlockId::Hash { block_hash } => devnet
+            .block_by_hash(&block_hash.0)
+            .map(|block| block.block_info.block_number)
+            .ok_or(RpcError::BlockNotFound),
+        _ => Err(RpcError::BlockNotFound),
+    }
+}
+
+/// Checks that the state of the block is the current one, the only state that is queried.
+fn check_block_id(devnet: &DevnetState, block_id: &BlockId) -> Result<(), RpcError> {
+    match block_number(devnet, block_id)? {
+        block_number if devnet.has_current_state(block_number) => Ok(()),
+        _ => Err(RpcError::BlockNotFound),
+    }
+}
+
+/// Returns the events matching the filter, in emission order.
+fn get_events(devnet: &DevnetState, filter: &EventFilter) -> Result<EventsChunk, RpcError> {
+    if filter.chunk_size == 0 {
+        return Err(RpcError::InvalidParams(
+            "chunk_size must be positive".to_string(),
+        ));
+    }
+    if filter.chunk_size > MAX_EVENTS_CHUNK_SIZE {
+        return Err(RpcError::PageSizeTooBig);
+    }
+    let from_block = match &filter.from_block {
+        Some(block_id) => Some(block_number(devnet, block_id)?),
+        None => None,
+    };
+    let to_block = match &filter.to_block {
+        Some(block_id) => Some(block_number(devnet, block_id)?),
+        None => None,
+    };
+    let query = EventQuery {
+        from_block,
+        to_block,
+        address: filter
+            .address
+            .as_ref()
+            .map(|address| Address(address.0.clone())),
+        keys: filter
+            .keys
+            .iter()
+            .map(|keys| keys.iter().map(|key| key.0.clone()).collect())
+            .collect(),
+        chunk_size: filter.chunk_size,
+        continuation_token: filter.continuation_token.clone(),
+    };
+
+    let page = devnet
+        .starknet_state
+        .get_events(&query)
+        .map_err(|_| RpcError::InvalidContinuationToken)?;
+    Ok(EventsChunk {
+        events: page.events.iter().map(EmittedEvent::from).collect(),
+        continuation_token: page.continuation_token,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+
+    fn request(devnet: &mut DevnetState, method: &str, params: Value) -> Value {
+        let body = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+        handle_rpc_body(devnet, body.to_string().as_bytes()).unwrap()
+    }
+
+    fn ws_request(
+        devnet: &mut DevnetState,
+        subscriptions: &mut Vec<SubscriptionId>,
+        method: &str,
+        params: Value,
+    ) -> Value {
+        let body = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+        handle_ws_body(devnet, body.to_string().as_bytes(), subscriptions).unwrap()
+    }
+
+    #[test]
+    fn chain_id_and_block_number() {
+        let mut devnet = DevnetState::default();
+
+        assert_eq!(
+            request(&mut devnet, "starknet_chainId", json!([])),
+            json!({"jsonrpc": "2.0", "id": 1, "result": "0x534e5f474f45524c49"})
+        );
+        assert_eq!(
+            request(&mut devnet, "starknet_blockNumber", json!([]))["error"]["code"],
+            json!(32)
+        );
+        devnet.create_block();
+        assert_eq!(
+            request(&mut devnet, "starknet_blockNumber", json!([]))["result"],
+            json!(0)
+        );
+    }
+
+    #[test]
+    fn errors_have_the_spec_codes() {
+        let mut devnet = DevnetState::default();
+        devnet.create_block();
+
+        assert_eq!(
+            request(&mut devnet, "starknet_unknown", json!([]))["error"]["code"],
+            json!(-32601)
+        );
+        assert_eq!(
+            request(&mut devnet, "starknet_getNonce", json!(["latest"]))["error"]["code"],
+            json!(-32602)
+        );
+        assert_eq!(
+            request(
+                &mut devnet,
+                "starknet_getNonce",
+                json!({"block_id": "latest", "contract_address": "0x1"})
+            )["error"]["code"],
+            json!(20)
+        );
+        assert_eq!(
+            request(
+                &mut devnet,
+                "starknet_getNonce",
+                json!([{"block_number": 2}, "0x1"])
+            )["error"]["code"],
+            json!(24)
+        );
+        assert_eq!(
+            handle_rpc_body(&mut devnet, b"{").unwrap()["error"]["code"],
+            json!(-32700)
+        );
+    }
+
+    #[test]
+    fn event_chunk_sizes_are_checked() {
+        let mut devnet = DevnetState::default();
+        let filter = |chunk_size: usize| json!([{ "chunk_size": chunk_size }]);
+
+        assert_eq!(
+            request(&mut devnet, "starknet_getEvents", filter(0))["error"]["code"],
+            json!(-32602)
+        );
+        assert_eq!(
+            request(
+                &mut devnet,
+                "starknet_getEvents",
+                filter(MAX_EVENTS_CHUNK_SIZE + 1)
+            )["error"]["code"],
+            json!(31)
+        );
+        assert_eq!(
+            request(&mut devnet, "starknet_getEvents", filter(1))["result"]["events"],
+            json!([])
+        );
+    }
+
+    #[test]
+    fn batches_are_answered_in_order() {
+        let mut devnet = DevnetState::default();
+        let body = json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "starknet_blockNumber"},
+            {"jsonrpc": "2.0", "id": 2, "method": "starknet_unknown"},
+        ]);
+
+        let response = handle_rpc_body(&mut devnet, body.to_string().as_bytes()).unwrap();
+
+        assert_eq!(response[0]["id"], json!(1));
+        assert_eq!(response[1]["error"]["code"], json!(-32601));
+    }
+
+    #[test]
+    fn notifications_are_not_answered() {
+        let mut devnet = DevnetState::default();
+        let notification = json!({"jsonrpc": "2.0", "method": "starknet_chainId"});
+        let body = json!([
+            notification,
+            {"jsonrpc": "2.0", "id": 1, "method": "starknet_chainId"},
+        ]);
+
+        assert_eq!(
+            handle_rpc_body(&mut devnet, notification.to_string().as_bytes()),
+            None
+        );
+        let response = handle_rpc_body(&mut devnet, body.to_string().as_bytes()).unwrap();
+        assert_eq!(response.as_array().unwrap().len(), 1);
+        assert_eq!(response[0]["id"], json!(1));
+    }
+
+    #[test]
+    fn subscriptions_are_opened_through_websockets() {
+        let mut devnet = DevnetState::default();
+        let mut subscriptions = Vec::new();
+        assert_eq!(
+            request(&mut devnet, "starknet_subscribeNewHeads", json!([]))["error"]["code"],
+            json!(-32601)
+        );
+        let id = ws_request(
+            &mut devnet,
+            &mut subscriptions,
+            "starknet_subscribeNewHeads",
+            json!([]),
+        )["result"]
+            .clone();
+        assert_eq!(subscriptions, vec![0]);
+
+        devnet.set_time(1000);
+        devnet.create_block();
+        let notifications = devnet.take_notifications();
+        let notification = subscription_notification(notifications[0].0, &notifications[0].1);
+        assert_eq!(
+            notification["method"],
+            json!("starknet_subscriptionNewHeads")
+        );
+        assert_eq!(notification["params"]["subscription_id"], id);
+        assert_eq!(notification["params"]["result"]["block_number"], json!(0));
+        assert_eq!(notification["params"]["result"]["timestamp"], json!(1000));
+
+        assert_eq!(
+            ws_request(
+                &mut devnet,
+                &mut Vec::new(),
+                "starknet_unsubscribe",
+                json!([id])
+            )["error"]["code"],
+            json!(66)
+        );
+        assert_eq!(
+            ws_request(
+                &mut devnet,
+                &mut subscriptions,
+                "starknet_unsubscribe",
+                json!({ "subscription_id": id })
+            )["result"],
+            json!(true)
+        );
+        assert!(subscriptions.is_empty());
+    }
+}
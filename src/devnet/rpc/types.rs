@@ -0,0 +1,561 @@
+use crate::{
+    business_logic::{
+        execution::objects::{Event, L2toL1MessageInfo},
+        state::state_api_objects::BlockInfo,
+        transaction::{
+            objects::{
+                internal_declare::InternalDeclare, internal_deploy_account::InternalDeployAccount,
+                internal_invoke_function::InternalInvokeFunction,
+            },
+            transactions::Transaction,
+        },
+    },
+    core::transaction_hash::starknet_transaction_hash::QUERY_VERSION_BASE,
+    definitions::{
+        constants::EXECUTE_ENTRY_POINT_SELECTOR, general_config::StarknetChainId,
+        transaction_type::TransactionType,
+    },
+    devnet::{
+        devnet_state::{FeeEstimate, TransactionReceipt},
+        error::DevnetError,
+    },
+    services::api::{contract_class::ContractClass, messages::StarknetMessageToL1},
+    testing::event_store::StoredEvent,
+    utils::Address,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use cairo_vm::felt::Felt252;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use num_traits::{Num, ToPrimitive};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::io::{Read, Write};
+
+/// A felt, written as a hex string prefixed with `0x`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RpcFelt(pub Felt252);
+
+impl RpcFelt {
+    pub fn to_u128(&self) -> Result<u128, DevnetError> {
+        self.0.to_u128().ok_or_else(|| {
+            DevnetError::InvalidTransaction(format!("{} doesn't fit in u128", self.0))
+        })
+    }
+
+    pub fn to_u64(&self) -> Result<u64, DevnetError> {
+        self.0.to_u64().ok_or_else(|| {
+            DevnetError::InvalidTransaction(format!("{} doesn't fit in u64", self.0))
+        })
+    }
+}
+
+impl From<Felt252> for RpcFelt {
+    fn from(felt: Felt252) -> Self {
+        RpcFelt(felt)
+    }
+}
+
+impl From<u128> for RpcFelt {
+    fn from(value: u128) -> Self {
+        RpcFelt(value.into())
+    }
+}
+
+impl Serialize for RpcFelt {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", self.0.to_str_radix(16)))
+    }
+}
+
+impl<'de> Deserialize<'de> for RpcFelt {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        hex.strip_prefix("0x")
+            .and_then(|digits| Felt252::from_str_radix(digits, 16).ok())
+            .map(RpcFelt)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid felt: {hex}")))
+    }
+}
+
+fn felts(values: Vec<RpcFelt>) -> Vec<Felt252> {
+    values.into_iter().map(|felt| felt.0).collect()
+}
+
+fn rpc_felts(values: &[Felt252]) -> Vec<RpcFelt> {
+    values.iter().cloned().map(RpcFelt).collect()
+}
+
+/// Checks that a transaction is of version 1, or of its query version when `allow_query` is
+/// set. Returns whether it is of the query version.
+fn is_query_version(version: &RpcFelt, allow_query: bool) -> Result<bool, DevnetError> {
+    let is_query = allow_query && version.0 >= *QUERY_VERSION_BASE;
+    let version = if is_query {
+        RpcFelt(version.0.clone() - QUERY_VERSION_BASE.clone()).to_u64()?
+    } else {
+        version.to_u64()?
+    };
+    if version != 1 {
+        return Err(DevnetError::UnsupportedTxVersion(version));
+    }
+
+    Ok(is_query)
+}
+
+// ---------------------------------------------------------------------
+//  Requests
+// ---------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockTag {
+    Latest,
+    Pending,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum BlockId {
+    Tag(BlockTag),
+    Number { block_number: u64 },
+    Hash { block_hash: RpcFelt },
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FunctionCall {
+    pub contract_address: RpcFelt,
+    pub entry_point_selector: RpcFelt,
+    pub calldata: Vec<RpcFelt>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BroadcastedInvokeTransaction {
+    pub sender_address: RpcFelt,
+    pub calldata: Vec<RpcFelt>,
+    pub max_fee: RpcFelt,
+    pub version: RpcFelt,
+    pub signature: Vec<RpcFelt>,
+    pub nonce: RpcFelt,
+}
+
+impl BroadcastedInvokeTransaction {
+    pub fn into_transaction(self, chain_id: StarknetChainId) -> Result<Transaction, DevnetError> {
+        self.into_transaction_with(chain_id, false)
+    }
+
+    fn into_transaction_with(
+        self,
+        chain_id: StarknetChainId,
+        allow_query: bool,
+    ) -> Result<Transaction, DevnetError> {
+        let is_query = is_query_version(&self.version, allow_query)?;
+
+        let tx = InternalInvokeFunction::new(
+            Address(self.sender_address.0),
+            EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+            self.max_fee.to_u128()?,
+            felts(self.calldata),
+            felts(self.signature),
+            chain_id.to_felt(),
+            Some(self.nonce.0),
+        )?;
+        let tx = if is_query {
+            tx.with_query_version(chain_id.to_felt())?
+        } else {
+            tx
+        };
+        Ok(Transaction::InvokeFunction(tx))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BroadcastedDeclareTransaction {
+    pub sender_address: RpcFelt,
+    pub max_fee: RpcFelt,
+    pub version: RpcFelt,
+    pub signature: Vec<RpcFelt>,
+    pub nonce: RpcFelt,
+    /// Definition of a Cairo 0 class, whose program may be compressed as in the
+    /// specification or sent as is.
+    pub contract_class: serde_json::Value,
+}
+
+impl BroadcastedDeclareTransaction {
+    /// Returns the declare transaction along with the definition of the declared class, with
+    /// its program compressed.
+    pub fn into_transaction(
+        self,
+        chain_id: StarknetChainId,
+    ) -> Result<(Transaction, serde_json::Value), DevnetError> {
+        self.into_transaction_with(chain_id, false)
+    }
+
+    fn into_transaction_with(
+        self,
+        chain_id: StarknetChainId,
+        allow_query: bool,
+    ) -> Result<(Transaction, serde_json::Value), DevnetError> {
+        let is_query = is_query_version(&self.version, allow_query)?;
+
+        let mut definition = self.contract_class;
+        let program = match definition.get("program") {
+            Some(serde_json::Value::String(compressed)) => decompress_program(compressed)?,
+            Some(program) => program.clone(),
+            None => {
+                return Err(DevnetError::InvalidTransaction(
+                    "missing class program".to_string(),
+                ))
+            }
+        };
+        definition["program"] = program;
+        let contract_class = ContractClass::try_from(definition.to_string().as_str())
+            .map_err(|error| DevnetError::InvalidTransaction(error.to_string()))?;
+        definition["program"] =
+            serde_json::Value::String(compress_program(&definition["program"])?);
+
+        let tx = InternalDeclare::new(
+            contract_class,
+            chain_id.to_felt(),
+            Address(self.sender_address.0),
+            self.max_fee.to_u128()?,
+            1,
+            felts(self.signature),
+            self.nonce.0,
+        )?;
+        let tx = if is_query {
+            tx.with_query_version(chain_id.to_felt())?
+        } else {
+            tx
+        };
+        Ok((Transaction::Declare(tx), definition))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BroadcastedDeployAccountTransaction {
+    pub max_fee: RpcFelt,
+    pub version: RpcFelt,
+    pub signature: Vec<RpcFelt>,
+    pub nonce: RpcFelt,
+    pub contract_address_salt: RpcFelt,
+    pub constructor_calldata: Vec<RpcFelt>,
+    pub class_hash: RpcFelt,
+}
+
+impl BroadcastedDeployAccountTransaction {
+    pub fn into_transaction(self, chain_id: StarknetChainId) -> Result<Transaction, DevnetError> {
+        self.into_transaction_with(chain_id, false)
+    }
+
+    fn into_transaction_with(
+        self,
+        chain_id: StarknetChainId,
+        allow_query: bool,
+    ) -> Result<Transaction, DevnetError> {
+        let is_query = is_query_version(&self.version, allow_query)?;
+
+        let tx = InternalDeployAccount::new(
+            self.class_hash.0.to_be_bytes(),
+            self.max_fee.to_u128()?,
+            1,
+            self.nonce.0,
+            felts(self.constructor_calldata),
+            felts(self.signature),
+            Address(self.contract_address_salt.0),
+            chain_id,
+        )
+        .map_err(|error| DevnetError::InvalidTransaction(error.to_string()))?;
+        let tx = if is_query {
+            tx.with_query_version()?
+        } else {
+            tx
+        };
+        Ok(Transaction::DeployAccount(tx))
+    }
+}
+
+/// A transaction of any type, as sent to `starknet_estimateFee`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type")]
+pub enum BroadcastedTransaction {
+    #[serde(rename = "INVOKE")]
+    Invoke(BroadcastedInvokeTransaction),
+    #[serde(rename = "DECLARE")]
+    Declare(BroadcastedDeclareTransaction),
+    #[serde(rename = "DEPLOY_ACCOUNT")]
+    DeployAccount(BroadcastedDeployAccountTransaction),
+}
+
+impl BroadcastedTransaction {
+    /// Returns the transaction to estimate, which may be of the query version.
+    pub fn into_transaction(self, chain_id: StarknetChainId) -> Result<Transaction, DevnetError> {
+        match self {
+            BroadcastedTransaction::Invoke(tx) => tx.into_transaction_with(chain_id, true),
+            BroadcastedTransaction::Declare(tx) => Ok(tx.into_transaction_with(chain_id, true)?.0),
+            BroadcastedTransaction::DeployAccount(tx) => tx.into_transaction_with(chain_id, true),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EventFilter {
+    pub from_block: Option<BlockId>,
+    pub to_block: Option<BlockId>,
+    pub address: Option<RpcFelt>,
+    /// Keys to match at each position. An event matches if, at every position, its key is one
+    /// of the given keys or no keys are given.
+    #[serde(default)]
+    pub keys: Vec<Vec<RpcFelt>>,
+    pub chunk_size: usize,
+    pub continuation_token: Option<String>,
+}
+
+/// Params of `starknet_subscribeEvents`, all optional.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct EventSubscriptionFilter {
+    #[serde(default)]
+    pub from_address: Option<RpcFelt>,
+    #[serde(default)]
+    pub keys: Vec<Vec<RpcFelt>>,
+}
+
+// ---------------------------------------------------------------------
+//  Responses
+// ---------------------------------------------------------------------
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RpcEvent {
+    pub from_address: RpcFelt,
+    pub keys: Vec<RpcFelt>,
+    pub data: Vec<RpcFelt>,
+}
+
+impl From<&Event> for RpcEvent {
+    fn from(event: &Event) -> Self {
+        RpcEvent {
+            from_address: RpcFelt(event.from_address.0.clone()),
+            keys: rpc_felts(&event.keys),
+            data: rpc_felts(&event.data),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct EmittedEvent {
+    #[serde(flatten)]
+    pub event: RpcEvent,
+    pub block_number: u64,
+    /// Not set for events of calls executed outside of a transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_hash: Option<RpcFelt>,
+}
+
+impl From<&StoredEvent> for EmittedEvent {
+    fn from(stored: &StoredEvent) -> Self {
+        EmittedEvent {
+            event: RpcEvent::from(&stored.event),
+            block_number: stored.block_number,
+            transaction_hash: stored.transaction_hash.clone().map(RpcFelt),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct EventsChunk {
+    pub events: Vec<EmittedEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continuation_token: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RpcMessageToL1 {
+    pub from_address: RpcFelt,
+    pub to_address: RpcFelt,
+    pub payload: Vec<RpcFelt>,
+}
+
+impl From<&L2toL1MessageInfo> for RpcMessageToL1 {
+    fn from(message: &L2toL1MessageInfo) -> Self {
+        RpcMessageToL1 {
+            from_address: RpcFelt(message.from_address.0.clone()),
+            to_address: RpcFelt(message.to_address.0.clone()),
+            payload: rpc_felts(&message.payload),
+        }
+    }
+}
+
+impl From<&StarknetMessageToL1> for RpcMessageToL1 {
+    fn from(message: &StarknetMessageToL1) -> Self {
+        RpcMessageToL1 {
+            from_address: RpcFelt(message.from_address.0.clone()),
+            to_address: RpcFelt(message.to_address.0.clone()),
+            payload: rpc_felts(&message.payload),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct RpcFeeEstimate {
+    pub gas_consumed: RpcFelt,
+    pub gas_price: RpcFelt,
+    pub overall_fee: RpcFelt,
+}
+
+impl From<FeeEstimate> for RpcFeeEstimate {
+    fn from(estimate: FeeEstimate) -> Self {
+        RpcFeeEstimate {
+            gas_consumed: estimate.gas_consumed.into(),
+            gas_price: estimate.gas_price.into(),
+            overall_fee: estimate.overall_fee.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct RpcBlockHeader {
+    pub block_number: u64,
+    pub timestamp: u64,
+    pub gas_price: RpcFelt,
+    pub sequencer_address: RpcFelt,
+    pub starknet_version: String,
+}
+
+impl From<&BlockInfo> for RpcBlockHeader {
+    fn from(block_info: &BlockInfo) -> Self {
+        RpcBlockHeader {
+            block_number: block_info.block_number,
+            timestamp: block_info.block_timestamp,
+            gas_price: block_info.gas_price.into(),
+            sequencer_address: RpcFelt(block_info.sequencer_address.0.clone()),
+            starknet_version: block_info.starknet_version.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct RpcTransactionStatus {
+    pub transaction_hash: RpcFelt,
+    pub finality_status: &'static str,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct RpcTransactionReceipt {
+    pub transaction_hash: RpcFelt,
+    #[serde(rename = "type")]
+    pub tx_type: &'static str,
+    pub actual_fee: RpcFelt,
+    pub execution_status: &'static str,
+    pub finality_status: &'static str,
+    pub block_number: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_address: Option<RpcFelt>,
+    pub messages_sent: Vec<RpcMessageToL1>,
+    pub events: Vec<RpcEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+}
+
+impl From<&TransactionReceipt> for RpcTransactionReceipt {
+    fn from(receipt: &TransactionReceipt) -> Self {
+        let tx_type = match receipt.tx_type {
+            TransactionType::Declare => "DECLARE",
+            TransactionType::Deploy => "DEPLOY",
+            TransactionType::DeployAccount => "DEPLOY_ACCOUNT",
+            TransactionType::InitializeBlockInfo => "INITIALIZE_BLOCK_INFO",
+            TransactionType::InvokeFunction => "INVOKE",
+            TransactionType::L1Handler => "L1_HANDLER",
+        };
+        let execution_info = &receipt.execution_info;
+
+        RpcTransactionReceipt {
+            transaction_hash: RpcFelt(receipt.transaction_hash.clone()),
+            tx_type,
+            actual_fee: execution_info.actual_fee.into(),
+            execution_status: if execution_info.is_reverted() {
+                "REVERTED"
+            } else {
+                "SUCCEEDED"
+            },
+            finality_status: "ACCEPTED_ON_L2",
+            block_number: receipt.block_number,
+            contract_address: receipt
+                .contract_address
+                .as_ref()
+                .map(|address| RpcFelt(address.0.clone())),
+            messages_sent: receipt.messages_sent.iter().map(Into::into).collect(),
+            events: receipt.events.iter().map(Into::into).collect(),
+            revert_reason: execution_info
+                .revert_error
+                .as_ref()
+                .map(ToString::to_string),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------
+//  Class programs
+// ---------------------------------------------------------------------
+
+/// Decodes a program compressed as in the specification: gzipped JSON, encoded in base64.
+pub fn decompress_program(compressed: &str) -> Result<serde_json::Value, DevnetError> {
+    let invalid_program =
+        |error: String| DevnetError::InvalidTransaction(format!("invalid program: {error}"));
+
+    let gzipped = STANDARD
+        .decode(compressed)
+        .map_err(|error| invalid_program(error.to_string()))?;
+    let mut program = String::new();
+    GzDecoder::new(gzipped.as_slice())
+        .read_to_string(&mut program)
+        .map_err(|error| invalid_program(error.to_string()))?;
+    serde_json::from_str(&program).map_err(|error| invalid_program(error.to_string()))
+}
+
+/// Compresses a program as in the specification, see `decompress_program`.
+pub fn compress_program(program: &serde_json::Value) -> Result<String, DevnetError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(program.to_string().as_bytes())
+        .and_then(|_| encoder.finish())
+        .map(|gzipped| STANDARD.encode(gzipped))
+        .map_err(|error| DevnetError::InvalidTransaction(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+
+    #[test]
+    fn felts_are_hex_strings() {
+        let felt: RpcFelt = serde_json::from_str("\"0x1f\"").unwrap();
+        assert_eq!(felt, RpcFelt(31.into()));
+        assert_eq!(serde_json::to_string(&felt).unwrap(), "\"0x1f\"");
+
+        assert!(serde_json::from_str::<RpcFelt>("\"31\"").is_err());
+    }
+
+    #[test]
+    fn parse_block_ids() {
+        assert_eq!(
+            serde_json::from_str::<BlockId>("\"latest\"").unwrap(),
+            BlockId::Tag(BlockTag::Latest)
+        );
+        assert_eq!(
+            serde_json::from_str::<BlockId>(r#"{"block_number": 3}"#).unwrap(),
+            BlockId::Number { block_number: 3 }
+        );
+    }
+
+    #[test]
+    fn compressed_programs_round_trip() {
+        let program = serde_json::json!({"data": ["0x1", "0x2"], "builtins": []});
+
+        assert_eq!(
+            decompress_program(&compress_program(&program).unwrap()).unwrap(),
+            program
+        );
+        assert_matches!(
+            decompress_program("not base64"),
+            Err(DevnetError::InvalidTransaction(_))
+        );
+    }
+}
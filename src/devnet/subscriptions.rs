@@ -0,0 +1,309 @@
+use super::devnet_state::DevnetState;
+use crate::{
+    business_logic::{execution::objects::Event, state::state_api_objects::BlockInfo},
+    testing::event_store::{EventQuery, StoredEvent},
+    utils::Address,
+};
+use cairo_vm::felt::Felt252;
+use std::collections::BTreeMap;
+
+pub type SubscriptionId = u64;
+
+/// What a subscription is notified of.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Subscription {
+    /// The info of each closed block.
+    NewHeads,
+    /// The events emitted by transactions, filtered as in an event query.
+    Events {
+        address: Option<Address>,
+        keys: Vec<Vec<Felt252>>,
+    },
+    /// The status changes of a transaction.
+    TransactionStatus { transaction_hash: Felt252 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransactionStatus {
+    /// Executed in the pending block.
+    Pending,
+    /// Its block was closed.
+    AcceptedOnL2,
+    /// Its block was aborted.
+    Aborted,
+    /// It failed its validation, so it wasn't executed.
+    Rejected,
+}
+
+impl TransactionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionStatus::Pending => "PENDING",
+            TransactionStatus::AcceptedOnL2 => "ACCEPTED_ON_L2",
+            TransactionStatus::Aborted => "ABORTED",
+            TransactionStatus::Rejected => "REJECTED",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Notification {
+    NewHead(BlockInfo),
+    Event(StoredEvent),
+    TransactionStatus {
+        transaction_hash: Felt252,
+        status: TransactionStatus,
+    },
+}
+
+/// Open subscriptions, along with the notifications produced for them and not taken yet.
+#[derive(Clone, Debug, Default)]
+pub struct Subscriptions {
+    next_id: SubscriptionId,
+    subscriptions: BTreeMap<SubscriptionId, Subscription>,
+    notifications: Vec<(SubscriptionId, Notification)>,
+}
+
+impl Subscriptions {
+    pub(crate) fn notify_new_head(&mut self, block_info: &BlockInfo) {
+        for (id, subscription) in &self.subscriptions {
+            if *subscription == Subscription::NewHeads {
+                self.notifications
+                    .push((*id, Notification::NewHead(block_info.clone())));
+            }
+        }
+    }
+
+    pub(crate) fn notify_events(
+        &mut self,
+        events: &[Event],
+        block_number: u64,
+        transaction_hash: &Felt252,
+    ) {
+        for (id, subscription) in &self.subscriptions {
+            if let Subscription::Events { address, keys } = subscription {
+                let query = EventQuery {
+                    address: address.clone(),
+                    keys: keys.clone(),
+                    ..Default::default()
+                };
+                for event in events {
+                    let stored = StoredEvent {
+                        event: event.clone(),
+                        block_number,
+                        transaction_hash: Some(transaction_hash.clone()),
+                    };
+                    if query.matches(&stored) {
+                        self.notifications.push((*id, Notification::Event(stored)));
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn notify_transaction_status(
+        &mut self,
+        transaction_hash: &Felt252,
+        status: TransactionStatus,
+    ) {
+        for (id, subscription) in &self.subscriptions {
+            let subscribed = match subscription {
+                Subscription::TransactionStatus {
+                    transaction_hash: subscribed_hash,
+                } => subscribed_hash == transaction_hash,
+                _ => false,
+            };
+            if subscribed {
+                self.notifications.push((
+                    *id,
+                    Notification::TransactionStatus {
+                        transaction_hash: transaction_hash.clone(),
+                        status,
+                    },
+                ));
+            }
+        }
+    }
+}
+
+impl DevnetState {
+    /// Opens a subscription. A subscription to the status of an executed transaction is
+    /// notified of its current status right away.
+    pub fn subscribe(&mut self, subscription: Subscription) -> SubscriptionId {
+        let id = self.subscriptions.next_id;
+        self.subscriptions.next_id += 1;
+
+        let current_status = match &subscription {
+            Subscription::TransactionStatus { transaction_hash } => self
+                .receipt(transaction_hash)
+                .map(|receipt| match self.block(receipt.block_number) {
+                    Some(_) => TransactionStatus::AcceptedOnL2,
+                    None => TransactionStatus::Pending,
+                })
+                .map(|status| Notification::TransactionStatus {
+                    transaction_hash: transaction_hash.clone(),
+                    status,
+                }),
+            _ => None,
+        };
+        self.subscriptions.subscriptions.insert(id, subscription);
+        if let Some(notification) = current_status {
+            self.subscriptions.notifications.push((id, notification));
+        }
+        id
+    }
+
+    /// Closes a subscription and discards its pending notifications. Returns whether it was
+    /// open.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        self.subscriptions
+            .notifications
+            .retain(|(subscription_id, _)| *subscription_id != id);
+        self.subscriptions.subscriptions.remove(&id).is_some()
+    }
+
+    /// Takes the notifications produced since the last call, in the order they were produced.
+    pub fn take_notifications(&mut self) -> Vec<(SubscriptionId, Notification)> {
+        std::mem::take(&mut self.subscriptions.notifications)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        business_logic::transaction::{
+            objects::internal_invoke_function::InternalInvokeFunction, transactions::Transaction,
+        },
+        definitions::{constants::EXECUTE_ENTRY_POINT_SELECTOR, transaction_type::TransactionType},
+        devnet::devnet_state::TransactionReceipt,
+    };
+    use coverage_helper::test;
+
+    fn record_transaction(devnet: &mut DevnetState, transaction_hash: u64, events: Vec<Event>) {
+        devnet.record_receipt(TransactionReceipt {
+            transaction_hash: transaction_hash.into(),
+            tx_type: TransactionType::InvokeFunction,
+            block_number: devnet.block_number(),
+            contract_address: None,
+            events,
+            messages_sent: vec![],
+            execution_info: Default::default(),
+        });
+    }
+
+    fn status(transaction_hash: u64, status: TransactionStatus) -> Notification {
+        Notification::TransactionStatus {
+            transaction_hash: transaction_hash.into(),
+            status,
+        }
+    }
+
+    #[test]
+    fn transaction_status_follows_the_block() {
+        let mut devnet = DevnetState::default();
+        devnet.create_block();
+        let id = devnet.subscribe(Subscription::TransactionStatus {
+            transaction_hash: 7.into(),
+        });
+
+        record_transaction(&mut devnet, 7, vec![]);
+        devnet.create_block();
+        devnet.abort_blocks(1).unwrap();
+
+        assert_eq!(
+            devnet.take_notifications(),
+            vec![
+                (id, status(7, TransactionStatus::Pending)),
+                (id, status(7, TransactionStatus::AcceptedOnL2)),
+                (id, status(7, TransactionStatus::Aborted)),
+            ]
+        );
+        assert!(devnet.take_notifications().is_empty());
+    }
+
+    #[test]
+    fn rejected_transactions_are_notified() {
+        let mut devnet = DevnetState::default();
+        // The sender isn't deployed, so the transaction fails its validation.
+        let tx = Transaction::InvokeFunction(
+            InternalInvokeFunction::new(
+                Address(1.into()),
+                EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+                0,
+                vec![],
+                vec![],
+                0.into(),
+                Some(0.into()),
+            )
+            .unwrap(),
+        );
+        let transaction_hash = tx.hash_value();
+        let id = devnet.subscribe(Subscription::TransactionStatus {
+            transaction_hash: transaction_hash.clone(),
+        });
+
+        assert!(devnet.add_transaction(tx).is_err());
+        assert_eq!(
+            devnet.take_notifications(),
+            vec![(
+                id,
+                Notification::TransactionStatus {
+                    transaction_hash,
+                    status: TransactionStatus::Rejected,
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn events_and_new_heads_are_filtered() {
+        let mut devnet = DevnetState::default();
+        let heads = devnet.subscribe(Subscription::NewHeads);
+        let events = devnet.subscribe(Subscription::Events {
+            address: Some(Address(1.into())),
+            keys: vec![vec![10.into()]],
+        });
+        let emitted = |from_address: u64, key: u64| Event {
+            from_address: Address(from_address.into()),
+            keys: vec![key.into()],
+            data: vec![],
+        };
+
+        record_transaction(
+            &mut devnet,
+            7,
+            vec![emitted(1, 10), emitted(2, 10), emitted(1, 20)],
+        );
+        let block_info = devnet.create_block().block_info.clone();
+
+        assert_eq!(
+            devnet.take_notifications(),
+            vec![
+                (
+                    events,
+                    Notification::Event(StoredEvent {
+                        event: emitted(1, 10),
+                        block_number: 0,
+                        transaction_hash: Some(7.into()),
+                    })
+                ),
+                (heads, Notification::NewHead(block_info)),
+            ]
+        );
+    }
+
+    #[test]
+    fn unsubscribing_discards_the_notifications() {
+        let mut devnet = DevnetState::default();
+        record_transaction(&mut devnet, 7, vec![]);
+        let id = devnet.subscribe(Subscription::TransactionStatus {
+            transaction_hash: 7.into(),
+        });
+
+        assert!(devnet.unsubscribe(id));
+        assert!(!devnet.unsubscribe(id));
+        devnet.create_block();
+        assert!(devnet.take_notifications().is_empty());
+    }
+}
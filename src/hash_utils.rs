@@ -2,7 +2,7 @@ use crate::{core::errors::syscall_handler_errors::SyscallHandlerError, utils::Ad
 use cairo_vm::felt::Felt252;
 use num_integer::Integer;
 use num_traits::Pow;
-use starknet_crypto::{pedersen_hash, FieldElement};
+use starknet_crypto::{pedersen_hash, poseidon_hash_many, FieldElement};
 use std::vec;
 
 pub fn calculate_contract_address(
@@ -49,6 +49,22 @@ pub(crate) fn compute_hash_on_elements(vec: &[Felt252]) -> Result<Felt252, Sysca
     Ok(result)
 }
 
+/// Computes the Poseidon hash of the given elements, as used by version 3 transaction hashes.
+pub(crate) fn compute_poseidon_hash_on_elements(
+    vec: &[Felt252],
+) -> Result<Felt252, SyscallHandlerError> {
+    let felt_vec = vec
+        .iter()
+        .map(|num| {
+            FieldElement::from_dec_str(&num.to_str_radix(10))
+                .map_err(|_| SyscallHandlerError::FailToComputeHash)
+        })
+        .collect::<Result<Vec<FieldElement>, SyscallHandlerError>>()?;
+
+    let felt_result = poseidon_hash_many(&felt_vec);
+    Ok(Felt252::from_bytes_be(&felt_result.to_bytes_be()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -9,6 +9,7 @@ extern crate assert_matches;
 pub mod business_logic;
 pub mod core;
 pub mod definitions;
+pub mod devnet;
 pub mod hash_utils;
 pub mod parser_errors;
 pub mod public;
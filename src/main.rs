@@ -1,8 +1,12 @@
-use actix_web::{post, web, App, HttpResponse, HttpServer};
+use actix_codec::{Decoder, Encoder};
+use actix_http::{body::BodyStream, ws};
+use actix_web::{get, post, web, App, HttpRequest, HttpResponse, HttpServer};
 use cairo_vm::felt::Felt252;
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use futures_util::{stream, StreamExt};
+use num_bigint::BigUint;
 use num_traits::{Num, Zero};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use starknet_rs::{
     business_logic::{
         execution::{
@@ -25,17 +29,35 @@ use starknet_rs::{
             calculate_transaction_hash_common, TransactionHashPrefix,
         },
     },
-    definitions::{
-        constants::{DECLARE_VERSION, TRANSACTION_VERSION},
-        general_config::StarknetGeneralConfig,
+    definitions::{constants::TRANSACTION_VERSION, general_config::StarknetGeneralConfig},
+    devnet::{
+        blocks::BlockGeneration,
+        devnet_state::DevnetState,
+        gateway::{
+            error::GatewayError,
+            handler as gateway,
+            types::{FeederQuery, GatewayFelt},
+        },
+        postman::{MessageToL2, Postman},
+        predeployed::PredeployConfig,
+        rpc::{
+            handler::{handle_rpc_body, handle_ws_body, subscription_notification},
+            types::RpcMessageToL1,
+        },
+        subscriptions::SubscriptionId,
     },
     hash_utils::calculate_contract_address,
     parser_errors::ParserError,
-    serde_structs::contract_abi::read_abi,
-    services::api::contract_class::ContractClass,
+    serde_structs::contract_abi::ContractAbi,
+    services::api::{contract_class::ContractClass, messages::StarknetMessageToL1},
     utils::{string_to_hash, Address},
 };
-use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tokio::sync::mpsc::{self, UnboundedSender};
 
 #[cfg(feature = "with_mimalloc")]
 use mimalloc::MiMalloc;
@@ -48,6 +70,13 @@ static ALLOC: MiMalloc = MiMalloc;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Run the command on the state dumped to this file instead of sending it to a devnet. The
+    /// resulting state is dumped back to the file.
+    #[arg(long, global = true, conflicts_with = "url")]
+    state: Option<PathBuf>,
+    /// URL of the devnet the commands are sent to.
+    #[arg(long, global = true, default_value = "http://127.0.0.1:7878")]
+    url: String,
 }
 
 #[derive(Subcommand)]
@@ -72,8 +101,9 @@ struct DeployArgs {
     class_hash: String,
     #[arg(long, default_value = "1111")]
     salt: i32,
+    /// Arguments of the constructor, encoded according to the ABI of the declared class.
     #[arg(long, num_args=1.., value_delimiter = ' ')]
-    inputs: Option<Vec<i32>>,
+    inputs: Option<Vec<String>>,
 }
 
 #[derive(Args, Serialize, Deserialize)]
@@ -84,8 +114,10 @@ struct InvokeArgs {
     abi: PathBuf,
     #[arg(long)]
     function: String,
+    /// Arguments of the function, encoded according to the ABI: felts as hex or decimal
+    /// numbers or short strings, and structs, tuples and arrays as JSON.
     #[arg(long, num_args=1.., value_delimiter = ' ')]
-    inputs: Option<Vec<i32>>,
+    inputs: Option<Vec<String>>,
 }
 
 #[derive(Args, Serialize, Deserialize)]
@@ -96,35 +128,170 @@ struct CallArgs {
     abi: PathBuf,
     #[arg(long)]
     function: String,
+    /// Arguments of the function, encoded according to the ABI: felts as hex or decimal
+    /// numbers or short strings, and structs, tuples and arrays as JSON.
     #[arg(long, num_args=1.., value_delimiter = ' ')]
-    inputs: Option<Vec<i32>>,
+    inputs: Option<Vec<String>>,
 }
 
 #[derive(Args)]
-struct DevnetArgs {
+pub struct DevnetArgs {
     #[arg(long, default_value = "7878")]
     port: u16,
+    /// File the state is dumped to, by `/dump` requests without a path and by `--dump-on`.
+    #[arg(long)]
+    dump_path: Option<PathBuf>,
+    /// When to dump the state to the `--dump-path` file.
+    #[arg(long, value_enum, requires = "dump_path")]
+    dump_on: Option<DumpOn>,
+    /// File of a dumped state to start from.
+    #[arg(long)]
+    load_path: Option<PathBuf>,
+    /// Number of funded accounts to deploy on startup.
+    #[arg(long, default_value = "10")]
+    accounts: usize,
+    /// Seed of the key pair of the first account. The following accounts use the next seeds.
+    #[arg(long, default_value = "0")]
+    seed: u64,
+    /// Fee token balance of each account, in wei.
+    #[arg(long = "initial-balance", default_value = "1000000000000000000000")]
+    initial_balance: u128,
+    /// Close blocks only on `/create_block` requests, instead of after every transaction.
+    #[arg(long)]
+    blocks_on_demand: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DumpOn {
+    /// When the devnet is stopped.
+    Exit,
+    /// After every transaction.
+    Transaction,
+}
+
+/// Body of `/mint` requests.
+#[derive(Deserialize)]
+struct MintRequest {
+    address: GatewayFelt,
+    amount: serde_json::Number,
+}
+
+/// Query of `/account_balance` requests.
+#[derive(Deserialize)]
+struct AccountBalanceQuery {
+    address: GatewayFelt,
+}
+
+/// Body of `/abort_blocks` requests.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AbortBlocksRequest {
+    starting_block_number: u64,
+}
+
+/// Body of `/increase_time` and `/set_time` requests, in seconds.
+#[derive(Deserialize)]
+struct TimeRequest {
+    time: u64,
+}
+
+/// Body of `/set_gas_price` requests, in wei.
+#[derive(Deserialize)]
+struct GasPriceRequest {
+    gas_price: u128,
+}
+
+/// Body of `/postman/send_message_to_l2` requests.
+#[derive(Deserialize)]
+struct MessageToL2Request {
+    l1_contract_address: GatewayFelt,
+    l2_contract_address: GatewayFelt,
+    entry_point_selector: GatewayFelt,
+    payload: Vec<GatewayFelt>,
+    nonce: GatewayFelt,
+}
+
+/// Body of `/postman/queue_message_to_l2` requests.
+#[derive(Deserialize)]
+struct QueuedMessageToL2Request {
+    l1_contract_address: GatewayFelt,
+    l2_contract_address: GatewayFelt,
+    entry_point_selector: GatewayFelt,
+    payload: Vec<GatewayFelt>,
+}
+
+/// Body of `/postman/consume_message_from_l2` requests.
+#[derive(Deserialize)]
+struct MessageFromL2Request {
+    l2_contract_address: GatewayFelt,
+    l1_contract_address: GatewayFelt,
+    payload: Vec<GatewayFelt>,
+}
+
+/// Body of `/dump` and `/load` requests.
+#[derive(Deserialize)]
+struct DumpRequest {
+    path: Option<PathBuf>,
 }
 
 struct AppState {
-    cached_state: Mutex<CachedState<InMemoryStateReader>>,
+    devnet: Mutex<DevnetState>,
+    dump_path: Option<PathBuf>,
+    dump_on: Option<DumpOn>,
+    /// WebSocket connections of the open subscriptions.
+    subscribers: Mutex<HashMap<SubscriptionId, UnboundedSender<ws::Message>>>,
+    postman: Mutex<Postman>,
+}
+
+impl AppState {
+    /// Sends the notifications of the subscriptions to their WebSocket connections, and dumps
+    /// the devnet if it has to be dumped after every transaction.
+    fn after_transaction(&self, devnet: &mut DevnetState) {
+        self.publish_notifications(devnet);
+        if let (Some(DumpOn::Transaction), Some(dump_path)) = (self.dump_on, &self.dump_path) {
+            if let Err(error) = devnet.dump_to_file(dump_path) {
+                eprintln!("Failed to dump the devnet: {error}");
+            }
+        }
+    }
+
+    fn publish_notifications(&self, devnet: &mut DevnetState) {
+        let notifications = devnet.take_notifications();
+        if notifications.is_empty() {
+            return;
+        }
+        let subscribers = self.subscribers.lock().unwrap();
+        for (id, notification) in notifications {
+            if let Some(subscriber) = subscribers.get(&id) {
+                let message = subscription_notification(id, &notification).to_string();
+                let _ = subscriber.send(ws::Message::Text(message.into()));
+            }
+        }
+    }
 }
 
 fn declare_parser(
-    cached_state: &mut CachedState<InMemoryStateReader>,
+    devnet: &mut DevnetState,
     args: &DeclareArgs,
 ) -> Result<(Felt252, Felt252), ParserError> {
     let contract_class =
         ContractClass::try_from(&args.contract).map_err(ContractAddressError::Program)?;
     let class_hash = compute_class_hash(&contract_class)?;
-    cached_state.set_contract_class(&class_hash.to_be_bytes(), &contract_class)?;
+    devnet
+        .starknet_state
+        .state
+        .set_contract_class(&class_hash.to_be_bytes(), &contract_class)?;
+    devnet.add_class_definition_from_file(class_hash.to_be_bytes(), &args.contract)?;
 
+    // Version 2 declares a Sierra class along with its compiled class hash, so a Cairo 0 class is
+    // declared with version 1.
     let tx_hash = calculate_declare_transaction_hash(
         &contract_class,
+        None,
         Felt252::zero(),
         &Address(0.into()),
         0,
-        DECLARE_VERSION,
+        TRANSACTION_VERSION.into(),
         Felt252::zero(),
     )?;
     Ok((class_hash, tx_hash))
@@ -134,23 +301,27 @@ fn deploy_parser(
     cached_state: &mut CachedState<InMemoryStateReader>,
     args: &DeployArgs,
 ) -> Result<(Felt252, Felt252), ParserError> {
-    let constructor_calldata = match &args.inputs {
-        Some(vec) => vec.iter().map(|&n| n.into()).collect(),
-        None => Vec::new(),
+    let class_hash = string_to_hash(&args.class_hash)
+        .map_err(|_| ParserError::ParseFelt(args.class_hash.clone()))?;
+    let abi = match cached_state.get_contract_class(&class_hash)?.abi() {
+        Some(abi) => ContractAbi::try_from(abi)?,
+        None => ContractAbi::default(),
+    };
+    let arguments = parse_arguments(&args.inputs);
+    // Classes without a constructor are deployed without arguments.
+    let constructor_calldata = if abi.function("constructor").is_none() && arguments.is_empty() {
+        Vec::new()
+    } else {
+        abi.encode_inputs("constructor", &arguments)?
     };
     let address = calculate_contract_address(
         &Address(args.salt.into()),
-        &Felt252::from_str_radix(&args.class_hash[2..], 16)
-            .map_err(|_| ParserError::ParseFelt(args.class_hash.clone()))?,
+        &Felt252::from_bytes_be(&class_hash),
         &constructor_calldata,
         Address(0.into()),
     )?;
 
-    cached_state.deploy_contract(
-        Address(address.clone()),
-        string_to_hash(&args.class_hash)
-            .map_err(|_| ParserError::ParseFelt(args.class_hash.clone()))?,
-    )?;
+    cached_state.deploy_contract(Address(address.clone()), class_hash)?;
     let tx_hash = calculate_deploy_transaction_hash(
         0,
         &Address(address.clone()),
@@ -170,7 +341,8 @@ fn invoke_parser(
     );
     let class_hash = cached_state.get_class_hash_at(&contract_address)?;
     let contract_class = cached_state.get_contract_class(&class_hash)?;
-    let function_entrypoint_indexes = read_abi(&args.abi);
+    let abi = ContractAbi::from_file(&args.abi)?;
+    let function_entrypoint_indexes = abi.entry_point_indexes();
 
     let entry_points_by_type = contract_class.entry_points_by_type();
     let (entry_point_index, entry_point_type) = function_entrypoint_indexes
@@ -185,10 +357,7 @@ fn invoke_parser(
         .selector
         .clone();
 
-    let calldata = match &args.inputs {
-        Some(vec) => vec.iter().map(|&n| n.into()).collect(),
-        None => Vec::new(),
-    };
+    let calldata = abi.encode_inputs(&args.function, &parse_arguments(&args.inputs))?;
     let internal_invoke = InternalInvokeFunction::new(
         contract_address.clone(),
         entrypoint_selector.clone(),
@@ -198,11 +367,14 @@ fn invoke_parser(
         Felt252::zero(),
         Some(Felt252::zero()),
     )?;
-    let _tx_info = internal_invoke.apply(cached_state, &StarknetGeneralConfig::default())?;
+    let tx_info = internal_invoke.apply(cached_state, &StarknetGeneralConfig::default())?;
+    if let Some(revert_error) = tx_info.revert_error {
+        return Err(ParserError::Reverted(revert_error));
+    }
 
     let tx_hash = calculate_transaction_hash_common(
         TransactionHashPrefix::Invoke,
-        TRANSACTION_VERSION,
+        TRANSACTION_VERSION.into(),
         &contract_address,
         entrypoint_selector,
         &calldata,
@@ -214,17 +386,19 @@ fn invoke_parser(
     Ok((contract_address.0, tx_hash))
 }
 
+/// Calls a function and returns its outputs, decoded according to the ABI.
 fn call_parser(
     cached_state: &mut CachedState<InMemoryStateReader>,
     args: &CallArgs,
-) -> Result<Vec<Felt252>, ParserError> {
+) -> Result<Vec<(String, serde_json::Value)>, ParserError> {
     let contract_address = Address(
         Felt252::from_str_radix(&args.address[2..], 16)
             .map_err(|_| ParserError::ParseFelt(args.address.clone()))?,
     );
     let class_hash = cached_state.get_class_hash_at(&contract_address)?;
     let contract_class = cached_state.get_contract_class(&class_hash)?;
-    let function_entrypoint_indexes = read_abi(&args.abi);
+    let abi = ContractAbi::from_file(&args.abi)?;
+    let function_entrypoint_indexes = abi.entry_point_indexes();
     let entry_points_by_type = contract_class.entry_points_by_type();
     let (entry_point_index, entry_point_type) = function_entrypoint_indexes
         .get(&args.function)
@@ -238,10 +412,7 @@ fn call_parser(
         .selector
         .clone();
     let caller_address = Address(0.into());
-    let calldata = match &args.inputs {
-        Some(vec) => vec.iter().map(|&n| n.into()).collect(),
-        None => Vec::new(),
-    };
+    let calldata = abi.encode_inputs(&args.function, &parse_arguments(&args.inputs))?;
     let execution_entry_point = ExecutionEntryPoint::new(
         contract_address,
         calldata,
@@ -257,37 +428,92 @@ fn call_parser(
         &mut ExecutionResourcesManager::default(),
         &TransactionExecutionContext::default(),
     )?;
-    Ok(call_info.retdata)
+
+    Ok(abi
+        .decode_outputs(&args.function, &call_info.retdata)?
+        .into_iter()
+        .map(|(name, value)| (name, serde_json::json!(value)))
+        .collect())
+}
+
+/// Parses command line arguments as JSON, taking the ones that aren't valid JSON, like hex
+/// numbers and short strings, as strings.
+fn parse_arguments(inputs: &Option<Vec<String>>) -> Vec<serde_json::Value> {
+    inputs
+        .iter()
+        .flatten()
+        .map(|input| {
+            serde_json::from_str(input).unwrap_or_else(|_| serde_json::Value::String(input.clone()))
+        })
+        .collect()
 }
 
 async fn devnet_parser(devnet_args: &DevnetArgs) -> Result<(), ParserError> {
-    start_devnet(devnet_args.port).await?;
+    let mut devnet = DevnetState::default();
+    if devnet_args.blocks_on_demand {
+        devnet.set_block_generation(BlockGeneration::Demand);
+    }
+    match &devnet_args.load_path {
+        Some(load_path) => devnet.load_from_file(load_path)?,
+        None => {
+            let config = PredeployConfig {
+                accounts: devnet_args.accounts,
+                seed: devnet_args.seed,
+                initial_balance: devnet_args.initial_balance.into(),
+            };
+            let accounts = devnet.predeploy(&config)?;
+            println!(
+                "Predeployed accounts, funded with {} wei:",
+                config.initial_balance
+            );
+            for account in accounts {
+                println!(
+                    "Address: 0x{:x}\nPublic key: 0x{:x}\nPrivate key: 0x{:x}\n",
+                    account.address().0.to_biguint(),
+                    account.key_pair().public_key().to_biguint(),
+                    account.key_pair().private_key().to_biguint(),
+                );
+            }
+            // The genesis block holds the predeployed contracts.
+            devnet.create_block();
+        }
+    }
+    start_devnet(devnet_args, devnet).await?;
     Ok(())
 }
 
 #[post("/declare")]
 async fn declare_req(data: web::Data<AppState>, args: web::Json<DeclareArgs>) -> HttpResponse {
-    let mut cached_state = data.cached_state.lock().unwrap();
-    match declare_parser(&mut cached_state, &args) {
-        Ok(t) => HttpResponse::Ok().json(t),
+    let mut devnet = data.devnet.lock().unwrap();
+    match declare_parser(&mut devnet, &args) {
+        Ok(t) => {
+            data.after_transaction(&mut devnet);
+            HttpResponse::Ok().json(t)
+        }
         Err(e) => HttpResponse::ExpectationFailed().body(e.to_string()),
     }
 }
 
 #[post("/deploy")]
 async fn deploy_req(data: web::Data<AppState>, args: web::Json<DeployArgs>) -> HttpResponse {
-    let mut cached_state = data.cached_state.lock().unwrap();
-    match deploy_parser(&mut cached_state, &args) {
-        Ok(t) => HttpResponse::Ok().json(t),
+    let mut devnet = data.devnet.lock().unwrap();
+    match deploy_parser(&mut devnet.starknet_state.state, &args) {
+        Ok(t) => {
+            data.after_transaction(&mut devnet);
+            HttpResponse::Ok().json(t)
+        }
         Err(e) => HttpResponse::ExpectationFailed().body(e.to_string()),
     }
 }
 
 #[post("/invoke")]
 async fn invoke_req(data: web::Data<AppState>, args: web::Json<InvokeArgs>) -> HttpResponse {
-    let mut cached_state = data.cached_state.lock().unwrap();
-    match invoke_parser(&mut cached_state, &args) {
-        Ok(t) => HttpResponse::Ok().json(t),
+    let mut devnet = data.devnet.lock().unwrap();
+    match invoke_parser(&mut devnet.starknet_state.state, &args) {
+        Ok(t) => {
+            data.after_transaction(&mut devnet);
+            HttpResponse::Ok().json(t)
+        }
         Err(e) => HttpResponse::ExpectationFailed().body(e.to_string()),
     }
 }
@@ -295,32 +521,539 @@ async fn invoke_req(data: web::Data<AppState>, args: web::Json<InvokeArgs>) -> H
 #[post("/call")]
 async fn call_req(data: web::Data<AppState>, args: web::Json<CallArgs>) -> HttpResponse {
     println!("call received");
-    let mut cached_state = data.cached_state.lock().unwrap();
-    match call_parser(&mut cached_state, &args) {
+    let mut devnet = data.devnet.lock().unwrap();
+    match call_parser(&mut devnet.starknet_state.state, &args) {
         Ok(t) => HttpResponse::Ok().json(t),
         Err(e) => HttpResponse::ExpectationFailed().body(e.to_string()),
     }
 }
 
-pub async fn start_devnet(port: u16) -> Result<(), std::io::Error> {
-    let cached_state = web::Data::new(AppState {
-        cached_state: Mutex::new(CachedState::<InMemoryStateReader>::new(
-            InMemoryStateReader::default(),
-            Some(HashMap::new()),
-        )),
+/// Starknet JSON-RPC endpoint.
+#[post("/rpc")]
+async fn rpc_req(data: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
+    let mut devnet = data.devnet.lock().unwrap();
+    let n_transactions = devnet.receipts().count();
+    let response = handle_rpc_body(&mut devnet, &body);
+    if devnet.receipts().count() != n_transactions {
+        data.after_transaction(&mut devnet);
+    } else {
+        data.publish_notifications(&mut devnet);
+    }
+    match response {
+        Some(response) => HttpResponse::Ok().json(response),
+        None => HttpResponse::NoContent().finish(),
+    }
+}
+
+/// Starknet JSON-RPC endpoint over WebSocket, with subscriptions.
+#[get("/ws")]
+async fn ws_req(
+    data: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Payload,
+) -> Result<HttpResponse, actix_web::Error> {
+    let mut response = ws::handshake(req.head())?;
+    let (sender, receiver) = mpsc::unbounded_channel();
+    actix_web::rt::spawn(ws_session(data, payload, sender));
+
+    // The frames are sent until the session and the subscriptions of the connection are gone.
+    let frames = stream::unfold(
+        (receiver, ws::Codec::new()),
+        |(mut receiver, mut codec)| async move {
+            let message = receiver.recv().await?;
+            let mut frame = web::BytesMut::new();
+            let frame = codec.encode(message, &mut frame).map(|()| frame.freeze());
+            Some((frame, (receiver, codec)))
+        },
+    );
+    Ok(HttpResponse::from(response.message_body(BodyStream::new(frames))?).map_into_boxed_body())
+}
+
+/// Reads the frames of a WebSocket connection, through which JSON-RPC requests are sent,
+/// including the subscription methods. The responses, and the notifications of the
+/// subscriptions opened through the connection, are sent through `sender`.
+async fn ws_session(
+    data: web::Data<AppState>,
+    mut payload: web::Payload,
+    sender: UnboundedSender<ws::Message>,
+) {
+    let mut codec = ws::Codec::new();
+    let mut buffer = web::BytesMut::new();
+    let mut subscriptions = Vec::new();
+    'connection: while let Some(Ok(chunk)) = payload.next().await {
+        buffer.extend_from_slice(&chunk);
+        loop {
+            match codec.decode(&mut buffer) {
+                Ok(Some(ws::Frame::Text(text))) => {
+                    handle_ws_text(&data, &text, &mut subscriptions, &sender)
+                }
+                Ok(Some(ws::Frame::Ping(message))) => {
+                    let _ = sender.send(ws::Message::Pong(message));
+                }
+                Ok(Some(ws::Frame::Close(reason))) => {
+                    let _ = sender.send(ws::Message::Close(reason));
+                    break 'connection;
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(_) => break 'connection,
+            }
+        }
+    }
+
+    let mut devnet = data.devnet.lock().unwrap();
+    let mut subscribers = data.subscribers.lock().unwrap();
+    for id in subscriptions {
+        devnet.unsubscribe(id);
+        subscribers.remove(&id);
+    }
+}
+
+/// Answers the JSON-RPC request, or batch, of a text frame, and registers `sender` as the
+/// subscriber of the subscriptions it opens.
+fn handle_ws_text(
+    data: &AppState,
+    text: &[u8],
+    subscriptions: &mut Vec<SubscriptionId>,
+    sender: &UnboundedSender<ws::Message>,
+) {
+    let mut devnet = data.devnet.lock().unwrap();
+    let n_transactions = devnet.receipts().count();
+    let opened = subscriptions.clone();
+    let response = handle_ws_body(&mut devnet, text, subscriptions);
+
+    let mut subscribers = data.subscribers.lock().unwrap();
+    subscribers.retain(|id, _| !opened.contains(id) || subscriptions.contains(id));
+    for id in subscriptions.iter() {
+        if !opened.contains(id) {
+            subscribers.insert(*id, sender.clone());
+        }
+    }
+    drop(subscribers);
+
+    if let Some(response) = response {
+        let _ = sender.send(ws::Message::Text(response.to_string().into()));
+    }
+    if devnet.receipts().count() != n_transactions {
+        data.after_transaction(&mut devnet);
+    } else {
+        data.publish_notifications(&mut devnet);
+    }
+}
+
+fn gateway_response(result: Result<serde_json::Value, GatewayError>) -> HttpResponse {
+    match result {
+        Ok(value) => HttpResponse::Ok().json(value),
+        Err(error) => HttpResponse::BadRequest().json(serde_json::json!({
+            "code": error.code(),
+            "message": error.to_string(),
+        })),
+    }
+}
+
+#[post("/gateway/add_transaction")]
+async fn add_transaction_req(data: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
+    let mut devnet = data.devnet.lock().unwrap();
+    let result = gateway::add_transaction(&mut devnet, &body);
+    if result.is_ok() {
+        data.after_transaction(&mut devnet);
+    } else {
+        data.publish_notifications(&mut devnet);
+    }
+    gateway_response(result)
+}
+
+#[post("/feeder_gateway/call_contract")]
+async fn call_contract_req(
+    data: web::Data<AppState>,
+    query: web::Query<FeederQuery>,
+    body: web::Bytes,
+) -> HttpResponse {
+    let devnet = data.devnet.lock().unwrap();
+    gateway_response(gateway::call_contract(&devnet, &query, &body))
+}
+
+#[post("/feeder_gateway/estimate_fee")]
+async fn estimate_fee_req(
+    data: web::Data<AppState>,
+    query: web::Query<FeederQuery>,
+    body: web::Bytes,
+) -> HttpResponse {
+    let devnet = data.devnet.lock().unwrap();
+    gateway_response(gateway::estimate_fee(&devnet, &query, &body))
+}
+
+#[get("/feeder_gateway/get_transaction_receipt")]
+async fn get_transaction_receipt_req(
+    data: web::Data<AppState>,
+    query: web::Query<FeederQuery>,
+) -> HttpResponse {
+    let devnet = data.devnet.lock().unwrap();
+    gateway_response(gateway::get_transaction_receipt(&devnet, &query))
+}
+
+#[get("/feeder_gateway/get_class_hash_at")]
+async fn get_class_hash_at_req(
+    data: web::Data<AppState>,
+    query: web::Query<FeederQuery>,
+) -> HttpResponse {
+    let mut devnet = data.devnet.lock().unwrap();
+    gateway_response(gateway::get_class_hash_at(&mut devnet, &query))
+}
+
+#[get("/feeder_gateway/get_storage_at")]
+async fn get_storage_at_req(
+    data: web::Data<AppState>,
+    query: web::Query<FeederQuery>,
+) -> HttpResponse {
+    let mut devnet = data.devnet.lock().unwrap();
+    gateway_response(gateway::get_storage_at(&mut devnet, &query))
+}
+
+#[get("/feeder_gateway/get_block")]
+async fn get_block_req(data: web::Data<AppState>, query: web::Query<FeederQuery>) -> HttpResponse {
+    let devnet = data.devnet.lock().unwrap();
+    gateway_response(gateway::get_block(&devnet, &query))
+}
+
+/// Converts a balance into a JSON number, which may not fit in 64 bits.
+fn balance_number(balance: &BigUint) -> Option<serde_json::Number> {
+    balance.to_string().parse().ok()
+}
+
+#[post("/mint")]
+async fn mint_req(data: web::Data<AppState>, args: web::Json<MintRequest>) -> HttpResponse {
+    let amount = match args.amount.to_string().parse::<BigUint>() {
+        Ok(amount) => amount,
+        Err(_) => return HttpResponse::BadRequest().body("the amount must be a natural number"),
+    };
+    let mut devnet = data.devnet.lock().unwrap();
+    match devnet.mint(&Address(args.address.0.clone()), &amount) {
+        Ok(balance) => HttpResponse::Ok().json(serde_json::json!({
+            "new_balance": balance_number(&balance),
+            "unit": "wei",
+            "tx_hash": null,
+        })),
+        Err(error) => HttpResponse::BadRequest().body(error.to_string()),
+    }
+}
+
+#[get("/account_balance")]
+async fn account_balance_req(
+    data: web::Data<AppState>,
+    query: web::Query<AccountBalanceQuery>,
+) -> HttpResponse {
+    let mut devnet = data.devnet.lock().unwrap();
+    match devnet.account_balance(&Address(query.address.0.clone())) {
+        Ok(balance) => HttpResponse::Ok().json(serde_json::json!({
+            "amount": balance_number(&balance),
+            "unit": "wei",
+        })),
+        Err(error) => HttpResponse::BadRequest().body(error.to_string()),
+    }
+}
+
+#[post("/create_block")]
+async fn create_block_req(data: web::Data<AppState>) -> HttpResponse {
+    let mut devnet = data.devnet.lock().unwrap();
+    let block_number = devnet.create_block().block_info.block_number;
+    data.after_transaction(&mut devnet);
+    gateway_response(gateway::block(&devnet, block_number))
+}
+
+#[post("/abort_blocks")]
+async fn abort_blocks_req(
+    data: web::Data<AppState>,
+    args: web::Json<AbortBlocksRequest>,
+) -> HttpResponse {
+    let mut devnet = data.devnet.lock().unwrap();
+    match devnet.abort_blocks(args.starting_block_number) {
+        Ok(aborted) => {
+            data.after_transaction(&mut devnet);
+            HttpResponse::Ok().json(serde_json::json!({ "aborted": aborted }))
+        }
+        Err(error) => HttpResponse::BadRequest().body(error.to_string()),
+    }
+}
+
+#[post("/increase_time")]
+async fn increase_time_req(
+    data: web::Data<AppState>,
+    args: web::Json<TimeRequest>,
+) -> HttpResponse {
+    let mut devnet = data.devnet.lock().unwrap();
+    devnet.increase_time(args.time);
+    HttpResponse::Ok().json(serde_json::json!({
+        "timestamp_increased_by": args.time,
+        "block_timestamp": devnet.starknet_state.general_config.block_info().block_timestamp,
+    }))
+}
+
+#[post("/set_time")]
+async fn set_time_req(data: web::Data<AppState>, args: web::Json<TimeRequest>) -> HttpResponse {
+    let mut devnet = data.devnet.lock().unwrap();
+    devnet.set_time(args.time);
+    HttpResponse::Ok().json(serde_json::json!({ "block_timestamp": args.time }))
+}
+
+#[post("/set_gas_price")]
+async fn set_gas_price_req(
+    data: web::Data<AppState>,
+    args: web::Json<GasPriceRequest>,
+) -> HttpResponse {
+    let mut devnet = data.devnet.lock().unwrap();
+    devnet.set_gas_price(args.gas_price);
+    HttpResponse::Ok().json(serde_json::json!({ "gas_price": args.gas_price }))
+}
+
+fn gateway_felts(felts: &[GatewayFelt]) -> Vec<Felt252> {
+    felts.iter().map(|felt| felt.0.clone()).collect()
+}
+
+fn message_to_l2_json(message: &MessageToL2) -> serde_json::Value {
+    serde_json::json!({
+        "l1_contract_address": GatewayFelt(message.from_address.clone()),
+        "l2_contract_address": GatewayFelt(message.to_address.0.clone()),
+        "entry_point_selector": GatewayFelt(message.entry_point_selector.clone()),
+        "payload": message.payload.iter().cloned().map(GatewayFelt).collect::<Vec<_>>(),
+        "nonce": GatewayFelt(message.nonce.clone()),
+    })
+}
+
+/// Handles a message sent from L1 right away, by executing its L1 handler.
+#[post("/postman/send_message_to_l2")]
+async fn send_message_to_l2_req(
+    data: web::Data<AppState>,
+    args: web::Json<MessageToL2Request>,
+) -> HttpResponse {
+    let message = MessageToL2 {
+        from_address: args.l1_contract_address.0.clone(),
+        to_address: Address(args.l2_contract_address.0.clone()),
+        entry_point_selector: args.entry_point_selector.0.clone(),
+        payload: gateway_felts(&args.payload),
+        nonce: args.nonce.0.clone(),
+    };
+    let mut devnet = data.devnet.lock().unwrap();
+    let result = devnet
+        .handle_message_to_l2(&message)
+        .map(|receipt| receipt.transaction_hash.clone());
+    match result {
+        Ok(tx_hash) => {
+            data.after_transaction(&mut devnet);
+            HttpResponse::Ok().json(serde_json::json!({
+                "transaction_hash": GatewayFelt(tx_hash)
+            }))
+        }
+        Err(error) => HttpResponse::BadRequest().body(error.to_string()),
+    }
+}
+
+/// Sends a message to L2 on the mock L1, to be handled by the next flush.
+#[post("/postman/queue_message_to_l2")]
+async fn queue_message_to_l2_req(
+    data: web::Data<AppState>,
+    args: web::Json<QueuedMessageToL2Request>,
+) -> HttpResponse {
+    let mut devnet = data.devnet.lock().unwrap();
+    let message = devnet.postman.mock_l1.send_message_to_l2(
+        args.l1_contract_address.0.clone(),
+        Address(args.l2_contract_address.0.clone()),
+        args.entry_point_selector.0.clone(),
+        gateway_felts(&args.payload),
+    );
+    HttpResponse::Ok().json(message_to_l2_json(&message))
+}
+
+/// Consumes on L1 a message sent from L2, as the L1 contract it was sent to would.
+#[post("/postman/consume_message_from_l2")]
+async fn consume_message_from_l2_req(
+    data: web::Data<AppState>,
+    args: web::Json<MessageFromL2Request>,
+) -> HttpResponse {
+    let message = StarknetMessageToL1::new(
+        Address(args.l2_contract_address.0.clone()),
+        Address(args.l1_contract_address.0.clone()),
+        gateway_felts(&args.payload),
+    );
+    let mut devnet = data.devnet.lock().unwrap();
+    match devnet.consume_message_from_l2(&message) {
+        Ok(message_hash) => HttpResponse::Ok().json(serde_json::json!({
+            "message_hash": format!("0x{}", hex::encode(message_hash)),
+        })),
+        Err(error) => HttpResponse::BadRequest().body(error.to_string()),
+    }
+}
+
+/// Exchanges the pending messages with L1, in both directions. A message to L2 that can't be
+/// handled fails the request, which still reports the messages exchanged before it.
+#[post("/postman/flush")]
+async fn flush_req(data: web::Data<AppState>) -> HttpResponse {
+    let mut devnet = data.devnet.lock().unwrap();
+    let result = data.postman.lock().unwrap().flush(&mut devnet);
+    match result {
+        Ok(flushed) => {
+            if !flushed.generated_l2_transactions.is_empty() {
+                data.after_transaction(&mut devnet);
+            }
+            let mut response = match &flushed.error {
+                Some(_) => HttpResponse::BadRequest(),
+                None => HttpResponse::Ok(),
+            };
+            response.json(serde_json::json!({
+                "messages_to_l1": flushed
+                    .messages_to_l1
+                    .iter()
+                    .map(RpcMessageToL1::from)
+                    .collect::<Vec<_>>(),
+                "messages_to_l2": flushed
+                    .messages_to_l2
+                    .iter()
+                    .map(message_to_l2_json)
+                    .collect::<Vec<_>>(),
+                "generated_l2_transactions": flushed
+                    .generated_l2_transactions
+                    .into_iter()
+                    .map(GatewayFelt)
+                    .collect::<Vec<_>>(),
+                "error": flushed.error.map(|error| error.to_string()),
+            }))
+        }
+        Err(error) => HttpResponse::BadRequest().body(error.to_string()),
+    }
+}
+
+/// Returns the path of a `/dump` or `/load` request, or the `--dump-path` if it has none.
+fn dump_request_path(data: &AppState, body: &[u8]) -> Result<PathBuf, String> {
+    let request: DumpRequest = match body {
+        [] => DumpRequest { path: None },
+        body => serde_json::from_slice(body).map_err(|error| error.to_string())?,
+    };
+    request
+        .path
+        .or_else(|| data.dump_path.clone())
+        .ok_or_else(|| "no path given and no --dump-path set".to_string())
+}
+
+#[post("/dump")]
+async fn dump_req(data: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
+    let devnet = data.devnet.lock().unwrap();
+    let result = dump_request_path(&data, &body).and_then(|path| {
+        devnet
+            .dump_to_file(&path)
+            .map_err(|error| error.to_string())
+    });
+    match result {
+        Ok(()) => HttpResponse::Ok().finish(),
+        Err(error) => HttpResponse::BadRequest().body(error),
+    }
+}
+
+#[post("/load")]
+async fn load_req(data: web::Data<AppState>, body: web::Bytes) -> HttpResponse {
+    let mut devnet = data.devnet.lock().unwrap();
+    let result = dump_request_path(&data, &body).and_then(|path| {
+        devnet
+            .load_from_file(&path)
+            .map_err(|error| error.to_string())
+    });
+    match result {
+        Ok(()) => HttpResponse::Ok().finish(),
+        Err(error) => HttpResponse::BadRequest().body(error),
+    }
+}
+
+pub async fn start_devnet(
+    devnet_args: &DevnetArgs,
+    devnet: DevnetState,
+) -> Result<(), std::io::Error> {
+    let app_state = web::Data::new(AppState {
+        devnet: Mutex::new(devnet),
+        dump_path: devnet_args.dump_path.clone(),
+        dump_on: devnet_args.dump_on,
+        subscribers: Mutex::new(HashMap::new()),
+        postman: Mutex::new(Postman::default()),
     });
+    let server_state = app_state.clone();
 
     HttpServer::new(move || {
         App::new()
-            .app_data(cached_state.clone())
+            .app_data(server_state.clone())
             .service(declare_req)
             .service(deploy_req)
             .service(invoke_req)
             .service(call_req)
+            .service(rpc_req)
+            .service(ws_req)
+            .service(add_transaction_req)
+            .service(call_contract_req)
+            .service(estimate_fee_req)
+            .service(get_transaction_receipt_req)
+            .service(get_class_hash_at_req)
+            .service(get_storage_at_req)
+            .service(get_block_req)
+            .service(mint_req)
+            .service(account_balance_req)
+            .service(create_block_req)
+            .service(abort_blocks_req)
+            .service(increase_time_req)
+            .service(set_time_req)
+            .service(set_gas_price_req)
+            .service(send_message_to_l2_req)
+            .service(queue_message_to_l2_req)
+            .service(consume_message_from_l2_req)
+            .service(flush_req)
+            .service(dump_req)
+            .service(load_req)
     })
-    .bind(("127.0.0.1", port))?
+    .bind(("127.0.0.1", devnet_args.port))?
     .run()
-    .await
+    .await?;
+
+    if let (Some(DumpOn::Exit), Some(dump_path)) = (app_state.dump_on, &app_state.dump_path) {
+        let devnet = app_state.devnet.lock().unwrap();
+        if let Err(error) = devnet.dump_to_file(dump_path) {
+            eprintln!("Failed to dump the devnet: {error}");
+        }
+    }
+    Ok(())
+}
+
+/// Runs a subcommand on the state dumped to a file, without a devnet server, and dumps the
+/// resulting state back to it. The file is created if it doesn't exist.
+fn run_locally<T>(
+    state_path: &Path,
+    command: impl FnOnce(&mut DevnetState) -> Result<T, ParserError>,
+) -> Result<T, ParserError> {
+    let mut devnet = DevnetState::default();
+    if state_path.exists() {
+        devnet.load_from_file(state_path)?;
+    }
+    let output = command(&mut devnet)?;
+    devnet.dump_to_file(state_path)?;
+    Ok(output)
+}
+
+/// Sends a subcommand to the endpoint of a running devnet and returns its response.
+async fn send_request<A: Serialize, T: DeserializeOwned>(
+    url: &str,
+    endpoint: &str,
+    args: &A,
+) -> Result<T, ParserError> {
+    let mut response = awc::Client::new()
+        .post(format!("{}/{endpoint}", url.trim_end_matches('/')))
+        .send_json(args)
+        .await
+        .map_err(|error| ParserError::Request(error.to_string()))?;
+    if !response.status().is_success() {
+        let body = response.body().await.unwrap_or_default();
+        return Err(ParserError::Request(
+            String::from_utf8_lossy(&body).into_owned(),
+        ));
+    }
+    response
+        .json()
+        .await
+        .map_err(|error| ParserError::Request(error.to_string()))
 }
 
 #[actix_web::main]
@@ -328,72 +1061,136 @@ async fn main() -> Result<(), ParserError> {
     let cli = Cli::parse();
     match &cli.command {
         Commands::Declare(declare_args) => {
-            let response = awc::Client::new()
-                .post("http://127.0.0.1:7878/declare")
-                .send_json(&declare_args)
-                .await;
-            match response {
-                Ok(mut resp) => {
-                    match resp.json::<(Felt252, Felt252)>().await {
-                        Ok(body) => println!("Declare transaction was sent.\nContract class hash: 0x{:x}\nTransaction hash: 0x{:x}", body.0.to_biguint(), body.1.to_biguint()),
-                        Err(e) => println!("{e}")
-                    }
-                },
-                Err(ref e) => println!("{e}"),
+            let (class_hash, tx_hash): (Felt252, Felt252) = match &cli.state {
+                Some(state_path) => {
+                    run_locally(state_path, |devnet| declare_parser(devnet, declare_args))?
+                }
+                None => send_request(&cli.url, "declare", declare_args).await?,
             };
-            Ok(())
+            println!("Declare transaction was sent.\nContract class hash: 0x{:x}\nTransaction hash: 0x{:x}", class_hash.to_biguint(), tx_hash.to_biguint());
         }
         Commands::Deploy(deploy_args) => {
-            let response = awc::Client::new()
-                .post("http://127.0.0.1:7878/deploy")
-                .send_json(&deploy_args)
-                .await;
-            match response {
-                Ok(mut resp) => {
-                    match resp.json::<(Felt252, Felt252)>().await {
-                        Ok(body) => println!("Invoke transaction for contract deployment was sent.\nContract address: 0x{:x}\nTransaction hash: 0x{:x}", body.0.to_biguint(), body.1.to_biguint()),
-                        Err(e) => println!("{e}")
-                    }
-                },
-                Err(ref e) => println!("{e}"),
+            let (address, tx_hash): (Felt252, Felt252) = match &cli.state {
+                Some(state_path) => run_locally(state_path, |devnet| {
+                    deploy_parser(&mut devnet.starknet_state.state, deploy_args)
+                })?,
+                None => send_request(&cli.url, "deploy", deploy_args).await?,
             };
-            Ok(())
+            println!("Invoke transaction for contract deployment was sent.\nContract address: 0x{:x}\nTransaction hash: 0x{:x}", address.to_biguint(), tx_hash.to_biguint());
         }
         Commands::Invoke(invoke_args) => {
-            let response = awc::Client::new()
-                .post("http://127.0.0.1:7878/invoke")
-                .send_json(&invoke_args)
-                .await;
-            match response {
-                Ok(mut resp) => {
-                    match resp.json::<(Felt252, Felt252)>().await {
-                        Ok(body) => println!("Invoke transaction was sent.\nContract address: 0x{:x}\nTransaction hash: 0x{:x}", body.0.to_biguint(), body.1.to_biguint()),
-                        Err(e) => println!("{e}")
-                    }
-                },
-                Err(ref e) => println!("{e}"),
+            let (address, tx_hash): (Felt252, Felt252) = match &cli.state {
+                Some(state_path) => run_locally(state_path, |devnet| {
+                    invoke_parser(&mut devnet.starknet_state.state, invoke_args)
+                })?,
+                None => send_request(&cli.url, "invoke", invoke_args).await?,
             };
-            Ok(())
+            println!(
+                "Invoke transaction was sent.\nContract address: 0x{:x}\nTransaction hash: 0x{:x}",
+                address.to_biguint(),
+                tx_hash.to_biguint()
+            );
         }
         Commands::Call(call_args) => {
-            let response = awc::Client::new()
-                .post("http://127.0.0.1:7878/call")
-                .send_json(&call_args)
-                .await;
-            match response {
-                Ok(mut resp) => match resp.json::<Vec<Felt252>>().await {
-                    Ok(body) => println!(
-                        "{}",
-                        body.iter()
-                            .fold(String::new(), |acc, arg| acc + &format!("{arg}"))
-                    ),
-                    Err(e) => println!("{e}"),
-                },
-                Err(ref e) => println!("{e}"),
+            let outputs: Vec<(String, serde_json::Value)> = match &cli.state {
+                Some(state_path) => run_locally(state_path, |devnet| {
+                    call_parser(&mut devnet.starknet_state.state, call_args)
+                })?,
+                None => send_request(&cli.url, "call", call_args).await?,
             };
-            Ok(())
+            for (name, value) in outputs {
+                println!("{name}: {value}");
+            }
         }
-        Commands::Devnet(devnet_args) => devnet_parser(devnet_args).await,
-    }?;
+        Commands::Devnet(devnet_args) => devnet_parser(devnet_args).await?,
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use coverage_helper::test;
+    use starknet_rs::core::errors::abi_errors::AbiError;
+
+    /// Temporary file, removed when dropped even if the test fails.
+    struct TempFile(PathBuf);
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn subcommands_run_on_the_same_state_file() {
+        let state_file = TempFile(std::env::temp_dir().join(format!(
+            "starknet_rs_run_locally_test_{}.json",
+            std::process::id()
+        )));
+        let state_path = &state_file.0;
+
+        let declare_args = DeclareArgs {
+            contract: PathBuf::from("starknet_programs/fibonacci.json"),
+        };
+        let (class_hash, _) =
+            run_locally(state_path, |devnet| declare_parser(devnet, &declare_args)).unwrap();
+        assert!(state_path.exists());
+
+        let deploy_args = DeployArgs {
+            class_hash: format!("0x{:x}", class_hash.to_biguint()),
+            salt: 1111,
+            inputs: None,
+        };
+        let (address, _) = run_locally(state_path, |devnet| {
+            deploy_parser(&mut devnet.starknet_state.state, &deploy_args)
+        })
+        .unwrap();
+
+        // The state file holds the results of both subcommands.
+        let mut devnet = DevnetState::default();
+        devnet.load_from_file(state_path).unwrap();
+        let state = &mut devnet.starknet_state.state;
+        assert!(state.get_contract_class(&class_hash.to_be_bytes()).is_ok());
+        assert_eq!(
+            state.get_class_hash_at(&Address(address)).unwrap(),
+            class_hash.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn constructor_arguments_are_encoded_with_the_abi() {
+        let mut devnet = DevnetState::default();
+        let declare_args = DeclareArgs {
+            contract: PathBuf::from("starknet_programs/constructor.json"),
+        };
+        let (class_hash, _) = declare_parser(&mut devnet, &declare_args).unwrap();
+
+        let mut deploy_args = DeployArgs {
+            class_hash: format!("0x{:x}", class_hash.to_biguint()),
+            salt: 1111,
+            inputs: Some(vec!["0x123".to_string()]),
+        };
+        let (address, _) = deploy_parser(&mut devnet.starknet_state.state, &deploy_args).unwrap();
+        assert_eq!(
+            address,
+            calculate_contract_address(
+                &Address(1111.into()),
+                &class_hash,
+                &[0x123.into()],
+                Address(0.into()),
+            )
+            .unwrap()
+        );
+
+        deploy_args.inputs = None;
+        assert_matches!(
+            deploy_parser(&mut devnet.starknet_state.state, &deploy_args),
+            Err(ParserError::Abi(AbiError::ArgumentCount {
+                expected: 1,
+                got: 0
+            }))
+        );
+    }
+}
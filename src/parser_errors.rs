@@ -1,9 +1,10 @@
 use crate::{
-    business_logic::transaction::error::TransactionError,
+    business_logic::transaction::error::{RevertError, TransactionError},
     core::errors::{
-        contract_address_errors::ContractAddressError, state_errors::StateError,
-        syscall_handler_errors::SyscallHandlerError,
+        abi_errors::AbiError, contract_address_errors::ContractAddressError,
+        state_errors::StateError, syscall_handler_errors::SyscallHandlerError,
     },
+    devnet::error::DevnetError,
     services::api::contract_class::EntryPointType,
 };
 use thiserror::Error;
@@ -28,4 +29,12 @@ pub enum ParserError {
     State(#[from] StateError),
     #[error(transparent)]
     Transaction(#[from] TransactionError),
+    #[error("Transaction reverted: {0}")]
+    Reverted(RevertError),
+    #[error(transparent)]
+    Abi(#[from] AbiError),
+    #[error(transparent)]
+    Devnet(#[from] DevnetError),
+    #[error("Request to the devnet failed: {0}")]
+    Request(String),
 }
@@ -0,0 +1,575 @@
+use super::contract_abi::{AbiParameter, ContractAbi};
+use crate::{core::errors::abi_errors::AbiError, utils::split_uint256};
+use cairo_vm::felt::{Felt252, PRIME_STR};
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+use num_traits::{Num, ToPrimitive};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Maximum length of a Cairo short string, the longest ASCII string that fits in a felt.
+const MAX_SHORT_STRING_LENGTH: usize = 31;
+
+lazy_static! {
+    static ref FELT_PRIME: BigUint =
+        BigUint::from_str_radix(PRIME_STR.trim_start_matches("0x"), 16).unwrap();
+}
+
+/// Cairo 0 type of an ABI parameter or struct member.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParamType {
+    Felt,
+    /// Array of the pointed type, preceded by its length in the calldata.
+    Pointer(Box<ParamType>),
+    Struct(String),
+    /// Tuple whose items may have names.
+    Tuple(Vec<(Option<String>, ParamType)>),
+}
+
+/// Value of an ABI parameter, decoded from the return data of a function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AbiValue {
+    Felt(Felt252),
+    Uint256(BigUint),
+    Array(Vec<AbiValue>),
+    /// Members of a struct or items of a named tuple, in order.
+    Struct(Vec<(String, AbiValue)>),
+    Tuple(Vec<AbiValue>),
+}
+
+impl FromStr for ParamType {
+    type Err = AbiError;
+
+    fn from_str(type_name: &str) -> Result<Self, AbiError> {
+        let type_name = type_name.trim();
+        let invalid_type = || AbiError::InvalidType(type_name.to_string());
+
+        if let Some(pointed) = type_name.strip_suffix('*') {
+            return Ok(ParamType::Pointer(Box::new(pointed.parse()?)));
+        }
+        if let Some(items) = type_name
+            .strip_prefix('(')
+            .and_then(|items| items.strip_suffix(')'))
+        {
+            return split_top_level(items, ',')
+                .into_iter()
+                .filter(|item| !item.trim().is_empty())
+                .map(|item| match split_top_level(item, ':').as_slice() {
+                    [item_type] => Ok((None, item_type.parse()?)),
+                    [name, item_type] => Ok((Some(name.trim().to_string()), item_type.parse()?)),
+                    _ => Err(invalid_type()),
+                })
+                .collect::<Result<_, _>>()
+                .map(ParamType::Tuple);
+        }
+        match type_name {
+            "felt" => Ok(ParamType::Felt),
+            name if !name.is_empty()
+                && name
+                    .chars()
+                    .all(|char| char.is_ascii_alphanumeric() || char == '_' || char == '.') =>
+            {
+                Ok(ParamType::Struct(name.to_string()))
+            }
+            _ => Err(invalid_type()),
+        }
+    }
+}
+
+/// Splits a string at the separators that aren't inside parentheses.
+fn split_top_level(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0_usize, 0);
+    for (index, char) in value.char_indices() {
+        match char {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            char if char == separator && depth == 0 => {
+                parts.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+impl ContractAbi {
+    /// Encodes human-readable arguments into the calldata of a function. Felts are given as
+    /// JSON numbers, hex or decimal strings, or short strings. `Uint256` values can also be
+    /// given as a single number. Structs and tuples are given as JSON objects or arrays, and
+    /// arrays as JSON arrays, without their length parameter.
+    pub fn encode_inputs(&self, function: &str, args: &[Value]) -> Result<Vec<Felt252>, AbiError> {
+        let function = self
+            .function(function)
+            .ok_or_else(|| AbiError::FunctionNotFound(function.to_string()))?;
+        let params = given_parameters(&function.inputs)?;
+        if params.len() != args.len() {
+            return Err(AbiError::ArgumentCount {
+                expected: params.len(),
+                got: args.len(),
+            });
+        }
+
+        let mut calldata = Vec::new();
+        for ((_, param_type), arg) in params.iter().zip(args) {
+            self.encode_value(param_type, arg, &mut calldata)?;
+        }
+        Ok(calldata)
+    }
+
+    /// Decodes the return data of a function into its named outputs. The length parameters of
+    /// arrays aren't returned separately.
+    pub fn decode_outputs(
+        &self,
+        function: &str,
+        retdata: &[Felt252],
+    ) -> Result<Vec<(String, AbiValue)>, AbiError> {
+        let function = self
+            .function(function)
+            .ok_or_else(|| AbiError::FunctionNotFound(function.to_string()))?;
+        let mut retdata = retdata.iter();
+
+        let outputs = given_parameters(&function.outputs)?
+            .into_iter()
+            .map(|(name, param_type)| {
+                Ok((
+                    name.to_string(),
+                    self.decode_value(&param_type, &mut retdata)?,
+                ))
+            })
+            .collect::<Result<_, AbiError>>()?;
+        match retdata.len() {
+            0 => Ok(outputs),
+            extra => Err(AbiError::ExtraRetdata(extra)),
+        }
+    }
+
+    fn encode_value(
+        &self,
+        param_type: &ParamType,
+        value: &Value,
+        calldata: &mut Vec<Felt252>,
+    ) -> Result<(), AbiError> {
+        match (param_type, value) {
+            (ParamType::Felt, value) => calldata.push(parse_felt(value)?),
+            (ParamType::Pointer(item_type), Value::Array(items)) => {
+                calldata.push(items.len().into());
+                for item in items {
+                    self.encode_value(item_type, item, calldata)?;
+                }
+            }
+            (ParamType::Struct(name), Value::Number(_) | Value::String(_)) if name == "Uint256" => {
+                let (low, high) = split_uint256(&parse_uint256(value)?);
+                calldata.extend([low, high]);
+            }
+            (ParamType::Struct(name), value) => {
+                let mut members = self
+                    .struct_definition(name)
+                    .ok_or_else(|| AbiError::UnknownType(name.clone()))?
+                    .members
+                    .clone();
+                members.sort_by_key(|member| member.offset);
+                let members = members
+                    .into_iter()
+                    .map(|member| Ok((Some(member.name), member.type_name.parse()?)))
+                    .collect::<Result<Vec<_>, AbiError>>()?;
+                self.encode_items(param_type, &members, value, calldata)?;
+            }
+            (ParamType::Tuple(items), value) => {
+                self.encode_items(param_type, items, value, calldata)?
+            }
+            (param_type, value) => return Err(invalid_value(param_type, value)),
+        }
+        Ok(())
+    }
+
+    /// Encodes the members of a struct or the items of a tuple, given as a JSON array in order
+    /// or as a JSON object by name.
+    fn encode_items(
+        &self,
+        param_type: &ParamType,
+        items: &[(Option<String>, ParamType)],
+        value: &Value,
+        calldata: &mut Vec<Felt252>,
+    ) -> Result<(), AbiError> {
+        match value {
+            Value::Array(values) if values.len() == items.len() => {
+                for ((_, item_type), value) in items.iter().zip(values) {
+                    self.encode_value(item_type, value, calldata)?;
+                }
+            }
+            Value::Object(values) if values.len() == items.len() => {
+                for (name, item_type) in items {
+                    let value = name
+                        .as_ref()
+                        .and_then(|name| values.get(name))
+                        .ok_or_else(|| invalid_value(param_type, value))?;
+                    self.encode_value(item_type, value, calldata)?;
+                }
+            }
+            value => return Err(invalid_value(param_type, value)),
+        }
+        Ok(())
+    }
+
+    fn decode_value<'a>(
+        &self,
+        param_type: &ParamType,
+        retdata: &mut impl Iterator<Item = &'a Felt252>,
+    ) -> Result<AbiValue, AbiError> {
+        match param_type {
+            ParamType::Felt => retdata
+                .next()
+                .cloned()
+                .map(AbiValue::Felt)
+                .ok_or(AbiError::MissingRetdata),
+            ParamType::Pointer(item_type) => {
+                let length = retdata
+                    .next()
+                    .ok_or(AbiError::MissingRetdata)?
+                    .to_usize()
+                    .ok_or_else(|| AbiError::InvalidType(param_type_name(param_type)))?;
+                (0..length)
+                    .map(|_| self.decode_value(item_type, retdata))
+                    .collect::<Result<_, _>>()
+                    .map(AbiValue::Array)
+            }
+            ParamType::Struct(name) if name == "Uint256" => {
+                let low = retdata.next().ok_or(AbiError::MissingRetdata)?;
+                let high = retdata.next().ok_or(AbiError::MissingRetdata)?;
+                Ok(AbiValue::Uint256(
+                    low.to_biguint() + (high.to_biguint() << 128_usize),
+                ))
+            }
+            ParamType::Struct(name) => {
+                let mut members = self
+                    .struct_definition(name)
+                    .ok_or_else(|| AbiError::UnknownType(name.clone()))?
+                    .members
+                    .clone();
+                members.sort_by_key(|member| member.offset);
+                members
+                    .iter()
+                    .map(|member| {
+                        let member_type = member.type_name.parse()?;
+                        Ok((
+                            member.name.clone(),
+                            self.decode_value(&member_type, retdata)?,
+                        ))
+                    })
+                    .collect::<Result<_, AbiError>>()
+                    .map(AbiValue::Struct)
+            }
+            ParamType::Tuple(items) if items.iter().all(|(name, _)| name.is_some()) => items
+                .iter()
+                .map(|(name, item_type)| {
+                    Ok((
+                        name.clone().unwrap_or_default(),
+                        self.decode_value(item_type, retdata)?,
+                    ))
+                })
+                .collect::<Result<_, AbiError>>()
+                .map(AbiValue::Struct),
+            ParamType::Tuple(items) => items
+                .iter()
+                .map(|(_, item_type)| self.decode_value(item_type, retdata))
+                .collect::<Result<_, _>>()
+                .map(AbiValue::Tuple),
+        }
+    }
+}
+
+/// Returns the parameters of a function along with their types, leaving out the length
+/// parameters of arrays, which are named after the array with a `_len` suffix and come right
+/// before it.
+fn given_parameters(params: &[AbiParameter]) -> Result<Vec<(&str, ParamType)>, AbiError> {
+    let mut given: Vec<(&str, ParamType)> = Vec::new();
+    for param in params {
+        let param_type = param.type_name.parse()?;
+        if let ParamType::Pointer(_) = param_type {
+            match given.last() {
+                Some((name, ParamType::Felt)) if *name == format!("{}_len", param.name) => {
+                    given.pop();
+                }
+                _ => return Err(AbiError::ArrayWithoutLength(param.name.clone())),
+            }
+        }
+        given.push((param.name.as_str(), param_type));
+    }
+    Ok(given)
+}
+
+/// Parses a felt given as a JSON number, a hex or decimal string, or a short string.
+fn parse_felt(value: &Value) -> Result<Felt252, AbiError> {
+    let text = match value {
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => text.clone(),
+        value => return Err(invalid_value(&ParamType::Felt, value)),
+    };
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.as_str()),
+    };
+
+    let number = match digits.strip_prefix("0x") {
+        Some(hex) => BigUint::from_str_radix(hex, 16).ok(),
+        None if !digits.is_empty() && digits.chars().all(|char| char.is_ascii_digit()) => {
+            BigUint::from_str_radix(digits, 10).ok()
+        }
+        // Anything else is a short string, unless it is a JSON number that isn't an integer.
+        None if value.is_string()
+            && !negative
+            && text.is_ascii()
+            && text.len() <= MAX_SHORT_STRING_LENGTH =>
+        {
+            Some(BigUint::from_bytes_be(text.as_bytes()))
+        }
+        None => None,
+    }
+    .filter(|number| number < &*FELT_PRIME)
+    .ok_or_else(|| invalid_value(&ParamType::Felt, value))?;
+
+    let felt = Felt252::from(number);
+    Ok(if negative { -felt } else { felt })
+}
+
+fn parse_uint256(value: &Value) -> Result<BigUint, AbiError> {
+    let invalid = || invalid_value(&ParamType::Struct("Uint256".to_string()), value);
+    let text = match value {
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => text.clone(),
+        _ => return Err(invalid()),
+    };
+    match text.strip_prefix("0x") {
+        Some(hex) => BigUint::from_str_radix(hex, 16),
+        None => BigUint::from_str_radix(&text, 10),
+    }
+    .ok()
+    .filter(|number| number.bits() <= 256)
+    .ok_or_else(invalid)
+}
+
+fn invalid_value(param_type: &ParamType, value: &Value) -> AbiError {
+    AbiError::InvalidValue(param_type_name(param_type), value.to_string())
+}
+
+fn param_type_name(param_type: &ParamType) -> String {
+    match param_type {
+        ParamType::Felt => "felt".to_string(),
+        ParamType::Pointer(item_type) => format!("{}*", param_type_name(item_type)),
+        ParamType::Struct(name) => name.clone(),
+        ParamType::Tuple(items) => {
+            let items = items
+                .iter()
+                .map(|(name, item_type)| match name {
+                    Some(name) => format!("{name}: {}", param_type_name(item_type)),
+                    None => param_type_name(item_type),
+                })
+                .collect::<Vec<_>>();
+            format!("({})", items.join(", "))
+        }
+    }
+}
+
+/// Felts and `Uint256` values are serialized as hex strings, structs and named tuples as JSON
+/// objects, and arrays and tuples as JSON arrays.
+impl Serialize for AbiValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            AbiValue::Felt(felt) => {
+                serializer.serialize_str(&format!("0x{}", felt.to_str_radix(16)))
+            }
+            AbiValue::Uint256(value) => serializer.serialize_str(&format!("0x{value:x}")),
+            AbiValue::Array(items) | AbiValue::Tuple(items) => items.serialize(serializer),
+            AbiValue::Struct(members) => {
+                let mut map = serializer.serialize_map(Some(members.len()))?;
+                for (name, value) in members {
+                    map.serialize_entry(name, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+    use num_traits::One;
+    use serde_json::json;
+
+    fn abi() -> ContractAbi {
+        serde_json::from_value(json!([
+            {
+                "type": "struct",
+                "name": "Uint256",
+                "size": 2,
+                "members": [
+                    {"name": "low", "type": "felt", "offset": 0},
+                    {"name": "high", "type": "felt", "offset": 1},
+                ],
+            },
+            {
+                "type": "struct",
+                "name": "Point",
+                "size": 2,
+                "members": [
+                    {"name": "y", "type": "felt", "offset": 1},
+                    {"name": "x", "type": "felt", "offset": 0},
+                ],
+            },
+            {
+                "type": "function",
+                "name": "store",
+                "inputs": [
+                    {"name": "name", "type": "felt"},
+                    {"name": "amount", "type": "Uint256"},
+                    {"name": "points_len", "type": "felt"},
+                    {"name": "points", "type": "Point*"},
+                    {"name": "pair", "type": "(felt, felt)"},
+                ],
+                "outputs": [
+                    {"name": "total", "type": "Uint256"},
+                    {"name": "values_len", "type": "felt"},
+                    {"name": "values", "type": "felt*"},
+                    {"name": "origin", "type": "Point"},
+                ],
+                "stateMutability": "view",
+            },
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn param_types_are_parsed() {
+        assert_eq!("felt".parse::<ParamType>().unwrap(), ParamType::Felt);
+        assert_eq!(
+            "Point**".parse::<ParamType>().unwrap(),
+            ParamType::Pointer(Box::new(ParamType::Pointer(Box::new(ParamType::Struct(
+                "Point".to_string()
+            )))))
+        );
+        assert_eq!(
+            "(a: felt, b: (felt, Point))".parse::<ParamType>().unwrap(),
+            ParamType::Tuple(vec![
+                (Some("a".to_string()), ParamType::Felt),
+                (
+                    Some("b".to_string()),
+                    ParamType::Tuple(vec![
+                        (None, ParamType::Felt),
+                        (None, ParamType::Struct("Point".to_string())),
+                    ])
+                ),
+            ])
+        );
+        assert_matches!("felt$".parse::<ParamType>(), Err(AbiError::InvalidType(_)));
+    }
+
+    #[test]
+    fn inputs_are_encoded() {
+        let args = [
+            json!("hi"),
+            json!("0x100000000000000000000000000000002"),
+            json!([{"x": 1, "y": 2}, [3, "-1"]]),
+            json!(["0x5", "6"]),
+        ];
+
+        assert_eq!(
+            abi().encode_inputs("store", &args).unwrap(),
+            vec![
+                Felt252::from(0x6869),
+                Felt252::from(2),
+                Felt252::from(1),
+                Felt252::from(2),
+                Felt252::from(1),
+                Felt252::from(2),
+                Felt252::from(3),
+                -Felt252::from(1),
+                Felt252::from(5),
+                Felt252::from(6),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_inputs_are_rejected() {
+        let abi = abi();
+
+        assert_matches!(
+            abi.encode_inputs("unknown", &[]),
+            Err(AbiError::FunctionNotFound(_))
+        );
+        assert_matches!(
+            abi.encode_inputs("store", &[json!(1)]),
+            Err(AbiError::ArgumentCount {
+                expected: 4,
+                got: 1
+            })
+        );
+        assert_matches!(
+            abi.encode_inputs(
+                "store",
+                &[
+                    json!("a string that is longer than 31 characters"),
+                    json!(1),
+                    json!([]),
+                    json!([1, 2]),
+                ]
+            ),
+            Err(AbiError::InvalidValue(type_name, _)) if type_name == "felt"
+        );
+        assert_matches!(
+            abi.encode_inputs(
+                "store",
+                &[json!(1), json!(1), json!([{"x": 1}]), json!([1, 2])]
+            ),
+            Err(AbiError::InvalidValue(type_name, _)) if type_name == "Point"
+        );
+    }
+
+    #[test]
+    fn outputs_are_decoded() {
+        let retdata = [1, 1, 2, 7, 8, 3, 4].map(Felt252::from);
+
+        let outputs = abi().decode_outputs("store", &retdata).unwrap();
+
+        assert_eq!(
+            outputs,
+            vec![
+                (
+                    "total".to_string(),
+                    AbiValue::Uint256((BigUint::one() << 128_usize) + 1_u32)
+                ),
+                (
+                    "values".to_string(),
+                    AbiValue::Array(vec![AbiValue::Felt(7.into()), AbiValue::Felt(8.into())])
+                ),
+                (
+                    "origin".to_string(),
+                    AbiValue::Struct(vec![
+                        ("x".to_string(), AbiValue::Felt(3.into())),
+                        ("y".to_string(), AbiValue::Felt(4.into())),
+                    ])
+                ),
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&outputs[2].1).unwrap(),
+            json!({"x": "0x3", "y": "0x4"})
+        );
+        assert_matches!(
+            abi().decode_outputs("store", &retdata[..6]),
+            Err(AbiError::MissingRetdata)
+        );
+        let mut longer_retdata = retdata.to_vec();
+        longer_retdata.push(0.into());
+        assert_matches!(
+            abi().decode_outputs("store", &longer_retdata),
+            Err(AbiError::ExtraRetdata(1))
+        );
+    }
+}
@@ -1,45 +1,143 @@
-use crate::services::api::contract_class::EntryPointType;
-use serde::Deserialize;
-use serde_json::Value;
-use std::{collections::HashMap, fs::File, path::PathBuf};
-
-#[allow(dead_code)]
-#[derive(Deserialize, Debug)]
-struct Signature {
-    inputs: Value,
-    name: String,
-    outputs: Value,
-    #[serde(default, rename = "stateMutability")]
-    state_mutability: Option<String>,
+use crate::{
+    core::errors::abi_errors::AbiError, public::abi::AbiType,
+    services::api::contract_class::EntryPointType,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+/// ABI of a Cairo 0 contract, as found in the compiled contract or in the `--abi_output` file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ContractAbi {
+    entries: Vec<AbiEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AbiEntry {
+    Function(AbiFunction),
+    Constructor(AbiFunction),
+    L1Handler(AbiFunction),
+    Struct(AbiStruct),
+    Event(AbiEvent),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiFunction {
+    pub name: String,
+    pub inputs: Vec<AbiParameter>,
+    #[serde(default)]
+    pub outputs: Vec<AbiParameter>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiStruct {
+    pub name: String,
+    pub size: usize,
+    pub members: Vec<AbiMember>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiMember {
+    pub name: String,
     #[serde(rename = "type")]
-    type_name: String,
+    pub type_name: String,
+    pub offset: usize,
 }
 
-// We should should consider reading all the information from the abi in the future. Right now we are not considering:
-// - type: "event"
-// - type: "struct"
-pub fn read_abi(abi_name: &PathBuf) -> HashMap<String, (usize, EntryPointType)> {
-    let abi: Vec<Signature> = serde_json::from_reader(&File::open(abi_name).unwrap()).unwrap();
-    let mut func_type_counter: HashMap<String, usize> = HashMap::new();
-    let mut result_hash_map: HashMap<String, (usize, EntryPointType)> = HashMap::new();
-
-    for function in abi {
-        let function_address = match func_type_counter.get(&function.type_name) {
-            Some(number) => number + 1,
-            None => 0,
-        };
-
-        let entry_point_type = match &function.type_name {
-            type_name if type_name == "function" => EntryPointType::External,
-            type_name if type_name == "constructor" => EntryPointType::Constructor,
-            _ => EntryPointType::L1Handler,
-        };
-
-        func_type_counter.insert(function.type_name, function_address);
-        result_hash_map.insert(function.name, (function_address, entry_point_type));
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiEvent {
+    pub name: String,
+    #[serde(default)]
+    pub keys: Vec<AbiParameter>,
+    #[serde(default)]
+    pub data: Vec<AbiParameter>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbiParameter {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+}
+
+impl ContractAbi {
+    pub fn new(entries: Vec<AbiEntry>) -> Self {
+        ContractAbi { entries }
     }
 
-    result_hash_map
+    pub fn from_file(path: &Path) -> Result<Self, AbiError> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+
+    pub fn entries(&self) -> &[AbiEntry] {
+        &self.entries
+    }
+
+    /// Returns the function, constructor or L1 handler with the given name.
+    pub fn function(&self, name: &str) -> Option<&AbiFunction> {
+        self.entries.iter().find_map(|entry| match entry {
+            AbiEntry::Function(function)
+            | AbiEntry::Constructor(function)
+            | AbiEntry::L1Handler(function)
+                if function.name == name =>
+            {
+                Some(function)
+            }
+            _ => None,
+        })
+    }
+
+    pub fn struct_definition(&self, name: &str) -> Option<&AbiStruct> {
+        self.entries.iter().find_map(|entry| match entry {
+            AbiEntry::Struct(definition) if definition.name == name => Some(definition),
+            _ => None,
+        })
+    }
+
+    /// Returns the type of each function, constructor and L1 handler along with its index among
+    /// the entry points of that type.
+    pub fn entry_point_indexes(&self) -> HashMap<String, (usize, EntryPointType)> {
+        let mut type_counts: HashMap<EntryPointType, usize> = HashMap::new();
+        let mut indexes = HashMap::new();
+        for entry in &self.entries {
+            let (function, entry_point_type) = match entry {
+                AbiEntry::Function(function) => (function, EntryPointType::External),
+                AbiEntry::Constructor(function) => (function, EntryPointType::Constructor),
+                AbiEntry::L1Handler(function) => (function, EntryPointType::L1Handler),
+                AbiEntry::Struct(_) | AbiEntry::Event(_) => continue,
+            };
+            let count = type_counts.entry(entry_point_type).or_default();
+            indexes.insert(function.name.clone(), (*count, entry_point_type));
+            *count += 1;
+        }
+        indexes
+    }
+}
+
+/// Converts the ABI kept in a contract class, whose entries have the same JSON format.
+impl TryFrom<&AbiType> for ContractAbi {
+    type Error = AbiError;
+
+    fn try_from(abi: &AbiType) -> Result<Self, AbiError> {
+        let entries = abi
+            .iter()
+            .flat_map(HashMap::values)
+            .map(|entry| serde_json::from_value(serde_json::to_value(entry)?))
+            .collect::<Result<_, _>>()?;
+        Ok(ContractAbi { entries })
+    }
+}
+
+pub fn read_abi(abi_name: &PathBuf) -> HashMap<String, (usize, EntryPointType)> {
+    ContractAbi::from_file(abi_name)
+        .unwrap()
+        .entry_point_indexes()
 }
 
 #[test]
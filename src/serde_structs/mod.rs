@@ -1 +1,2 @@
+pub mod abi_codec;
 pub mod contract_abi;
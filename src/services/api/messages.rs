@@ -6,9 +6,9 @@ use sha3::{Digest, Keccak256};
 /// A StarkNet Message from L2 to L1.
 #[derive(Debug, Clone)]
 pub struct StarknetMessageToL1 {
-    from_address: Address,
-    to_address: Address,
-    payload: Vec<Felt252>,
+    pub(crate) from_address: Address,
+    pub(crate) to_address: Address,
+    pub(crate) payload: Vec<Felt252>,
 }
 
 impl StarknetMessageToL1 {
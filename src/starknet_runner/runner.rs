@@ -9,10 +9,15 @@ use cairo_vm::felt::Felt252;
 use cairo_vm::{
     types::relocatable::{MaybeRelocatable, Relocatable},
     vm::{
+        errors::{
+            cairo_run_errors::CairoRunError, vm_errors::VirtualMachineError,
+            vm_exception::VmException,
+        },
         runners::{
             builtin_runner::BuiltinRunner,
             cairo_runner::{CairoArg, CairoRunner, ExecutionResources},
         },
+        security::verify_secure_runner,
         vm_core::VirtualMachine,
     },
 };
@@ -44,22 +49,70 @@ where
         }
     }
 
+    /// Runs the entry point until it returns. With `max_n_steps`, the run is stopped and fails
+    /// once it reaches that many steps.
     pub fn run_from_entrypoint(
         &mut self,
         entrypoint: usize,
         args: &[&CairoArg],
+        max_n_steps: Option<u64>,
     ) -> Result<(), TransactionError> {
         let verify_secure = true;
         let args: Vec<&CairoArg> = args.iter().map(ToOwned::to_owned).collect();
 
-        self.cairo_runner.run_from_entrypoint(
+        let max_n_steps = match max_n_steps {
+            Some(max_n_steps) => max_n_steps,
+            None => {
+                self.cairo_runner.run_from_entrypoint(
+                    entrypoint,
+                    &args,
+                    verify_secure,
+                    None,
+                    &mut self.vm,
+                    &mut self.hint_processor,
+                )?;
+                return Ok(());
+            }
+        };
+
+        // Same as `CairoRunner::run_from_entrypoint`, except that the VM runs at most
+        // `max_n_steps` steps instead of running until the end of the entry point.
+        let stack = args
+            .iter()
+            .map(|arg| self.vm.segments.gen_cairo_arg(arg))
+            .collect::<Result<Vec<MaybeRelocatable>, _>>()?;
+        let end = self.cairo_runner.initialize_function_entrypoint(
+            &mut self.vm,
             entrypoint,
-            &args,
-            verify_secure,
-            None,
+            stack,
+            MaybeRelocatable::from(0),
+        )?;
+        self.cairo_runner.initialize_vm(&mut self.vm)?;
+
+        match self.cairo_runner.run_for_steps(
+            max_n_steps as usize,
             &mut self.vm,
             &mut self.hint_processor,
-        )?;
+        ) {
+            // The entry point returned before running out of steps.
+            Err(VirtualMachineError::EndOfProgram(_)) => {}
+            Ok(()) if *self.vm.get_pc() == end => {}
+            Ok(()) => return Err(TransactionError::StepsLimitExceeded(max_n_steps)),
+            Err(error) => {
+                return Err(CairoRunError::VmException(VmException::from_vm_error(
+                    &self.cairo_runner,
+                    &self.vm,
+                    error,
+                ))
+                .into())
+            }
+        }
+
+        self.cairo_runner
+            .end_run(true, false, &mut self.vm, &mut self.hint_processor)?;
+        if verify_secure {
+            verify_secure_runner(&self.cairo_runner, false, None, &mut self.vm)?;
+        }
         Ok(())
     }
 
@@ -265,7 +318,7 @@ mod test {
             SyscallHintProcessor::new(BusinessLogicSyscallHandler::default_with(&mut state));
 
         let mut runner = StarknetRunner::new(cairo_runner, vm, hint_processor);
-        assert!(runner.run_from_entrypoint(1, &[]).is_err())
+        assert!(runner.run_from_entrypoint(1, &[], None).is_err())
     }
 
     #[test]
@@ -0,0 +1,331 @@
+use super::{
+    starknet_state::StarknetState,
+    starknet_state_error::StarknetStateError,
+    udc::{deploy_contract_call, ContractDeployed},
+};
+use crate::{
+    business_logic::{
+        execution::objects::TransactionExecutionInfo,
+        state::state_api::{State, StateReader},
+        transaction::{
+            objects::{
+                call::{encode_execute_calldata, Call, CallFormat},
+                internal_declare::InternalDeclare,
+                internal_deploy_account::InternalDeployAccount,
+                internal_invoke_function::InternalInvokeFunction,
+            },
+            transactions::Transaction,
+        },
+    },
+    core::contract_address::starknet_contract_address::compute_class_hash,
+    definitions::constants::{EXECUTE_ENTRY_POINT_SELECTOR, TRANSACTION_VERSION},
+    hash_utils::calculate_contract_address,
+    services::api::contract_class::ContractClass,
+    utils::{
+        calculate_sn_keccak, felt_to_field_element, field_element_to_felt, get_storage_var_address,
+        Address, ClassHash,
+    },
+};
+use cairo_vm::felt::Felt252;
+use getset::Getters;
+use num_traits::Zero;
+use starknet_crypto::{get_public_key, rfc6979_generate_k, sign, verify};
+use std::path::PathBuf;
+
+/// Compiled OpenZeppelin account, which validates transactions signed by a single Stark key.
+pub const ACCOUNT_CONTRACT_PATH: &str = "starknet_programs/Account.json";
+
+// ---------------------------------------------------------------------
+/// Stark key pair used to sign transactions.
+#[derive(Clone, Debug, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct KeyPair {
+    private_key: Felt252,
+    public_key: Felt252,
+}
+
+impl KeyPair {
+    pub fn new(private_key: Felt252) -> Result<Self, StarknetStateError> {
+        let public_key = get_public_key(&felt_to_field_element(&private_key)?);
+
+        Ok(KeyPair {
+            private_key,
+            public_key: field_element_to_felt(&public_key),
+        })
+    }
+
+    /// Derives a key pair from a seed, so that tests use the same keys on every run.
+    pub fn from_seed(seed: u64) -> Result<Self, StarknetStateError> {
+        let private_key = Felt252::from_bytes_be(&calculate_sn_keccak(&seed.to_be_bytes()));
+        Self::new(private_key)
+    }
+
+    /// Signs a message hash, returning the `[r, s]` signature expected by the account contract.
+    pub fn sign(&self, message_hash: &Felt252) -> Result<Vec<Felt252>, StarknetStateError> {
+        let private_key = felt_to_field_element(&self.private_key)?;
+        let message_hash = felt_to_field_element(message_hash)?;
+        let k = rfc6979_generate_k(&message_hash, &private_key, None);
+
+        let signature = sign(&private_key, &message_hash, &k)
+            .map_err(|err| StarknetStateError::Signature(format!("{err:?}")))?;
+
+        Ok(vec![
+            field_element_to_felt(&signature.r),
+            field_element_to_felt(&signature.s),
+        ])
+    }
+}
+
+/// Checks an `[r, s]` signature of a message hash against a public key.
+pub fn verify_signature(
+    public_key: &Felt252,
+    message_hash: &Felt252,
+    signature: &[Felt252],
+) -> Result<bool, StarknetStateError> {
+    let (r, s) = match signature {
+        [r, s] => (r, s),
+        _ => return Ok(false),
+    };
+
+    verify(
+        &felt_to_field_element(public_key)?,
+        &felt_to_field_element(message_hash)?,
+        &felt_to_field_element(r)?,
+        &felt_to_field_element(s)?,
+    )
+    .map_err(|err| StarknetStateError::Signature(format!("{err:?}")))
+}
+
+// ---------------------------------------------------------------------
+/// Account deployed in a `StarknetState`, which signs the transactions it sends with its key
+/// pair and takes its nonce from the state.
+#[derive(Clone, Debug, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct Account {
+    address: Address,
+    class_hash: ClassHash,
+    key_pair: KeyPair,
+}
+
+impl Account {
+    /// Loads the class of the account contract, see `ACCOUNT_CONTRACT_PATH`.
+    pub fn contract_class() -> Result<ContractClass, StarknetStateError> {
+        Ok(ContractClass::try_from(PathBuf::from(
+            ACCOUNT_CONTRACT_PATH,
+        ))?)
+    }
+
+    /// Returns the address of an account of the given class deployed from a deploy account
+    /// transaction, whose salt and only constructor argument are the public key.
+    pub fn calculate_address(
+        class_hash: &ClassHash,
+        public_key: &Felt252,
+    ) -> Result<Address, StarknetStateError> {
+        let address = calculate_contract_address(
+            &Address(public_key.clone()),
+            &Felt252::from_bytes_be(class_hash),
+            &[public_key.clone()],
+            Address(Felt252::zero()),
+        )?;
+
+        Ok(Address(address))
+    }
+
+    /// Writes an account owned by `key_pair` directly into the state, without executing any
+    /// transaction. Its address is the one of a deploy account transaction.
+    pub fn predeploy(
+        starknet_state: &mut StarknetState,
+        key_pair: KeyPair,
+    ) -> Result<Self, StarknetStateError> {
+        let contract_class = Self::contract_class()?;
+        let class_hash = compute_class_hash(&contract_class)?.to_be_bytes();
+        let address = Self::calculate_address(&class_hash, key_pair.public_key())?;
+
+        let state = &mut starknet_state.state;
+        state.set_contract_class(&class_hash, &contract_class)?;
+        state.deploy_contract(address.clone(), class_hash)?;
+
+        let public_key_var = get_storage_var_address("public_key", &[])?.to_be_bytes();
+        state.set_storage_at(
+            &(address.clone(), public_key_var),
+            key_pair.public_key().clone(),
+        );
+
+        Ok(Account {
+            address,
+            class_hash,
+            key_pair,
+        })
+    }
+
+    /// Deploys an account owned by `key_pair` with a signed deploy account transaction.
+    /// The class must already be declared, and the address given by `calculate_address` must be
+    /// funded if `max_fee` isn't zero.
+    pub fn deploy(
+        starknet_state: &mut StarknetState,
+        class_hash: ClassHash,
+        key_pair: KeyPair,
+        max_fee: u128,
+    ) -> Result<(Self, TransactionExecutionInfo), StarknetStateError> {
+        let public_key = key_pair.public_key().clone();
+        let tx = InternalDeployAccount::new(
+            class_hash,
+            max_fee,
+            TRANSACTION_VERSION,
+            Felt252::zero(),
+            vec![public_key.clone()],
+            Vec::new(),
+            Address(public_key),
+            *starknet_state
+                .general_config
+                .starknet_os_config()
+                .chain_id(),
+        )?;
+        let signature = key_pair.sign(tx.hash_value())?;
+        let tx = tx.with_signature(signature);
+
+        let address = tx.contract_address().clone();
+        let tx_execution_info = starknet_state.execute_tx(&mut Transaction::DeployAccount(tx))?;
+
+        let account = Account {
+            address,
+            class_hash,
+            key_pair,
+        };
+        Ok((account, tx_execution_info))
+    }
+
+    /// Signs and executes an invoke transaction of the account's `__execute__` entry point with
+    /// the given calldata, using the current nonce of the account.
+    pub fn invoke(
+        &self,
+        starknet_state: &mut StarknetState,
+        calldata: Vec<Felt252>,
+        max_fee: u128,
+    ) -> Result<TransactionExecutionInfo, StarknetStateError> {
+        let nonce = starknet_state.state.get_nonce_at(&self.address)?;
+        let mut tx = self.invoke_transaction(starknet_state, calldata, max_fee, nonce)?;
+        starknet_state.execute_tx(&mut tx)
+    }
+
+    /// Builds a signed invoke transaction of the account's `__execute__` entry point with the
+    /// given calldata and nonce, without executing it.
+    pub fn invoke_transaction(
+        &self,
+        starknet_state: &StarknetState,
+        calldata: Vec<Felt252>,
+        max_fee: u128,
+        nonce: Felt252,
+    ) -> Result<Transaction, StarknetStateError> {
+        let tx = InternalInvokeFunction::new(
+            self.address.clone(),
+            EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+            max_fee,
+            calldata,
+            Vec::new(),
+            chain_id(starknet_state),
+            Some(nonce),
+        )?;
+        let signature = self.key_pair.sign(tx.hash_value())?;
+
+        Ok(Transaction::InvokeFunction(tx.with_signature(signature)))
+    }
+
+    /// Signs and executes an invoke transaction making the given calls from the account.
+    pub fn execute(
+        &self,
+        starknet_state: &mut StarknetState,
+        calls: &[Call],
+        max_fee: u128,
+    ) -> Result<TransactionExecutionInfo, StarknetStateError> {
+        self.invoke(
+            starknet_state,
+            encode_execute_calldata(calls, CallFormat::Legacy),
+            max_fee,
+        )
+    }
+
+    /// Deploys a contract of a declared class through the UDC, which must be predeployed.
+    /// Returns the address of the contract, taken from the `ContractDeployed` event.
+    pub fn deploy_contract(
+        &self,
+        starknet_state: &mut StarknetState,
+        class_hash: &ClassHash,
+        salt: Felt252,
+        unique: bool,
+        constructor_calldata: Vec<Felt252>,
+        max_fee: u128,
+    ) -> Result<(Address, TransactionExecutionInfo), StarknetStateError> {
+        let call = deploy_contract_call(class_hash, salt, unique, constructor_calldata);
+        let tx_execution_info = self.execute(starknet_state, &[call], max_fee)?;
+
+        let address = ContractDeployed::from_execution_info(&tx_execution_info)?
+            .pop()
+            .ok_or(StarknetStateError::MissingContractDeployedEvent)?
+            .address;
+        Ok((address, tx_execution_info))
+    }
+
+    /// Signs and executes a declare transaction of the given class sent by the account, using
+    /// the current nonce of the account. Returns the class hash and the execution info.
+    pub fn declare(
+        &self,
+        starknet_state: &mut StarknetState,
+        contract_class: ContractClass,
+        max_fee: u128,
+    ) -> Result<(ClassHash, TransactionExecutionInfo), StarknetStateError> {
+        let nonce = starknet_state.state.get_nonce_at(&self.address)?;
+        let mut tx = InternalDeclare::new(
+            contract_class,
+            chain_id(starknet_state),
+            self.address.clone(),
+            max_fee,
+            TRANSACTION_VERSION,
+            Vec::new(),
+            nonce,
+        )?;
+        tx.signature = self.key_pair.sign(&tx.hash_value)?;
+
+        let class_hash = tx.class_hash;
+        let tx_execution_info = starknet_state.execute_tx(&mut Transaction::Declare(tx))?;
+        Ok((class_hash, tx_execution_info))
+    }
+}
+
+fn chain_id(starknet_state: &StarknetState) -> Felt252 {
+    starknet_state
+        .general_config
+        .starknet_os_config()
+        .chain_id()
+        .to_felt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+
+    #[test]
+    fn key_pair_from_seed_is_deterministic() {
+        let key_pair = KeyPair::from_seed(1).unwrap();
+
+        assert_eq!(key_pair, KeyPair::from_seed(1).unwrap());
+        assert_ne!(key_pair, KeyPair::from_seed(2).unwrap());
+    }
+
+    #[test]
+    fn signature_is_verified_with_the_public_key() {
+        let key_pair = KeyPair::from_seed(1).unwrap();
+        let message_hash = Felt252::from(1234);
+
+        let signature = key_pair.sign(&message_hash).unwrap();
+
+        assert!(verify_signature(key_pair.public_key(), &message_hash, &signature).unwrap());
+        assert!(
+            !verify_signature(key_pair.public_key(), &Felt252::from(1235), &signature).unwrap()
+        );
+        let other_key_pair = KeyPair::from_seed(2).unwrap();
+        assert!(!verify_signature(other_key_pair.public_key(), &message_hash, &signature).unwrap());
+        assert!(!verify_signature(key_pair.public_key(), &message_hash, &signature[..1]).unwrap());
+    }
+}
@@ -0,0 +1,74 @@
+use super::{
+    mempool::pool::Mempool, starknet_state::StarknetState, starknet_state_error::StarknetStateError,
+};
+use crate::business_logic::{
+    execution::objects::TransactionExecutionInfo, state::state_api_objects::BlockInfo,
+};
+use cairo_vm::felt::Felt252;
+
+/// Block built by a `BlockProducer`.
+#[derive(Debug)]
+pub struct ProducedBlock {
+    pub block_info: BlockInfo,
+    /// Hashes and execution infos of the transactions included in the block, in execution order.
+    pub transactions: Vec<(Felt252, TransactionExecutionInfo)>,
+    /// Hashes of the transactions taken from the mempool that failed to execute, with their
+    /// errors.
+    pub rejected: Vec<(Felt252, StarknetStateError)>,
+}
+
+// ---------------------------------------------------------------------
+/// Builds blocks on top of a `StarknetState` by executing the ready transactions of a mempool.
+/// Only library users drive it: the devnet closes its own blocks, see `DevnetState`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockProducer {
+    max_txs_per_block: usize,
+}
+
+impl BlockProducer {
+    pub fn new(max_txs_per_block: usize) -> Self {
+        BlockProducer { max_txs_per_block }
+    }
+
+    /// Evicts the stale transactions of the mempool, then executes its ready transactions with
+    /// the current block info until the block is full or no transaction is ready. The block
+    /// number is increased afterwards.
+    pub fn produce_block(
+        &self,
+        starknet_state: &mut StarknetState,
+        mempool: &mut Mempool,
+    ) -> Result<ProducedBlock, StarknetStateError> {
+        mempool.evict_stale(&mut starknet_state.state)?;
+
+        let block_info = starknet_state.general_config.block_info().clone();
+        let mut transactions = Vec::new();
+        let mut rejected = Vec::new();
+
+        while transactions.len() + rejected.len() < self.max_txs_per_block {
+            let mut tx = match mempool.pop_ready(&mut starknet_state.state)? {
+                Some(tx) => tx,
+                None => break,
+            };
+
+            let tx_hash = tx.hash_value();
+            match starknet_state.execute_tx(&mut tx) {
+                Ok(tx_execution_info) => transactions.push((tx_hash, tx_execution_info)),
+                Err(error) => rejected.push((tx_hash, error)),
+            }
+        }
+
+        starknet_state.general_config.block_info_mut().block_number += 1;
+
+        Ok(ProducedBlock {
+            block_info,
+            transactions,
+            rejected,
+        })
+    }
+}
+
+impl Default for BlockProducer {
+    fn default() -> Self {
+        BlockProducer::new(1000)
+    }
+}
@@ -0,0 +1,344 @@
+use crate::{
+    business_logic::{
+        execution::objects::{Event, TransactionExecutionContext},
+        state::state_api_objects::BlockInfo,
+    },
+    core::syscalls::syscall_hooks::SyscallHooks,
+    utils::Address,
+};
+use cairo_vm::felt::Felt252;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// Cheatcodes shared between every syscall handler of an execution, so that overrides also
+/// apply to nested calls. Installed in the general config as its syscall hooks.
+pub type SharedCheatcodes = Arc<Mutex<Cheatcodes>>;
+
+/// Overrides the caller address seen by a contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Prank {
+    pub caller_address: Address,
+    /// Number of calls to the target contract left to prank. `None` pranks until stopped.
+    pub remaining_calls: Option<usize>,
+}
+
+/// Overrides for the fields returned by the `get_tx_info` syscall.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxInfoSpoof {
+    pub version: Option<u64>,
+    pub account_contract_address: Option<Address>,
+    pub max_fee: Option<u128>,
+    pub signature: Option<Vec<Felt252>>,
+    pub transaction_hash: Option<Felt252>,
+    pub nonce: Option<Felt252>,
+}
+
+/// Cheatcode layer applied on top of the `BusinessLogicSyscallHandler`, meant for contract
+/// unit testing.
+#[derive(Clone, Debug, Default)]
+pub struct Cheatcodes {
+    pranks: HashMap<Address, Prank>,
+    block_number: Option<u64>,
+    block_timestamp: Option<u64>,
+    sequencer_address: Option<Address>,
+    mocked_calls: HashMap<(Address, Felt252), Vec<Felt252>>,
+    tx_info_spoof: Option<TxInfoSpoof>,
+    expected_events: Vec<Event>,
+}
+
+impl Cheatcodes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides the caller address of the next `n_calls` calls to `target`.
+    /// If `n_calls` is `None`, the override stays until `stop_prank` is called.
+    pub fn prank(&mut self, target: Address, caller_address: Address, n_calls: Option<usize>) {
+        self.pranks.insert(
+            target,
+            Prank {
+                caller_address,
+                remaining_calls: n_calls,
+            },
+        );
+    }
+
+    pub fn stop_prank(&mut self, target: &Address) {
+        self.pranks.remove(target);
+    }
+
+    /// Overrides the block number returned to contracts.
+    pub fn roll(&mut self, block_number: u64) {
+        self.block_number = Some(block_number);
+    }
+
+    pub fn stop_roll(&mut self) {
+        self.block_number = None;
+    }
+
+    /// Overrides the block timestamp returned to contracts.
+    pub fn warp(&mut self, block_timestamp: u64) {
+        self.block_timestamp = Some(block_timestamp);
+    }
+
+    pub fn stop_warp(&mut self) {
+        self.block_timestamp = None;
+    }
+
+    /// Overrides the sequencer address returned to contracts.
+    pub fn set_sequencer_address(&mut self, sequencer_address: Address) {
+        self.sequencer_address = Some(sequencer_address);
+    }
+
+    /// Makes every `call_contract` to `contract_address` with `selector` return `retdata`
+    /// without executing the callee.
+    pub fn mock_call(
+        &mut self,
+        contract_address: Address,
+        selector: Felt252,
+        retdata: Vec<Felt252>,
+    ) {
+        self.mocked_calls
+            .insert((contract_address, selector), retdata);
+    }
+
+    pub fn clear_mock_call(&mut self, contract_address: Address, selector: Felt252) {
+        self.mocked_calls.remove(&(contract_address, selector));
+    }
+
+    /// Overrides the transaction info returned by the `get_tx_info` syscall.
+    pub fn spoof_tx_info(&mut self, tx_info_spoof: TxInfoSpoof) {
+        self.tx_info_spoof = Some(tx_info_spoof);
+    }
+
+    pub fn stop_spoof_tx_info(&mut self) {
+        self.tx_info_spoof = None;
+    }
+
+    /// Registers an event that must be emitted by `from_address` before
+    /// `take_unmatched_expected_events` is called.
+    pub fn expect_event(&mut self, from_address: Address, keys: Vec<Felt252>, data: Vec<Felt252>) {
+        self.expected_events.push(Event {
+            from_address,
+            keys,
+            data,
+        });
+    }
+
+    /// Returns the expected events that were not emitted, and clears the expectations.
+    pub fn take_unmatched_expected_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.expected_events)
+    }
+
+    /// Removes every cheatcode.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    // ------------------------------------
+    //  Hooks used by the syscall handler
+    // ------------------------------------
+
+    /// Returns the pranked caller for a new call to `contract_address`, consuming one of the
+    /// pranked calls.
+    pub(crate) fn take_prank(&mut self, contract_address: &Address) -> Option<Address> {
+        let prank = self.pranks.get(contract_address)?.clone();
+
+        match prank.remaining_calls {
+            Some(0) => {
+                self.pranks.remove(contract_address);
+                None
+            }
+            Some(1) => {
+                self.pranks.remove(contract_address);
+                Some(prank.caller_address)
+            }
+            Some(remaining_calls) => {
+                self.pranks.insert(
+                    contract_address.clone(),
+                    Prank {
+                        caller_address: prank.caller_address.clone(),
+                        remaining_calls: Some(remaining_calls - 1),
+                    },
+                );
+                Some(prank.caller_address)
+            }
+            None => Some(prank.caller_address),
+        }
+    }
+
+    pub(crate) fn apply_block_info(&self, block_info: &mut BlockInfo) {
+        if let Some(block_number) = self.block_number {
+            block_info.block_number = block_number;
+        }
+        if let Some(block_timestamp) = self.block_timestamp {
+            block_info.block_timestamp = block_timestamp;
+        }
+        if let Some(sequencer_address) = &self.sequencer_address {
+            block_info.sequencer_address = sequencer_address.clone();
+        }
+    }
+
+    pub(crate) fn apply_tx_info(&self, tx_execution_context: &mut TransactionExecutionContext) {
+        let spoof = match &self.tx_info_spoof {
+            Some(spoof) => spoof,
+            None => return,
+        };
+
+        if let Some(version) = spoof.version {
+            tx_execution_context.version = version;
+        }
+        if let Some(account_contract_address) = &spoof.account_contract_address {
+            tx_execution_context.account_contract_address = account_contract_address.clone();
+        }
+        if let Some(max_fee) = spoof.max_fee {
+            tx_execution_context.max_fee = max_fee;
+        }
+        if let Some(signature) = &spoof.signature {
+            tx_execution_context.signature = signature.clone();
+        }
+        if let Some(transaction_hash) = &spoof.transaction_hash {
+            tx_execution_context.transaction_hash = transaction_hash.clone();
+        }
+        if let Some(nonce) = &spoof.nonce {
+            tx_execution_context.nonce = nonce.clone();
+        }
+    }
+
+    pub(crate) fn get_mocked_call(
+        &self,
+        contract_address: &Address,
+        selector: &Felt252,
+    ) -> Option<Vec<Felt252>> {
+        self.mocked_calls
+            .get(&(contract_address.clone(), selector.clone()))
+            .cloned()
+    }
+
+    /// Marks the first matching expected event as emitted.
+    pub(crate) fn record_event(
+        &mut self,
+        from_address: &Address,
+        keys: &[Felt252],
+        data: &[Felt252],
+    ) {
+        if let Some(position) = self.expected_events.iter().position(|event| {
+            event.from_address == *from_address && event.keys == keys && event.data == data
+        }) {
+            self.expected_events.remove(position);
+        }
+    }
+}
+
+fn lock(cheatcodes: &Mutex<Cheatcodes>) -> MutexGuard<Cheatcodes> {
+    cheatcodes.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl SyscallHooks for Mutex<Cheatcodes> {
+    fn take_caller_address(&self, contract_address: &Address) -> Option<Address> {
+        lock(self).take_prank(contract_address)
+    }
+
+    fn apply_block_info(&self, block_info: &mut BlockInfo) {
+        lock(self).apply_block_info(block_info)
+    }
+
+    fn apply_tx_info(&self, tx_execution_context: &mut TransactionExecutionContext) {
+        lock(self).apply_tx_info(tx_execution_context)
+    }
+
+    fn mocked_call(&self, contract_address: &Address, selector: &Felt252) -> Option<Vec<Felt252>> {
+        lock(self).get_mocked_call(contract_address, selector)
+    }
+
+    fn on_event(&self, from_address: &Address, keys: &[Felt252], data: &[Felt252]) {
+        lock(self).record_event(from_address, keys, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+
+    #[test]
+    fn prank_is_consumed_after_n_calls() {
+        let mut cheatcodes = Cheatcodes::new();
+        let target = Address(1.into());
+        cheatcodes.prank(target.clone(), Address(2.into()), Some(2));
+
+        assert_eq!(cheatcodes.take_prank(&target), Some(Address(2.into())));
+        assert_eq!(cheatcodes.take_prank(&target), Some(Address(2.into())));
+        assert_eq!(cheatcodes.take_prank(&target), None);
+    }
+
+    #[test]
+    fn prank_without_limit_lasts_until_stopped() {
+        let mut cheatcodes = Cheatcodes::new();
+        let target = Address(1.into());
+        cheatcodes.prank(target.clone(), Address(2.into()), None);
+
+        for _ in 0..5 {
+            assert_eq!(cheatcodes.take_prank(&target), Some(Address(2.into())));
+        }
+        cheatcodes.stop_prank(&target);
+        assert_eq!(cheatcodes.take_prank(&target), None);
+    }
+
+    #[test]
+    fn roll_and_warp_override_block_info() {
+        let mut cheatcodes = Cheatcodes::new();
+        cheatcodes.roll(10);
+        cheatcodes.warp(20);
+
+        let mut block_info = BlockInfo::default();
+        cheatcodes.apply_block_info(&mut block_info);
+
+        assert_eq!(block_info.block_number, 10);
+        assert_eq!(block_info.block_timestamp, 20);
+        assert_eq!(block_info.sequencer_address, Address(0.into()));
+    }
+
+    #[test]
+    fn spoof_tx_info_only_overrides_given_fields() {
+        let mut cheatcodes = Cheatcodes::new();
+        cheatcodes.spoof_tx_info(TxInfoSpoof {
+            max_fee: Some(7),
+            signature: Some(vec![1.into(), 2.into()]),
+            ..Default::default()
+        });
+
+        let mut tx_execution_context = TransactionExecutionContext {
+            version: 1,
+            ..Default::default()
+        };
+        cheatcodes.apply_tx_info(&mut tx_execution_context);
+
+        assert_eq!(tx_execution_context.max_fee, 7);
+        assert_eq!(tx_execution_context.signature, vec![1.into(), 2.into()]);
+        assert_eq!(tx_execution_context.version, 1);
+    }
+
+    #[test]
+    fn expected_events_are_matched_once() {
+        let mut cheatcodes = Cheatcodes::new();
+        let address = Address(1.into());
+        cheatcodes.expect_event(address.clone(), vec![1.into()], vec![2.into()]);
+        cheatcodes.expect_event(address.clone(), vec![1.into()], vec![2.into()]);
+
+        cheatcodes.record_event(&address, &[1.into()], &[2.into()]);
+        cheatcodes.record_event(&Address(3.into()), &[1.into()], &[2.into()]);
+
+        assert_eq!(
+            cheatcodes.take_unmatched_expected_events(),
+            vec![Event {
+                from_address: address,
+                keys: vec![1.into()],
+                data: vec![2.into()],
+            }]
+        );
+        assert!(cheatcodes.take_unmatched_expected_events().is_empty());
+    }
+}
@@ -0,0 +1,255 @@
+use super::starknet_state_error::StarknetStateError;
+use crate::{business_logic::execution::objects::Event, utils::Address};
+use cairo_vm::felt::Felt252;
+use std::collections::HashMap;
+
+/// An event along with the block and transaction that emitted it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredEvent {
+    pub event: Event,
+    pub block_number: u64,
+    /// Hash of the emitting transaction. Events of calls executed outside of a transaction
+    /// don't have one.
+    pub transaction_hash: Option<Felt252>,
+}
+
+/// Filter and page of an event query.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventQuery {
+    /// First block of the range, inclusive.
+    pub from_block: Option<u64>,
+    /// Last block of the range, inclusive.
+    pub to_block: Option<u64>,
+    pub address: Option<Address>,
+    /// Keys to match at each position. An event matches if, at every position, its key is one
+    /// of the given keys or no keys are given.
+    pub keys: Vec<Vec<Felt252>>,
+    /// Maximum number of events returned.
+    pub chunk_size: usize,
+    /// Token returned by the previous page, to continue from it.
+    pub continuation_token: Option<String>,
+}
+
+/// Events matching a query. The continuation token is set if more events match after them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventsPage {
+    pub events: Vec<StoredEvent>,
+    pub continuation_token: Option<String>,
+}
+
+/// Events in emission order, indexed by block number and emitting contract. Events are expected
+/// to be added in block order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventStore {
+    events: Vec<StoredEvent>,
+    /// Positions of the events of each emitting contract, in emission order.
+    positions_by_address: HashMap<Address, Vec<usize>>,
+}
+
+impl EventQuery {
+    pub fn matches(&self, event: &StoredEvent) -> bool {
+        let block_number = event.block_number;
+        if self.from_block.map_or(false, |from| block_number < from)
+            || self.to_block.map_or(false, |to| block_number > to)
+            || matches!(&self.address, Some(address) if *address != event.event.from_address)
+        {
+            return false;
+        }
+
+        self.keys.iter().enumerate().all(|(i, keys)| {
+            keys.is_empty()
+                || event
+                    .event
+                    .keys
+                    .get(i)
+                    .map_or(false, |key| keys.contains(key))
+        })
+    }
+}
+
+impl EventStore {
+    /// Adds the events of a transaction or call, in emission order.
+    pub fn add_events(
+        &mut self,
+        events: &[Event],
+        block_number: u64,
+        transaction_hash: Option<Felt252>,
+    ) {
+        for event in events {
+            self.push(StoredEvent {
+                event: event.clone(),
+                block_number,
+                transaction_hash: transaction_hash.clone(),
+            });
+        }
+    }
+
+    pub fn push(&mut self, event: StoredEvent) {
+        self.positions_by_address
+            .entry(event.event.from_address.clone())
+            .or_default()
+            .push(self.events.len());
+        self.events.push(event);
+    }
+
+    /// Returns a page of the events matching the query, in emission order. The continuation
+    /// token is the position in the store of the next matching event. Empty chunks are
+    /// rejected, as their token would point at the event they didn't return.
+    pub fn query(&self, query: &EventQuery) -> Result<EventsPage, StarknetStateError> {
+        if query.chunk_size == 0 {
+            return Err(StarknetStateError::EmptyChunkSize);
+        }
+        let token_position = match &query.continuation_token {
+            Some(token) => token
+                .parse::<usize>()
+                .map_err(|_| StarknetStateError::InvalidContinuationToken(token.clone()))?,
+            None => 0,
+        };
+        let from_position = query.from_block.map_or(0, |from_block| {
+            self.events
+                .partition_point(|event| event.block_number < from_block)
+        });
+        let start = token_position.max(from_position);
+
+        let positions: Box<dyn Iterator<Item = usize>> = match &query.address {
+            Some(address) => {
+                let positions = self
+                    .positions_by_address
+                    .get(address)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                let first = positions.partition_point(|position| *position < start);
+                Box::new(positions[first..].iter().copied())
+            }
+            None => Box::new(start..self.events.len()),
+        };
+        let mut matching = positions
+            .take_while(|position| {
+                query.to_block.map_or(true, |to_block| {
+                    self.events[*position].block_number <= to_block
+                })
+            })
+            .filter(|position| query.matches(&self.events[*position]));
+
+        let events = matching
+            .by_ref()
+            .take(query.chunk_size)
+            .map(|position| self.events[position].clone())
+            .collect();
+        Ok(EventsPage {
+            events,
+            continuation_token: matching.next().map(|position| position.to_string()),
+        })
+    }
+
+    /// Returns the events, in emission order.
+    pub fn iter(&self) -> impl Iterator<Item = &StoredEvent> {
+        self.events.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+
+    fn event(from_address: u64, keys: &[u64]) -> Event {
+        Event {
+            from_address: Address(from_address.into()),
+            keys: keys.iter().map(|key| Felt252::from(*key)).collect(),
+            data: vec![],
+        }
+    }
+
+    fn store() -> EventStore {
+        let mut store = EventStore::default();
+        store.add_events(&[event(1, &[10, 20]), event(2, &[10])], 0, Some(5.into()));
+        store.add_events(&[event(1, &[30])], 1, None);
+        store.add_events(&[event(1, &[10, 30]), event(2, &[20])], 2, Some(6.into()));
+        store
+    }
+
+    fn addresses(page: &EventsPage) -> Vec<(u64, Felt252)> {
+        page.events
+            .iter()
+            .map(|event| (event.block_number, event.event.from_address.0.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn keys_match_per_position() {
+        let stored = StoredEvent {
+            event: event(1, &[10, 20]),
+            block_number: 0,
+            transaction_hash: None,
+        };
+        let query = |keys: Vec<Vec<u64>>| EventQuery {
+            keys: keys
+                .into_iter()
+                .map(|keys| keys.into_iter().map(Felt252::from).collect())
+                .collect(),
+            ..Default::default()
+        };
+
+        assert!(query(vec![]).matches(&stored));
+        assert!(query(vec![vec![], vec![30, 20]]).matches(&stored));
+        assert!(!query(vec![vec![20]]).matches(&stored));
+        assert!(!query(vec![vec![], vec![], vec![30]]).matches(&stored));
+    }
+
+    #[test]
+    fn queries_filter_by_block_and_address() {
+        let store = store();
+        let query = EventQuery {
+            from_block: Some(1),
+            to_block: Some(2),
+            address: Some(Address(1.into())),
+            chunk_size: 10,
+            ..Default::default()
+        };
+
+        let page = store.query(&query).unwrap();
+
+        assert_eq!(addresses(&page), vec![(1, 1.into()), (2, 1.into())]);
+        assert_eq!(page.continuation_token, None);
+        assert_eq!(page.events[0].transaction_hash, None);
+        assert_eq!(page.events[1].transaction_hash, Some(6.into()));
+    }
+
+    #[test]
+    fn pages_continue_from_the_token() {
+        let store = store();
+        let mut query = EventQuery {
+            keys: vec![vec![10.into()]],
+            chunk_size: 2,
+            ..Default::default()
+        };
+
+        let page = store.query(&query).unwrap();
+        assert_eq!(addresses(&page), vec![(0, 1.into()), (0, 2.into())]);
+        assert_eq!(page.continuation_token, Some("3".to_string()));
+
+        query.continuation_token = page.continuation_token;
+        let page = store.query(&query).unwrap();
+        assert_eq!(addresses(&page), vec![(2, 1.into())]);
+        assert_eq!(page.continuation_token, None);
+
+        query.continuation_token = Some("not a position".to_string());
+        assert_matches!(
+            store.query(&query),
+            Err(StarknetStateError::InvalidContinuationToken(_))
+        );
+
+        query.continuation_token = None;
+        query.chunk_size = 0;
+        assert_matches!(store.query(&query), Err(StarknetStateError::EmptyChunkSize));
+    }
+}
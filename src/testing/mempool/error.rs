@@ -0,0 +1,23 @@
+use crate::{core::errors::state_errors::StateError, utils::Address};
+use cairo_vm::felt::Felt252;
+use thiserror::Error;
+
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum MempoolError {
+    #[error("Transaction {0} is already in the mempool")]
+    DuplicateTransaction(Felt252),
+    #[error("Transactions without a nonce can't be queued in the mempool")]
+    MissingNonce,
+    #[error("Invalid transaction nonce for {0:?}. The account nonce is {1}, got {2}")]
+    NonceTooLow(Address, Felt252, Felt252),
+    #[error(
+        "Replacement price ({1}) doesn't bump the price ({0}) of the queued transaction enough"
+    )]
+    ReplacementUnderpriced(u128, u128),
+    #[error("The mempool holds the maximum number of transactions of {0:?}")]
+    AccountLimitReached(Address),
+    #[error("The mempool is full")]
+    Full,
+    #[error(transparent)]
+    State(#[from] StateError),
+}
@@ -0,0 +1,526 @@
+use super::error::MempoolError;
+use crate::{
+    business_logic::{state::state_api::StateReader, transaction::transactions::Transaction},
+    core::errors::state_errors::StateError,
+    utils::Address,
+};
+use cairo_vm::felt::Felt252;
+use num_traits::Zero;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+/// Limits and replacement rules of a `Mempool`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MempoolConfig {
+    /// Maximum number of queued transactions.
+    pub max_txs: usize,
+    /// Maximum number of queued transactions sent by the same account.
+    pub max_txs_per_account: usize,
+    /// Percentage by which a transaction must raise the price of the queued transaction with the
+    /// same sender and nonce to replace it, see `replacement_price`.
+    pub fee_bump_percentage: u128,
+    /// Time after which a queued transaction is evicted.
+    pub time_to_live: Duration,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        MempoolConfig {
+            max_txs: 10_000,
+            max_txs_per_account: 64,
+            fee_bump_percentage: 10,
+            time_to_live: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+struct PooledTransaction {
+    tx: Transaction,
+    /// Order in which the transaction entered the mempool.
+    arrival: u64,
+    added_at: Instant,
+}
+
+// ---------------------------------------------------------------------
+/// Pending transactions, queued by sender and ordered by nonce.
+/// A transaction is ready once its nonce is the current nonce of its sender, so transactions
+/// with future nonces are held until the ones filling the gap arrive.
+/// The mempool is meant for library users building blocks with a `BlockProducer`; the devnet
+/// doesn't use it and executes each transaction as it arrives.
+#[derive(Default)]
+pub struct Mempool {
+    config: MempoolConfig,
+    queues: HashMap<Address, BTreeMap<Felt252, PooledTransaction>>,
+    hashes: HashSet<Felt252>,
+    next_arrival: u64,
+    /// Nonces of the senders read during the current block, see `pop_ready`.
+    account_nonces: HashMap<Address, Felt252>,
+}
+
+impl Mempool {
+    pub fn new(config: MempoolConfig) -> Self {
+        Mempool {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    pub fn contains(&self, tx_hash: &Felt252) -> bool {
+        self.hashes.contains(tx_hash)
+    }
+
+    /// Returns the nonces of the queued transactions sent by `sender`, in increasing order.
+    pub fn queued_nonces(&self, sender: &Address) -> Vec<Felt252> {
+        self.queues
+            .get(sender)
+            .map(|queue| queue.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Queues a transaction. If a transaction with the same sender and nonce is already queued,
+    /// it is replaced when the price is bumped enough, and its hash is returned.
+    pub fn add_transaction<S: StateReader>(
+        &mut self,
+        tx: Transaction,
+        state: &mut S,
+    ) -> Result<Option<Felt252>, MempoolError> {
+        let tx_hash = tx.hash_value();
+        if self.hashes.contains(&tx_hash) {
+            return Err(MempoolError::DuplicateTransaction(tx_hash));
+        }
+
+        let nonce = tx.nonce().ok_or(MempoolError::MissingNonce)?;
+        let sender = tx.contract_address();
+        let account_nonce = account_nonce(state, &sender)?;
+        if nonce < account_nonce {
+            return Err(MempoolError::NonceTooLow(sender, account_nonce, nonce));
+        }
+
+        let queued = self.queues.get(&sender);
+        let replaced_tx = queued
+            .and_then(|queue| queue.get(&nonce))
+            .map(|pooled| &pooled.tx);
+        match replaced_tx {
+            Some(replaced_tx) => {
+                let (replaced_price, price) = replacement_price(replaced_tx, &tx);
+                let bump = replaced_price.saturating_mul(self.config.fee_bump_percentage) / 100;
+                let min_price = replaced_price.saturating_add(bump.max(1));
+                if price < min_price {
+                    return Err(MempoolError::ReplacementUnderpriced(replaced_price, price));
+                }
+            }
+            None => {
+                if queued.map_or(0, BTreeMap::len) >= self.config.max_txs_per_account {
+                    return Err(MempoolError::AccountLimitReached(sender));
+                }
+                if self.hashes.len() >= self.config.max_txs {
+                    return Err(MempoolError::Full);
+                }
+            }
+        }
+
+        let pooled = PooledTransaction {
+            tx,
+            arrival: self.next_arrival,
+            added_at: Instant::now(),
+        };
+        self.next_arrival += 1;
+        self.hashes.insert(tx_hash);
+
+        let replaced = self.queues.entry(sender).or_default().insert(nonce, pooled);
+        Ok(replaced.map(|pooled| {
+            let replaced_hash = pooled.tx.hash_value();
+            self.hashes.remove(&replaced_hash);
+            replaced_hash
+        }))
+    }
+
+    /// Removes and returns the ready transaction that entered the mempool first, if any.
+    /// Queued transactions whose nonce was already used by their sender are dropped.
+    /// The nonce of a sender is read from `state` once per block, and read again only after one
+    /// of its transactions is popped, so `evict_stale` must be called before building a block on
+    /// a state that changed otherwise.
+    pub fn pop_ready<S: StateReader>(
+        &mut self,
+        state: &mut S,
+    ) -> Result<Option<Transaction>, MempoolError> {
+        let mut next: Option<(u64, Address)> = None;
+
+        for (sender, queue) in self.queues.iter_mut() {
+            let account_nonce = match self.account_nonces.get(sender) {
+                Some(account_nonce) => account_nonce.clone(),
+                None => {
+                    let account_nonce = account_nonce(state, sender)?;
+                    self.account_nonces
+                        .insert(sender.clone(), account_nonce.clone());
+                    account_nonce
+                }
+            };
+            while queue
+                .first_key_value()
+                .map_or(false, |(nonce, _)| *nonce < account_nonce)
+            {
+                if let Some((_, stale)) = queue.pop_first() {
+                    self.hashes.remove(&stale.tx.hash_value());
+                }
+            }
+
+            if let Some((nonce, pooled)) = queue.first_key_value() {
+                let arrived_first = next
+                    .as_ref()
+                    .map_or(true, |(arrival, _)| pooled.arrival < *arrival);
+                if *nonce == account_nonce && arrived_first {
+                    next = Some((pooled.arrival, sender.clone()));
+                }
+            }
+        }
+
+        let sender = match next {
+            Some((_, sender)) => sender,
+            None => return Ok(None),
+        };
+        // Executing the transaction may or may not use its nonce.
+        self.account_nonces.remove(&sender);
+        let tx = self
+            .queues
+            .get_mut(&sender)
+            .and_then(|queue| queue.pop_first())
+            .map(|(_, pooled)| pooled.tx);
+        self.queues.retain(|_, queue| !queue.is_empty());
+
+        if let Some(tx) = &tx {
+            self.hashes.remove(&tx.hash_value());
+        }
+        Ok(tx)
+    }
+
+    /// Removes the transactions whose nonce was already used by their sender, and the ones
+    /// queued for longer than the time to live. Returns the hashes of the evicted transactions.
+    /// Starts a new block: the nonces of the senders are read again from `state`.
+    pub fn evict_stale<S: StateReader>(
+        &mut self,
+        state: &mut S,
+    ) -> Result<Vec<Felt252>, MempoolError> {
+        let time_to_live = self.config.time_to_live;
+        let mut evicted = Vec::new();
+
+        self.account_nonces.clear();
+        for (sender, queue) in self.queues.iter_mut() {
+            let account_nonce = account_nonce(state, sender)?;
+            self.account_nonces
+                .insert(sender.clone(), account_nonce.clone());
+            queue.retain(|nonce, pooled| {
+                let is_stale = *nonce < account_nonce || pooled.added_at.elapsed() >= time_to_live;
+                if is_stale {
+                    evicted.push(pooled.tx.hash_value());
+                }
+                !is_stale
+            });
+        }
+        self.queues.retain(|_, queue| !queue.is_empty());
+
+        for tx_hash in &evicted {
+            self.hashes.remove(tx_hash);
+        }
+        Ok(evicted)
+    }
+}
+
+/// Returns the nonce of an account, which is zero until the account is deployed.
+fn account_nonce<S: StateReader>(state: &mut S, address: &Address) -> Result<Felt252, StateError> {
+    match state.get_nonce_at(address) {
+        Err(StateError::NoneContractState(_)) => Ok(Felt252::zero()),
+        result => result,
+    }
+}
+
+/// Returns the prices of a queued transaction and of the transaction replacing it: their max L1
+/// gas price if both are version 3 transactions, which are priced by their resource bounds, and
+/// their max fee otherwise.
+fn replacement_price(replaced_tx: &Transaction, tx: &Transaction) -> (u128, u128) {
+    match (replaced_tx.v3_fields(), tx.v3_fields()) {
+        (Some(replaced_fields), Some(fields)) => (
+            replaced_fields.resource_bounds.l1_gas.max_price_per_unit,
+            fields.resource_bounds.l1_gas.max_price_per_unit,
+        ),
+        _ => (replaced_tx.max_fee(), tx.max_fee()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::business_logic::{
+        fact_state::in_memory_state_reader::InMemoryStateReader,
+        state::{cached_state::CachedState, state_api::State},
+        transaction::objects::{
+            internal_invoke_function::InternalInvokeFunction,
+            resource_bounds::{ResourceBounds, ResourceBoundsMapping, V3TransactionFields},
+        },
+    };
+    use coverage_helper::test;
+
+    fn invoke(sender: u64, nonce: u64, max_fee: u128) -> Transaction {
+        Transaction::InvokeFunction(
+            InternalInvokeFunction::new(
+                Address(sender.into()),
+                0.into(),
+                max_fee,
+                Vec::new(),
+                Vec::new(),
+                0.into(),
+                Some(nonce.into()),
+            )
+            .unwrap(),
+        )
+    }
+
+    fn invoke_v3(
+        sender: u64,
+        nonce: u64,
+        max_amount: u64,
+        max_price_per_unit: u128,
+    ) -> Transaction {
+        let v3_fields = V3TransactionFields {
+            resource_bounds: ResourceBoundsMapping {
+                l1_gas: ResourceBounds {
+                    max_amount,
+                    max_price_per_unit,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        Transaction::InvokeFunction(
+            InternalInvokeFunction::new_v3(
+                Address(sender.into()),
+                Vec::new(),
+                Vec::new(),
+                0.into(),
+                nonce.into(),
+                v3_fields,
+            )
+            .unwrap(),
+        )
+    }
+
+    fn state_with_nonces(nonces: &[(u64, u64)]) -> CachedState<InMemoryStateReader> {
+        let mut state_reader = InMemoryStateReader::default();
+        for (address, nonce) in nonces {
+            state_reader
+                .address_to_nonce
+                .insert(Address((*address).into()), (*nonce).into());
+        }
+        CachedState::new(state_reader, None)
+    }
+
+    fn pop_all(
+        mempool: &mut Mempool,
+        state: &mut CachedState<InMemoryStateReader>,
+    ) -> Vec<(Address, Felt252)> {
+        let mut popped = Vec::new();
+        while let Some(tx) = mempool.pop_ready(state).unwrap() {
+            let sender = tx.contract_address();
+            state.increment_nonce(&sender).unwrap();
+            popped.push((sender, tx.nonce().unwrap()));
+        }
+        popped
+    }
+
+    #[test]
+    fn future_nonces_are_held_until_the_gap_is_filled() {
+        let mut state = state_with_nonces(&[(1, 0)]);
+        let mut mempool = Mempool::default();
+
+        mempool
+            .add_transaction(invoke(1, 2, 0), &mut state)
+            .unwrap();
+        mempool
+            .add_transaction(invoke(1, 1, 0), &mut state)
+            .unwrap();
+        assert!(mempool.pop_ready(&mut state).unwrap().is_none());
+
+        mempool
+            .add_transaction(invoke(1, 0, 0), &mut state)
+            .unwrap();
+        assert_eq!(
+            pop_all(&mut mempool, &mut state),
+            vec![
+                (Address(1.into()), 0.into()),
+                (Address(1.into()), 1.into()),
+                (Address(1.into()), 2.into()),
+            ]
+        );
+        assert!(mempool.is_empty());
+    }
+
+    #[test]
+    fn ready_transactions_are_popped_in_arrival_order() {
+        let mut state = state_with_nonces(&[(1, 0), (2, 5)]);
+        let mut mempool = Mempool::default();
+
+        mempool
+            .add_transaction(invoke(2, 5, 0), &mut state)
+            .unwrap();
+        mempool
+            .add_transaction(invoke(1, 0, 0), &mut state)
+            .unwrap();
+        mempool
+            .add_transaction(invoke(1, 1, 0), &mut state)
+            .unwrap();
+        mempool
+            .add_transaction(invoke(2, 6, 0), &mut state)
+            .unwrap();
+
+        assert_eq!(
+            pop_all(&mut mempool, &mut state),
+            vec![
+                (Address(2.into()), 5.into()),
+                (Address(1.into()), 0.into()),
+                (Address(1.into()), 1.into()),
+                (Address(2.into()), 6.into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn nonces_are_read_once_per_block() {
+        let mut state = state_with_nonces(&[(1, 0), (2, 0)]);
+        let mut mempool = Mempool::default();
+
+        mempool
+            .add_transaction(invoke(1, 0, 0), &mut state)
+            .unwrap();
+        mempool
+            .add_transaction(invoke(2, 1, 0), &mut state)
+            .unwrap();
+        assert_eq!(
+            pop_all(&mut mempool, &mut state),
+            vec![(Address(1.into()), 0.into())]
+        );
+
+        // The nonce of the second sender changed outside of the mempool, which is seen once the
+        // next block starts.
+        state.increment_nonce(&Address(2.into())).unwrap();
+        assert!(mempool.pop_ready(&mut state).unwrap().is_none());
+        mempool.evict_stale(&mut state).unwrap();
+        assert_eq!(
+            pop_all(&mut mempool, &mut state),
+            vec![(Address(2.into()), 1.into())]
+        );
+    }
+
+    #[test]
+    fn replacement_requires_a_fee_bump() {
+        let mut state = state_with_nonces(&[(1, 0)]);
+        let mut mempool = Mempool::default();
+        let replaced_hash = invoke(1, 0, 100).hash_value();
+
+        mempool
+            .add_transaction(invoke(1, 0, 100), &mut state)
+            .unwrap();
+        assert_eq!(
+            mempool.add_transaction(invoke(1, 0, 109), &mut state),
+            Err(MempoolError::ReplacementUnderpriced(100, 109))
+        );
+        assert_eq!(
+            mempool.add_transaction(invoke(1, 0, 110), &mut state),
+            Ok(Some(replaced_hash.clone()))
+        );
+
+        assert_eq!(mempool.len(), 1);
+        assert!(!mempool.contains(&replaced_hash));
+        let tx = mempool.pop_ready(&mut state).unwrap().unwrap();
+        assert_eq!(tx.max_fee(), 110);
+    }
+
+    #[test]
+    fn version_3_replacement_requires_a_price_bump() {
+        let mut state = state_with_nonces(&[(1, 0)]);
+        let mut mempool = Mempool::default();
+
+        mempool
+            .add_transaction(invoke_v3(1, 0, 1000, 100), &mut state)
+            .unwrap();
+        // Raising the max amount raises the max fee, but not the price.
+        assert_eq!(
+            mempool.add_transaction(invoke_v3(1, 0, 2000, 100), &mut state),
+            Err(MempoolError::ReplacementUnderpriced(100, 100))
+        );
+        let replacement = invoke_v3(1, 0, 1000, 110);
+        let replacement_hash = replacement.hash_value();
+        mempool.add_transaction(replacement, &mut state).unwrap();
+
+        assert_eq!(mempool.len(), 1);
+        assert!(mempool.contains(&replacement_hash));
+    }
+
+    #[test]
+    fn invalid_transactions_are_rejected() {
+        let mut state = state_with_nonces(&[(1, 3)]);
+        let mut mempool = Mempool::new(MempoolConfig {
+            max_txs_per_account: 1,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            mempool.add_transaction(invoke(1, 2, 0), &mut state),
+            Err(MempoolError::NonceTooLow(
+                Address(1.into()),
+                3.into(),
+                2.into()
+            ))
+        );
+        mempool
+            .add_transaction(invoke(1, 3, 0), &mut state)
+            .unwrap();
+        assert_eq!(
+            mempool.add_transaction(invoke(1, 3, 0), &mut state),
+            Err(MempoolError::DuplicateTransaction(
+                invoke(1, 3, 0).hash_value()
+            ))
+        );
+        assert_eq!(
+            mempool.add_transaction(invoke(1, 4, 0), &mut state),
+            Err(MempoolError::AccountLimitReached(Address(1.into())))
+        );
+    }
+
+    #[test]
+    fn stale_transactions_are_evicted() {
+        let mut state = state_with_nonces(&[(1, 0), (2, 0)]);
+        let mut mempool = Mempool::default();
+        mempool
+            .add_transaction(invoke(1, 0, 0), &mut state)
+            .unwrap();
+        mempool
+            .add_transaction(invoke(1, 1, 0), &mut state)
+            .unwrap();
+        mempool
+            .add_transaction(invoke(2, 0, 0), &mut state)
+            .unwrap();
+
+        // The first transaction of the account was executed without going through the mempool.
+        state.increment_nonce(&Address(1.into())).unwrap();
+
+        assert_eq!(
+            mempool.evict_stale(&mut state),
+            Ok(vec![invoke(1, 0, 0).hash_value()])
+        );
+        assert_eq!(mempool.queued_nonces(&Address(1.into())), vec![1.into()]);
+
+        mempool.config.time_to_live = Duration::ZERO;
+        assert_eq!(mempool.evict_stale(&mut state).unwrap().len(), 2);
+        assert!(mempool.is_empty());
+    }
+}
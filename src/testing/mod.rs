@@ -1,3 +1,9 @@
+pub mod accounts;
+pub mod block_producer;
+pub mod cheatcodes;
+pub mod event_store;
+pub mod mempool;
 pub mod starknet_state;
 pub mod starknet_state_error;
 pub mod type_utils;
+pub mod udc;
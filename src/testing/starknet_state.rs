@@ -1,4 +1,9 @@
-use super::starknet_state_error::StarknetStateError;
+use super::{
+    cheatcodes::{Cheatcodes, SharedCheatcodes},
+    event_store::{EventQuery, EventStore, EventsPage},
+    starknet_state_error::StarknetStateError,
+    udc::UDC_CONTRACT_PATH,
+};
 use crate::{
     business_logic::{
         execution::{
@@ -18,13 +23,19 @@ use crate::{
         transaction::{
             error::TransactionError,
             objects::{
-                internal_declare::InternalDeclare, internal_deploy::InternalDeploy,
+                call::{encode_execute_calldata, Call, CallFormat},
+                internal_declare::InternalDeclare,
+                internal_deploy::InternalDeploy,
                 internal_invoke_function::InternalInvokeFunction,
             },
             transactions::Transaction,
         },
     },
-    definitions::{constants::TRANSACTION_VERSION, general_config::StarknetGeneralConfig},
+    core::contract_address::starknet_contract_address::compute_class_hash,
+    definitions::{
+        constants::{EXECUTE_ENTRY_POINT_SELECTOR, TRANSACTION_VERSION, UDC_ADDRESS},
+        general_config::StarknetGeneralConfig,
+    },
     services::api::{
         contract_class::{ContractClass, EntryPointType},
         messages::StarknetMessageToL1,
@@ -33,7 +44,11 @@ use crate::{
 };
 use cairo_vm::felt::Felt252;
 use num_traits::{One, Zero};
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{MutexGuard, PoisonError},
+};
 
 // ---------------------------------------------------------------------
 /// StarkNet testing object. Represents a state of a StarkNet network.
@@ -41,12 +56,15 @@ use std::collections::HashMap;
 pub struct StarknetState {
     pub state: CachedState<InMemoryStateReader>,
     pub general_config: StarknetGeneralConfig,
-    l2_to_l1_messages: HashMap<Vec<u8>, usize>,
-    l2_to_l1_messages_log: Vec<StarknetMessageToL1>,
-    events: Vec<Event>,
+    pub(crate) l2_to_l1_messages: HashMap<Vec<u8>, usize>,
+    pub(crate) l2_to_l1_messages_log: Vec<StarknetMessageToL1>,
+    pub(crate) events: EventStore,
+    pub(crate) cheatcodes: Option<SharedCheatcodes>,
 }
 
 impl StarknetState {
+    /// Creates an empty state. Nothing is predeployed: call `predeploy_udc` to deploy contracts
+    /// through the Universal Deployer Contract.
     pub fn new(config: Option<StarknetGeneralConfig>) -> Self {
         let general_config = config.unwrap_or_default();
         let state_reader = InMemoryStateReader::default();
@@ -56,13 +74,14 @@ impl StarknetState {
         let l2_to_l1_messages = HashMap::new();
         let l2_to_l1_messages_log = Vec::new();
 
-        let events = Vec::new();
+        let events = EventStore::default();
         StarknetState {
             state,
             general_config,
             l2_to_l1_messages,
             l2_to_l1_messages_log,
             events,
+            cheatcodes: None,
         }
     }
 
@@ -74,13 +93,14 @@ impl StarknetState {
         let l2_to_l1_messages = HashMap::new();
         let l2_to_l1_messages_log = Vec::new();
 
-        let events = Vec::new();
+        let events = EventStore::default();
         StarknetState {
             state,
             general_config,
             l2_to_l1_messages,
             l2_to_l1_messages_log,
             events,
+            cheatcodes: None,
         }
     }
 
@@ -115,7 +135,7 @@ impl StarknetState {
         contract_address: Address,
         selector: Felt252,
         calldata: Vec<Felt252>,
-        max_fee: u64,
+        max_fee: u128,
         signature: Option<Vec<Felt252>>,
         nonce: Option<Felt252>,
     ) -> Result<TransactionExecutionInfo, StarknetStateError> {
@@ -132,6 +152,27 @@ impl StarknetState {
         self.execute_tx(&mut tx)
     }
 
+    /// Invokes the `__execute__` entry point of an account with the given calls, encoded in the
+    /// calldata format of the account. Returns the execution info.
+    pub fn invoke_calls(
+        &mut self,
+        account_address: Address,
+        calls: &[Call],
+        call_format: CallFormat,
+        max_fee: u128,
+        signature: Option<Vec<Felt252>>,
+        nonce: Option<Felt252>,
+    ) -> Result<TransactionExecutionInfo, StarknetStateError> {
+        self.invoke_raw(
+            account_address,
+            EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+            encode_execute_calldata(calls, call_format),
+            max_fee,
+            signature,
+            nonce,
+        )
+    }
+
     /// Builds the transaction execution context and executes the entry point.
     /// Returns the CallInfo.
     pub fn execute_entry_point_raw(
@@ -165,7 +206,7 @@ impl StarknetState {
         self.add_messages_and_events(
             &call_info.get_sorted_events()?,
             &call_info.get_sorted_l2_to_l1_messages()?,
-        )?;
+        );
 
         Ok(call_info)
     }
@@ -197,44 +238,54 @@ impl StarknetState {
         Ok((tx.contract_address(), tx_execution_info))
     }
 
+    /// Declares the Universal Deployer Contract and deploys it at its canonical address,
+    /// without executing any transaction. Returns its class hash.
+    /// The UDC is opt-in, as its class is read from `UDC_CONTRACT_PATH`, relative to the working
+    /// directory.
+    pub fn predeploy_udc(&mut self) -> Result<ClassHash, StarknetStateError> {
+        let contract_class = ContractClass::try_from(PathBuf::from(UDC_CONTRACT_PATH))?;
+        let class_hash = compute_class_hash(&contract_class)?.to_be_bytes();
+
+        self.state
+            .set_contract_class(&class_hash, &contract_class)?;
+        self.state
+            .deploy_contract(UDC_ADDRESS.clone(), class_hash)?;
+        Ok(class_hash)
+    }
+
     pub fn execute_tx(
         &mut self,
         tx: &mut Transaction,
     ) -> Result<TransactionExecutionInfo, StarknetStateError> {
-        let tx = tx.execute(&mut self.state, &self.general_config)?;
-        self.add_messages_and_events(
-            &tx.get_sorted_events()?,
-            &tx.get_sorted_l2_to_l1_messages()?,
-        )?;
-        Ok(tx)
+        let execution_info = tx.execute(&mut self.state, &self.general_config)?;
+        self.add_messages(&execution_info.get_sorted_l2_to_l1_messages()?);
+        self.events.add_events(
+            &execution_info.get_sorted_events()?,
+            self.general_config.block_info().block_number,
+            Some(tx.hash_value()),
+        );
+        Ok(execution_info)
     }
 
+    /// Records the L2-to-L1 messages and events of a call executed outside of a transaction.
     pub fn add_messages_and_events(
         &mut self,
         events: &[Event],
         l2_to_l1_messages: &[L2toL1MessageInfo],
-    ) -> Result<(), StarknetStateError> {
-        for msg in l2_to_l1_messages {
-            let starknet_message = StarknetMessageToL1::new(
-                msg.from_address.clone(),
-                msg.to_address.clone(),
-                msg.payload.clone(),
-            );
-
-            self.l2_to_l1_messages_log.push(starknet_message.clone());
-            let message_hash = starknet_message.get_hash();
+    ) {
+        self.add_messages(l2_to_l1_messages);
+        self.events
+            .add_events(events, self.general_config.block_info().block_number, None);
+    }
 
-            if self.l2_to_l1_messages.contains_key(&message_hash) {
-                let val = self.l2_to_l1_messages.get(&message_hash).unwrap();
-                self.l2_to_l1_messages.insert(message_hash, val + 1);
-            } else {
-                self.l2_to_l1_messages.insert(message_hash, 1);
-            }
-        }
+    /// Returns the emitted events.
+    pub fn events(&self) -> &EventStore {
+        &self.events
+    }
 
-        let mut events = events.to_owned();
-        self.events.append(&mut events);
-        Ok(())
+    /// Returns a page of the emitted events matching the query.
+    pub fn get_events(&self, query: &EventQuery) -> Result<EventsPage, StarknetStateError> {
+        self.events.query(query)
     }
 
     /// Consumes the given message hash.
@@ -255,10 +306,56 @@ impl StarknetState {
         }
     }
 
+    /// Returns the cheatcodes applied to every execution of this state, enabling them if needed.
+    pub fn cheatcodes(&mut self) -> MutexGuard<Cheatcodes> {
+        let general_config = &mut self.general_config;
+        self.cheatcodes
+            .get_or_insert_with(|| {
+                let cheatcodes = SharedCheatcodes::default();
+                general_config.syscall_hooks = Some(cheatcodes.clone());
+                cheatcodes
+            })
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Checks that every event registered with `Cheatcodes::expect_event` was emitted, and
+    /// clears the expectations.
+    pub fn assert_expected_events_emitted(&mut self) -> Result<(), StarknetStateError> {
+        let unmatched_events = self.cheatcodes().take_unmatched_expected_events();
+        if unmatched_events.is_empty() {
+            Ok(())
+        } else {
+            Err(StarknetStateError::ExpectedEventsNotEmitted(
+                unmatched_events,
+            ))
+        }
+    }
+
     // ------------------------
     //    Private functions
     // ------------------------
 
+    pub(crate) fn add_messages(&mut self, l2_to_l1_messages: &[L2toL1MessageInfo]) {
+        for msg in l2_to_l1_messages {
+            let starknet_message = StarknetMessageToL1::new(
+                msg.from_address.clone(),
+                msg.to_address.clone(),
+                msg.payload.clone(),
+            );
+
+            self.l2_to_l1_messages_log.push(starknet_message.clone());
+            let message_hash = starknet_message.get_hash();
+
+            if self.l2_to_l1_messages.contains_key(&message_hash) {
+                let val = self.l2_to_l1_messages.get(&message_hash).unwrap();
+                self.l2_to_l1_messages.insert(message_hash, val + 1);
+            } else {
+                self.l2_to_l1_messages.insert(message_hash, 1);
+            }
+        }
+    }
+
     fn chain_id(&self) -> Felt252 {
         self.general_config.starknet_os_config.chain_id.to_felt()
     }
@@ -268,7 +365,7 @@ impl StarknetState {
         contract_address: Address,
         entry_point_selector: Felt252,
         calldata: Vec<Felt252>,
-        max_fee: u64,
+        max_fee: u128,
         signature: Option<Vec<Felt252>>,
         nonce: Option<Felt252>,
     ) -> Result<InternalInvokeFunction, TransactionError> {
@@ -353,6 +450,7 @@ mod tests {
             actual_fee: 0,
             actual_resources,
             tx_type: Some(TransactionType::Deploy),
+            revert_error: None,
         };
 
         // check result is correct
@@ -604,12 +702,10 @@ mod tests {
             ..Default::default()
         }));
 
-        starknet_state
-            .add_messages_and_events(
-                &exec_info.get_sorted_events().unwrap(),
-                &exec_info.get_sorted_l2_to_l1_messages().unwrap(),
-            )
-            .unwrap();
+        starknet_state.add_messages_and_events(
+            &exec_info.get_sorted_events().unwrap(),
+            &exec_info.get_sorted_l2_to_l1_messages().unwrap(),
+        );
         let msg_hash =
             StarknetMessageToL1::new(Address(0.into()), Address(0.into()), vec![0.into()])
                 .get_hash();
@@ -639,12 +735,10 @@ mod tests {
             ..Default::default()
         }));
 
-        starknet_state
-            .add_messages_and_events(
-                &exec_info.get_sorted_events().unwrap(),
-                &exec_info.get_sorted_l2_to_l1_messages().unwrap(),
-            )
-            .unwrap();
+        starknet_state.add_messages_and_events(
+            &exec_info.get_sorted_events().unwrap(),
+            &exec_info.get_sorted_l2_to_l1_messages().unwrap(),
+        );
         let msg_hash =
             StarknetMessageToL1::new(Address(0.into()), Address(0.into()), vec![0.into()])
                 .get_hash();
@@ -672,12 +766,10 @@ mod tests {
             ..Default::default()
         }));
 
-        starknet_state
-            .add_messages_and_events(
-                &exec_info.get_sorted_events().unwrap(),
-                &exec_info.get_sorted_l2_to_l1_messages().unwrap(),
-            )
-            .unwrap();
+        starknet_state.add_messages_and_events(
+            &exec_info.get_sorted_events().unwrap(),
+            &exec_info.get_sorted_l2_to_l1_messages().unwrap(),
+        );
         let msg_hash =
             StarknetMessageToL1::new(Address(0.into()), Address(0.into()), vec![0.into()])
                 .get_hash();
@@ -1,14 +1,35 @@
+use cairo_vm::types::errors::program_errors::ProgramError;
 use thiserror::Error;
 
 use crate::{
-    business_logic::transaction::error::TransactionError,
-    core::errors::{state_errors::StateError, syscall_handler_errors::SyscallHandlerError},
+    business_logic::{execution::objects::Event, transaction::error::TransactionError},
+    core::errors::{
+        contract_address_errors::ContractAddressError, state_errors::StateError,
+        syscall_handler_errors::SyscallHandlerError,
+    },
+    testing::mempool::error::MempoolError,
 };
 
 #[derive(Debug, Error)]
 pub enum StarknetStateError {
     #[error("Invalid message hash key passed to l2 messages")]
     InvalidMessageHash,
+    #[error("Expected events were not emitted: {0:?}")]
+    ExpectedEventsNotEmitted(Vec<Event>),
+    #[error("The transaction didn't emit a ContractDeployed event")]
+    MissingContractDeployedEvent,
+    #[error("Invalid continuation token: {0}")]
+    InvalidContinuationToken(String),
+    #[error("The chunk size of an events query must be at least 1")]
+    EmptyChunkSize,
+    #[error("Couldn't sign or verify the message: {0}")]
+    Signature(String),
+    #[error(transparent)]
+    Mempool(#[from] MempoolError),
+    #[error(transparent)]
+    Program(#[from] ProgramError),
+    #[error(transparent)]
+    ContractAddress(#[from] ContractAddressError),
     #[error(transparent)]
     Syscall(#[from] SyscallHandlerError),
     #[error(transparent)]
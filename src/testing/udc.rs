@@ -0,0 +1,198 @@
+use crate::{
+    business_logic::{
+        execution::objects::{Event, TransactionExecutionInfo},
+        transaction::{error::TransactionError, objects::call::Call},
+    },
+    core::errors::syscall_handler_errors::SyscallHandlerError,
+    definitions::constants::UDC_ADDRESS,
+    hash_utils::calculate_contract_address,
+    utils::{
+        calculate_sn_keccak, felt_to_field_element, field_element_to_felt, Address, ClassHash,
+    },
+};
+use cairo_vm::felt::Felt252;
+use num_traits::{One, ToPrimitive, Zero};
+use starknet_crypto::pedersen_hash;
+
+/// Compiled OpenZeppelin Universal Deployer Contract.
+pub const UDC_CONTRACT_PATH: &str = "starknet_programs/UniversalDeployer.json";
+
+/// Returns the call to the `deployContract` entry point of the UDC deploying a contract of the
+/// given class.
+pub fn deploy_contract_call(
+    class_hash: &ClassHash,
+    salt: Felt252,
+    unique: bool,
+    constructor_calldata: Vec<Felt252>,
+) -> Call {
+    let mut calldata = vec![
+        Felt252::from_bytes_be(class_hash),
+        salt,
+        Felt252::from(unique as u8),
+        Felt252::from(constructor_calldata.len()),
+    ];
+    calldata.extend(constructor_calldata);
+
+    Call::from_entry_point_name(UDC_ADDRESS.clone(), "deployContract", calldata)
+}
+
+/// Returns the address of a contract deployed through the UDC by `deployer_address`.
+/// Unique deployments are made from the UDC with the salt hashed together with the deployer
+/// address, so that other accounts can't take the address. The other deployments are made from
+/// address zero, like a deploy account transaction.
+pub fn calculate_udc_contract_address(
+    class_hash: &ClassHash,
+    salt: &Felt252,
+    unique: bool,
+    constructor_calldata: &[Felt252],
+    deployer_address: &Address,
+) -> Result<Address, SyscallHandlerError> {
+    let (salt, deployer) = if unique {
+        let unique_salt = pedersen_hash(
+            &felt_to_field_element(&deployer_address.0)?,
+            &felt_to_field_element(salt)?,
+        );
+        (field_element_to_felt(&unique_salt), UDC_ADDRESS.clone())
+    } else {
+        (salt.clone(), Address(Felt252::zero()))
+    };
+
+    let address = calculate_contract_address(
+        &Address(salt),
+        &Felt252::from_bytes_be(class_hash),
+        constructor_calldata,
+        deployer,
+    )?;
+    Ok(Address(address))
+}
+
+// ---------------------------------------------------------------------
+/// Data of a `ContractDeployed` event, emitted by the UDC for each deployment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractDeployed {
+    pub address: Address,
+    pub deployer: Address,
+    pub unique: bool,
+    pub class_hash: ClassHash,
+    pub constructor_calldata: Vec<Felt252>,
+    pub salt: Felt252,
+}
+
+impl ContractDeployed {
+    /// Parses a `ContractDeployed` event. Returns `None` if the event has another key or its
+    /// data doesn't have the layout of the event.
+    pub fn from_event(event: &Event) -> Option<Self> {
+        let key = Felt252::from_bytes_be(&calculate_sn_keccak(b"ContractDeployed"));
+        if event.keys != [key] {
+            return None;
+        }
+
+        match event.data.as_slice() {
+            [address, deployer, unique, class_hash, calldata_len, rest @ ..] => {
+                let calldata_len = calldata_len.to_usize()?;
+                if rest.len().checked_sub(1) != Some(calldata_len) {
+                    return None;
+                }
+
+                Some(ContractDeployed {
+                    address: Address(address.clone()),
+                    deployer: Address(deployer.clone()),
+                    unique: unique.is_one(),
+                    class_hash: class_hash.to_be_bytes(),
+                    constructor_calldata: rest[..calldata_len].to_vec(),
+                    salt: rest[calldata_len].clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the `ContractDeployed` events emitted by the UDC during a transaction, in
+    /// emission order.
+    pub fn from_execution_info(
+        tx_execution_info: &TransactionExecutionInfo,
+    ) -> Result<Vec<Self>, TransactionError> {
+        Ok(tx_execution_info
+            .get_sorted_events()?
+            .iter()
+            .filter(|event| event.from_address == *UDC_ADDRESS)
+            .filter_map(ContractDeployed::from_event)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use coverage_helper::test;
+
+    #[test]
+    fn unique_address_depends_on_the_deployer() {
+        let class_hash = [1; 32];
+        let salt = Felt252::from(123);
+        let calldata = [Felt252::from(7)];
+        let address_of = |unique: bool, deployer: u64| {
+            calculate_udc_contract_address(
+                &class_hash,
+                &salt,
+                unique,
+                &calldata,
+                &Address(deployer.into()),
+            )
+            .unwrap()
+        };
+
+        assert_ne!(address_of(true, 1), address_of(true, 2));
+        assert_eq!(address_of(false, 1), address_of(false, 2));
+        assert_eq!(
+            address_of(false, 1),
+            Address(
+                calculate_contract_address(
+                    &Address(salt.clone()),
+                    &Felt252::from_bytes_be(&class_hash),
+                    &calldata,
+                    Address(0.into()),
+                )
+                .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn parse_contract_deployed_event() {
+        let mut event = Event {
+            from_address: UDC_ADDRESS.clone(),
+            keys: vec![Felt252::from_bytes_be(&calculate_sn_keccak(
+                b"ContractDeployed",
+            ))],
+            data: vec![
+                10.into(), // address
+                11.into(), // deployer
+                1.into(),  // unique
+                12.into(), // classHash
+                2.into(),  // calldata_len
+                13.into(),
+                14.into(),
+                15.into(), // salt
+            ],
+        };
+
+        assert_eq!(
+            ContractDeployed::from_event(&event),
+            Some(ContractDeployed {
+                address: Address(10.into()),
+                deployer: Address(11.into()),
+                unique: true,
+                class_hash: Felt252::from(12).to_be_bytes(),
+                constructor_calldata: vec![13.into(), 14.into()],
+                salt: 15.into(),
+            })
+        );
+
+        event.data.pop();
+        assert_eq!(ContractDeployed::from_event(&event), None);
+
+        event.data[4] = usize::MAX.into();
+        assert_eq!(ContractDeployed::from_event(&event), None);
+    }
+}
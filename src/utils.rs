@@ -17,10 +17,12 @@ use crate::{
 };
 use cairo_vm::felt::{Felt252, ParseFeltError};
 use cairo_vm::{types::relocatable::Relocatable, vm::vm_core::VirtualMachine};
-use num_traits::{Num, ToPrimitive};
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{Num, One, Pow, ToPrimitive};
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
-use starknet_crypto::FieldElement;
+use starknet_crypto::{pedersen_hash, FieldElement};
 use std::{
     collections::{HashMap, HashSet},
     hash::Hash,
@@ -32,7 +34,7 @@ pub type ClassHash = [u8; 32];
 //*      Address
 //* -------------------
 
-#[derive(Debug, Clone, PartialEq, Hash, Eq, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Hash, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub struct Address(pub Felt252);
 
 //* -------------------
@@ -132,6 +134,7 @@ pub fn calculate_tx_resources(
     tx_type: TransactionType,
     storage_changes: (usize, usize),
     l1_handler_payload_size: Option<usize>,
+    starknet_version: &str,
 ) -> Result<HashMap<String, usize>, TransactionError> {
     let (n_modified_contracts, n_storage_changes) = storage_changes;
 
@@ -156,7 +159,8 @@ pub fn calculate_tx_resources(
     let tx_syscall_counter = resources_manager.syscall_counter;
 
     // Add additional Cairo resources needed for the OS to run the transaction.
-    let additional_resources = get_additional_os_resources(tx_syscall_counter, &tx_type)?;
+    let additional_resources =
+        get_additional_os_resources(tx_syscall_counter, &tx_type, starknet_version)?;
     let new_resources = &cairo_usage + &additional_resources;
     let filtered_builtins = new_resources.filter_unused_builtins();
 
@@ -263,6 +267,7 @@ pub(crate) fn verify_no_calls_to_other_contracts(
     }
     Ok(())
 }
+
 pub fn calculate_sn_keccak(data: &[u8]) -> ClassHash {
     let mut hasher = Keccak256::default();
     hasher.update(data);
@@ -272,6 +277,44 @@ pub fn calculate_sn_keccak(data: &[u8]) -> ClassHash {
     result
 }
 
+/// Returns the address of a storage variable, given its name and the keys used to access it.
+pub fn get_storage_var_address(
+    storage_var_name: &str,
+    args: &[Felt252],
+) -> Result<Felt252, SyscallHandlerError> {
+    let l2_address_upper_bound = Felt252::new(2).pow(251) - Felt252::new(256);
+
+    let mut address = felt_to_field_element(&Felt252::from_bytes_be(&calculate_sn_keccak(
+        storage_var_name.as_bytes(),
+    )))?;
+    for arg in args {
+        address = pedersen_hash(&address, &felt_to_field_element(arg)?);
+    }
+
+    Ok(field_element_to_felt(&address).mod_floor(&l2_address_upper_bound))
+}
+
+/// Returns the storage keys of the low and high words of an account's balance in an ERC20
+/// contract, i.e. the `ERC20_balances` storage variable.
+pub fn get_erc20_balance_var_addresses(
+    contract_address: &Address,
+) -> Result<(ClassHash, ClassHash), SyscallHandlerError> {
+    let balance_low = get_storage_var_address("ERC20_balances", &[contract_address.0.clone()])?;
+    let balance_high = balance_low.clone() + Felt252::one();
+
+    Ok((balance_low.to_be_bytes(), balance_high.to_be_bytes()))
+}
+
+/// Splits a Uint256 value into its low and high 128-bit words, the way Cairo contracts receive
+/// it.
+pub fn split_uint256(value: &BigUint) -> (Felt252, Felt252) {
+    let low_mask = (BigUint::one() << 128_usize) - BigUint::one();
+    (
+        Felt252::from(value & low_mask),
+        Felt252::from(value >> 128_usize),
+    )
+}
+
 //* -------------------
 //*      Macros
 //* -------------------
@@ -702,4 +745,39 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn get_erc20_balance_var_addresses_test() {
+        let (balance_low, balance_high) =
+            get_erc20_balance_var_addresses(&Address(257.into())).unwrap();
+
+        assert_eq!(
+            balance_low,
+            felt_str!(
+                "1192211877881866289306604115402199097887041303917861778777990838480655617515"
+            )
+            .to_be_bytes()
+        );
+        assert_eq!(
+            balance_high,
+            felt_str!(
+                "1192211877881866289306604115402199097887041303917861778777990838480655617516"
+            )
+            .to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn split_uint256_test() {
+        let value = (BigUint::from(3_u32) << 128_usize) + BigUint::from(u128::MAX);
+
+        assert_eq!(
+            split_uint256(&value),
+            (Felt252::from(u128::MAX), Felt252::from(3))
+        );
+        assert_eq!(
+            split_uint256(&BigUint::from(7_u32)),
+            (Felt252::from(7), Felt252::from(0))
+        );
+    }
 }
@@ -0,0 +1,221 @@
+#![deny(warnings)]
+
+use assert_matches::assert_matches;
+use cairo_vm::felt::Felt252;
+use starknet_rs::{
+    business_logic::{
+        state::state_api::StateReader,
+        transaction::{
+            error::TransactionError,
+            objects::{
+                call::{decode_execute_retdata, encode_execute_calldata, Call, CallFormat},
+                internal_invoke_function::InternalInvokeFunction,
+            },
+        },
+    },
+    definitions::constants::EXECUTE_ENTRY_POINT_SELECTOR,
+    services::api::contract_class::ContractClass,
+    testing::{
+        accounts::{Account, KeyPair},
+        starknet_state::StarknetState,
+    },
+    utils::Address,
+};
+use std::path::PathBuf;
+
+fn deploy_fibonacci(starknet_state: &mut StarknetState) -> Address {
+    let contract_class =
+        ContractClass::try_from(PathBuf::from("starknet_programs/fibonacci.json")).unwrap();
+    let (contract_address, _exec_info) = starknet_state
+        .deploy(contract_class, vec![], Address(1.into()))
+        .unwrap();
+
+    contract_address
+}
+
+fn fib_call(contract_address: &Address) -> Call {
+    Call::from_entry_point_name(
+        contract_address.clone(),
+        "fib",
+        vec![1.into(), 1.into(), 10.into()],
+    )
+}
+
+#[test]
+fn predeployed_account_invokes_with_increasing_nonces() {
+    let mut starknet_state = StarknetState::new(None);
+    let account = Account::predeploy(&mut starknet_state, KeyPair::from_seed(1).unwrap()).unwrap();
+    let contract_address = deploy_fibonacci(&mut starknet_state);
+
+    for expected_nonce in 1..=2 {
+        let tx_info = account
+            .execute(&mut starknet_state, &[fib_call(&contract_address)], 0)
+            .unwrap();
+
+        assert_eq!(tx_info.revert_error, None);
+        assert_eq!(
+            decode_execute_retdata(&tx_info.call_info.unwrap(), CallFormat::Legacy).unwrap(),
+            vec![vec![144.into()]]
+        );
+        assert_eq!(
+            starknet_state.state.get_nonce_at(account.address()),
+            Ok(expected_nonce.into())
+        );
+    }
+}
+
+#[test]
+fn invoke_with_a_wrong_signature_is_rejected() {
+    let mut starknet_state = StarknetState::new(None);
+    let account = Account::predeploy(&mut starknet_state, KeyPair::from_seed(1).unwrap()).unwrap();
+    let contract_address = deploy_fibonacci(&mut starknet_state);
+    let other_key_pair = KeyPair::from_seed(2).unwrap();
+
+    let result = starknet_state.invoke_calls(
+        account.address().clone(),
+        &[fib_call(&contract_address)],
+        CallFormat::Legacy,
+        0,
+        Some(other_key_pair.sign(&Felt252::from(1)).unwrap()),
+        None,
+    );
+
+    assert!(result.is_err());
+    assert_eq!(
+        starknet_state.state.get_nonce_at(account.address()),
+        Ok(0.into())
+    );
+}
+
+#[test]
+fn validate_checks_the_signature_without_executing() {
+    let mut starknet_state = StarknetState::new(None);
+    let account = Account::predeploy(&mut starknet_state, KeyPair::from_seed(1).unwrap()).unwrap();
+    let contract_address = deploy_fibonacci(&mut starknet_state);
+    let calldata = encode_execute_calldata(&[fib_call(&contract_address)], CallFormat::Legacy);
+
+    let tx = account
+        .invoke_transaction(&starknet_state, calldata.clone(), 0, 0.into())
+        .unwrap();
+    let tx_info = tx
+        .validate(&mut starknet_state.state, &starknet_state.general_config)
+        .unwrap();
+    assert!(tx_info.validate_info.is_some());
+    assert_eq!(tx_info.call_info, None);
+    assert_eq!(tx_info.actual_fee, 0);
+    assert_eq!(
+        starknet_state.state.get_nonce_at(account.address()),
+        Ok(0.into())
+    );
+
+    let chain_id = starknet_state
+        .general_config
+        .starknet_os_config()
+        .chain_id()
+        .to_felt();
+    let tx = InternalInvokeFunction::new(
+        account.address().clone(),
+        EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+        0,
+        calldata,
+        Vec::new(),
+        chain_id,
+        Some(0.into()),
+    )
+    .unwrap();
+    let other_signature = KeyPair::from_seed(2)
+        .unwrap()
+        .sign(tx.hash_value())
+        .unwrap();
+    assert!(tx
+        .with_signature(other_signature)
+        .validate(&mut starknet_state.state, &starknet_state.general_config)
+        .is_err());
+}
+
+#[test]
+fn validate_enforces_the_step_limit() {
+    let mut starknet_state = StarknetState::new(None);
+    let account = Account::predeploy(&mut starknet_state, KeyPair::from_seed(1).unwrap()).unwrap();
+    let contract_address = deploy_fibonacci(&mut starknet_state);
+    let tx = account
+        .invoke_transaction(
+            &starknet_state,
+            encode_execute_calldata(&[fib_call(&contract_address)], CallFormat::Legacy),
+            0,
+            0.into(),
+        )
+        .unwrap();
+
+    *starknet_state.general_config.validate_max_n_steps_mut() = 10;
+    assert_matches!(
+        tx.validate(&mut starknet_state.state, &starknet_state.general_config),
+        Err(TransactionError::StepsLimitExceeded(10))
+    );
+}
+
+#[test]
+fn multicall_returns_the_result_of_each_call() {
+    let mut starknet_state = StarknetState::new(None);
+    let account = Account::predeploy(&mut starknet_state, KeyPair::from_seed(1).unwrap()).unwrap();
+    let contract_address = deploy_fibonacci(&mut starknet_state);
+    let calls = [
+        fib_call(&contract_address),
+        Call::from_entry_point_name(contract_address, "fib", vec![0.into(), 1.into(), 5.into()]),
+    ];
+
+    let tx_info = account.execute(&mut starknet_state, &calls, 0).unwrap();
+
+    assert_eq!(
+        decode_execute_retdata(&tx_info.call_info.unwrap(), CallFormat::Legacy).unwrap(),
+        vec![vec![144.into()], vec![8.into()]]
+    );
+}
+
+#[test]
+fn account_declares_classes() {
+    let mut starknet_state = StarknetState::new(None);
+    let account = Account::predeploy(&mut starknet_state, KeyPair::from_seed(1).unwrap()).unwrap();
+    let contract_class =
+        ContractClass::try_from(PathBuf::from("starknet_programs/fibonacci.json")).unwrap();
+
+    let (class_hash, tx_info) = account
+        .declare(&mut starknet_state, contract_class.clone(), 0)
+        .unwrap();
+
+    assert!(tx_info.validate_info.is_some());
+    assert_eq!(
+        starknet_state.state.get_contract_class(&class_hash),
+        Ok(contract_class)
+    );
+    assert_eq!(
+        starknet_state.state.get_nonce_at(account.address()),
+        Ok(1.into())
+    );
+}
+
+#[test]
+fn deploy_account_uses_the_precomputed_address() {
+    let mut starknet_state = StarknetState::new(None);
+    let predeployed_account =
+        Account::predeploy(&mut starknet_state, KeyPair::from_seed(1).unwrap()).unwrap();
+    let key_pair = KeyPair::from_seed(2).unwrap();
+    let class_hash = *predeployed_account.class_hash();
+
+    let (account, tx_info) =
+        Account::deploy(&mut starknet_state, class_hash, key_pair.clone(), 0).unwrap();
+
+    assert_eq!(tx_info.revert_error, None);
+    assert_eq!(
+        account.address(),
+        &Account::calculate_address(&class_hash, key_pair.public_key()).unwrap()
+    );
+    assert_eq!(
+        starknet_state.state.get_class_hash_at(account.address()),
+        Ok(class_hash)
+    );
+    assert_eq!(
+        starknet_state.state.get_nonce_at(account.address()),
+        Ok(1.into())
+    );
+}
@@ -0,0 +1,251 @@
+#![deny(warnings)]
+
+use cairo_vm::felt::Felt252;
+use starknet_rs::{
+    business_logic::transaction::objects::call::{decode_execute_retdata, Call, CallFormat},
+    services::api::contract_class::ContractClass,
+    testing::{
+        accounts::{Account, KeyPair},
+        cheatcodes::TxInfoSpoof,
+        starknet_state::StarknetState,
+        starknet_state_error::StarknetStateError,
+    },
+    utils::{calculate_sn_keccak, Address},
+};
+use std::path::PathBuf;
+
+fn selector(name: &str) -> Felt252 {
+    Felt252::from_bytes_be(&calculate_sn_keccak(name.as_bytes()))
+}
+
+fn deploy_syscalls_contract() -> (StarknetState, Address) {
+    let mut starknet_state = StarknetState::new(None);
+    let contract_address = deploy_syscalls_contract_in(&mut starknet_state);
+
+    (starknet_state, contract_address)
+}
+
+fn deploy_syscalls_contract_in(starknet_state: &mut StarknetState) -> Address {
+    let contract_class =
+        ContractClass::try_from(PathBuf::from("starknet_programs/syscalls.json")).unwrap();
+
+    let (contract_address, _exec_info) = starknet_state
+        .deploy(contract_class, vec![], Address(1.into()))
+        .unwrap();
+
+    contract_address
+}
+
+#[test]
+fn prank_overrides_caller_for_the_next_calls() {
+    let (mut starknet_state, contract_address) = deploy_syscalls_contract();
+    let caller_address = Address(7.into());
+
+    starknet_state
+        .cheatcodes()
+        .prank(contract_address.clone(), Address(1234.into()), Some(1));
+
+    let call_info = starknet_state
+        .execute_entry_point_raw(
+            contract_address.clone(),
+            selector("test_get_caller_address"),
+            vec![],
+            caller_address.clone(),
+        )
+        .unwrap();
+    assert_eq!(call_info.retdata, vec![1234.into()]);
+    assert_eq!(call_info.caller_address, Address(1234.into()));
+
+    let call_info = starknet_state
+        .execute_entry_point_raw(
+            contract_address,
+            selector("test_get_caller_address"),
+            vec![],
+            caller_address.clone(),
+        )
+        .unwrap();
+    assert_eq!(call_info.retdata, vec![caller_address.0.clone()]);
+    assert_eq!(call_info.caller_address, caller_address);
+}
+
+#[test]
+fn roll_and_warp_override_block_info() {
+    let (mut starknet_state, contract_address) = deploy_syscalls_contract();
+
+    starknet_state.cheatcodes().roll(10);
+    starknet_state.cheatcodes().warp(20);
+
+    let call_info = starknet_state
+        .execute_entry_point_raw(
+            contract_address.clone(),
+            selector("test_get_block_number"),
+            vec![],
+            Address(0.into()),
+        )
+        .unwrap();
+    assert_eq!(call_info.retdata, vec![10.into()]);
+
+    let call_info = starknet_state
+        .execute_entry_point_raw(
+            contract_address,
+            selector("test_get_block_timestamp"),
+            vec![],
+            Address(0.into()),
+        )
+        .unwrap();
+    assert_eq!(call_info.retdata, vec![20.into()]);
+}
+
+#[test]
+fn mock_call_skips_callee_execution() {
+    let (mut starknet_state, contract_address) = deploy_syscalls_contract();
+    // Nothing is deployed at this address, so the call only succeeds if every call is mocked.
+    let mocked_address = Address(0xdead.into());
+
+    {
+        let mut cheatcodes = starknet_state.cheatcodes();
+        cheatcodes.mock_call(
+            mocked_address.clone(),
+            selector("stateless_func"),
+            vec![42.into()],
+        );
+        cheatcodes.mock_call(mocked_address.clone(), selector("stateful_func"), vec![]);
+        cheatcodes.mock_call(
+            mocked_address.clone(),
+            selector("stateful_get_contract_address"),
+            vec![mocked_address.0.clone()],
+        );
+    }
+
+    let call_info = starknet_state
+        .execute_entry_point_raw(
+            contract_address,
+            selector("test_call_contract"),
+            vec![mocked_address.0.clone()],
+            Address(0.into()),
+        )
+        .unwrap();
+
+    // Mocked calls are still recorded, without a class hash since nothing was executed.
+    assert_eq!(call_info.internal_calls.len(), 3);
+    assert_eq!(call_info.internal_calls[0].retdata, vec![42.into()]);
+    assert_eq!(call_info.internal_calls[2].retdata, vec![mocked_address.0]);
+    assert!(call_info
+        .internal_calls
+        .iter()
+        .all(
+            |internal_call| internal_call.contract_address == mocked_address
+                && internal_call.class_hash.is_none()
+        ));
+}
+
+#[test]
+fn mocked_calls_keep_multicall_results_decodable() {
+    let mut starknet_state = StarknetState::new(None);
+    let account = Account::predeploy(&mut starknet_state, KeyPair::from_seed(1).unwrap()).unwrap();
+    let contract_address = deploy_syscalls_contract_in(&mut starknet_state);
+    let mocked_address = Address(0xdead.into());
+
+    starknet_state.cheatcodes().mock_call(
+        mocked_address.clone(),
+        selector("stateless_func"),
+        vec![42.into(), 43.into()],
+    );
+
+    let tx_info = account
+        .execute(
+            &mut starknet_state,
+            &[
+                Call::new(mocked_address, selector("stateless_func"), vec![]),
+                Call::from_entry_point_name(
+                    contract_address.clone(),
+                    "test_get_contract_address",
+                    vec![],
+                ),
+            ],
+            0,
+        )
+        .unwrap();
+
+    assert_eq!(tx_info.revert_error, None);
+    assert_eq!(
+        decode_execute_retdata(&tx_info.call_info.unwrap(), CallFormat::Legacy).unwrap(),
+        vec![vec![42.into(), 43.into()], vec![contract_address.0]]
+    );
+}
+
+#[test]
+fn spoof_tx_info_overrides_get_tx_info() {
+    let (mut starknet_state, contract_address) = deploy_syscalls_contract();
+
+    starknet_state.cheatcodes().spoof_tx_info(TxInfoSpoof {
+        version: Some(1),
+        account_contract_address: Some(Address(5.into())),
+        max_fee: Some(100),
+        signature: Some(vec![3.into(), 4.into()]),
+        transaction_hash: Some(6.into()),
+        nonce: None,
+    });
+
+    let call_info = starknet_state
+        .execute_entry_point_raw(
+            contract_address,
+            selector("test_get_tx_info"),
+            vec![],
+            Address(0.into()),
+        )
+        .unwrap();
+
+    assert_eq!(
+        call_info.retdata,
+        vec![
+            1.into(),
+            5.into(),
+            100.into(),
+            2.into(),
+            7.into(),
+            6.into(),
+            starknet_state
+                .general_config
+                .starknet_os_config()
+                .chain_id()
+                .to_felt(),
+        ]
+    );
+}
+
+#[test]
+fn expect_event_reports_missing_events() {
+    let (mut starknet_state, contract_address) = deploy_syscalls_contract();
+
+    starknet_state.cheatcodes().expect_event(
+        contract_address.clone(),
+        vec![selector("test_event")],
+        vec![1.into(), 2.into(), 3.into()],
+    );
+    starknet_state.cheatcodes().expect_event(
+        contract_address.clone(),
+        vec![selector("test_event")],
+        vec![9.into()],
+    );
+
+    starknet_state
+        .execute_entry_point_raw(
+            contract_address.clone(),
+            selector("test_emit_event"),
+            vec![],
+            Address(0.into()),
+        )
+        .unwrap();
+
+    match starknet_state.assert_expected_events_emitted() {
+        Err(StarknetStateError::ExpectedEventsNotEmitted(events)) => {
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].data, vec![9.into()]);
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    // Expectations are cleared after being checked.
+    assert!(starknet_state.assert_expected_events_emitted().is_ok());
+}
@@ -0,0 +1,83 @@
+#![deny(warnings)]
+
+mod devnet_utils;
+
+use assert_matches::assert_matches;
+use cairo_vm::felt::Felt252;
+use devnet_utils::{hex, invoke_transaction, setup, FIBONACCI_PATH};
+use serde_json::{json, Value};
+use starknet_rs::{
+    business_logic::transaction::transactions::Transaction,
+    core::contract_address::starknet_contract_address::compute_class_hash,
+    devnet::{devnet_state::DevnetState, error::DevnetError, rpc::handler::handle_rpc_body},
+    services::api::contract_class::ContractClass,
+    testing::accounts::{Account, KeyPair, ACCOUNT_CONTRACT_PATH},
+};
+use std::path::{Path, PathBuf};
+
+fn rpc(devnet: &mut DevnetState, method: &str, params: Value) -> Value {
+    let body = json!({"jsonrpc": "2.0", "id": 0, "method": method, "params": params});
+    handle_rpc_body(devnet, body.to_string().as_bytes()).unwrap()["result"].clone()
+}
+
+/// Returns a devnet where the account invoked the fibonacci contract once, with the
+/// definitions of its classes, along with the account and the hash of the invoke transaction.
+fn setup_with_invoke() -> (DevnetState, Account, Felt252) {
+    let (mut devnet, account, fib_address) = setup();
+    for (contract_class, path) in [
+        (Account::contract_class().unwrap(), ACCOUNT_CONTRACT_PATH),
+        (
+            ContractClass::try_from(PathBuf::from(FIBONACCI_PATH)).unwrap(),
+            FIBONACCI_PATH,
+        ),
+    ] {
+        let class_hash = compute_class_hash(&contract_class).unwrap().to_be_bytes();
+        devnet
+            .add_class_definition_from_file(class_hash, Path::new(path))
+            .unwrap();
+    }
+
+    let tx = invoke_transaction(&devnet, &account, &fib_address);
+    let tx_hash = devnet
+        .add_transaction(Transaction::InvokeFunction(tx))
+        .unwrap()
+        .transaction_hash
+        .clone();
+
+    (devnet, account, tx_hash)
+}
+
+#[test]
+fn a_loaded_dump_has_the_dumped_state() {
+    let (devnet, account, tx_hash) = setup_with_invoke();
+    let dump = devnet.dump().unwrap();
+
+    let mut loaded = DevnetState::default();
+    loaded.load(dump.clone()).unwrap();
+
+    assert_eq!(loaded.dump().unwrap(), dump);
+    assert_eq!(
+        rpc(
+            &mut loaded,
+            "starknet_getNonce",
+            json!(["latest", hex(&account.address().0)])
+        ),
+        json!("0x1")
+    );
+    assert_eq!(
+        rpc(
+            &mut loaded,
+            "starknet_getTransactionReceipt",
+            json!([hex(&tx_hash)])
+        )["execution_status"],
+        json!("SUCCEEDED")
+    );
+}
+
+#[test]
+fn classes_without_definition_cannot_be_dumped() {
+    let mut devnet = DevnetState::default();
+    Account::predeploy(&mut devnet.starknet_state, KeyPair::from_seed(1).unwrap()).unwrap();
+
+    assert_matches!(devnet.dump(), Err(DevnetError::MissingClassDefinition(_)));
+}
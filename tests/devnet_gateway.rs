@@ -0,0 +1,113 @@
+#![deny(warnings)]
+
+mod devnet_utils;
+
+use cairo_vm::felt::Felt252;
+use devnet_utils::{hex, invoke_transaction, setup};
+use serde_json::{json, Value};
+use starknet_rs::{
+    business_logic::transaction::objects::internal_invoke_function::InternalInvokeFunction,
+    devnet::gateway::{error::GatewayError, handler, types::FeederQuery},
+    utils::calculate_sn_keccak,
+};
+
+/// Returns an invoke transaction in the gateway format, whose calldata and signature are
+/// decimal.
+fn gateway_transaction(tx: &InternalInvokeFunction) -> Value {
+    json!({
+        "type": "INVOKE_FUNCTION",
+        "sender_address": hex(&tx.contract_address().0),
+        "calldata": tx.calldata().iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "max_fee": "0x0",
+        "version": "0x1",
+        "signature": tx.signature().iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "nonce": "0x0",
+    })
+}
+
+#[test]
+fn add_transaction_and_query_the_feeder_gateway() {
+    let (mut devnet, account, fib_address) = setup();
+    let tx = invoke_transaction(&devnet, &account, &fib_address);
+    let tx_hash = tx.hash_value().clone();
+    let body = gateway_transaction(&tx).to_string();
+
+    let estimate =
+        handler::estimate_fee(&devnet, &FeederQuery::default(), body.as_bytes()).unwrap();
+    assert_eq!(estimate["unit"], json!("wei"));
+
+    let response = handler::add_transaction(&mut devnet, body.as_bytes()).unwrap();
+    assert_eq!(
+        response,
+        json!({"code": "TRANSACTION_RECEIVED", "transaction_hash": hex(&tx_hash)})
+    );
+
+    let query = FeederQuery {
+        transaction_hash: Some(hex(&tx_hash)),
+        ..Default::default()
+    };
+    let receipt = handler::get_transaction_receipt(&devnet, &query).unwrap();
+    assert_eq!(receipt["status"], json!("ACCEPTED_ON_L2"));
+    assert_eq!(receipt["execution_status"], json!("SUCCEEDED"));
+    assert_eq!(receipt["transaction_index"], json!(0));
+
+    let block = handler::get_block(&devnet, &FeederQuery::default()).unwrap();
+    assert_eq!(
+        block["transactions"],
+        json!([{"transaction_hash": hex(&tx_hash), "type": "INVOKE_FUNCTION"}])
+    );
+    assert_eq!(receipt["block_hash"], block["block_hash"]);
+    let query = FeederQuery {
+        block_hash: block["block_hash"].as_str().map(ToString::to_string),
+        ..Default::default()
+    };
+    assert_eq!(handler::get_block(&devnet, &query).unwrap(), block);
+
+    let fib_selector = Felt252::from_bytes_be(&calculate_sn_keccak(b"fib"));
+    let call = json!({
+        "contract_address": hex(&fib_address.0),
+        "entry_point_selector": hex(&fib_selector),
+        "calldata": ["1", "1", "10"],
+    });
+    assert_eq!(
+        handler::call_contract(
+            &devnet,
+            &FeederQuery::default(),
+            call.to_string().as_bytes()
+        ),
+        Ok(json!({"result": ["0x90"]}))
+    );
+
+    let query = FeederQuery {
+        contract_address: Some(hex(&fib_address.0)),
+        key: Some("0".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        handler::get_storage_at(&mut devnet, &query),
+        Ok(json!("0x0"))
+    );
+    assert!(handler::get_class_hash_at(&mut devnet, &query).is_ok());
+}
+
+#[test]
+fn rejected_transactions_have_gateway_error_codes() {
+    let (mut devnet, account, fib_address) = setup();
+    let mut tx = gateway_transaction(&invoke_transaction(&devnet, &account, &fib_address));
+    tx["nonce"] = json!("0x1");
+
+    let error = handler::add_transaction(&mut devnet, tx.to_string().as_bytes()).unwrap_err();
+    assert_eq!(error.code(), "StarknetErrorCode.INVALID_TRANSACTION_NONCE");
+
+    tx["version"] = json!("0x0");
+    assert_eq!(
+        handler::add_transaction(&mut devnet, tx.to_string().as_bytes()),
+        Err(GatewayError::InvalidTransactionVersion(0))
+    );
+    assert_eq!(
+        handler::add_transaction(&mut devnet, b"{}")
+            .unwrap_err()
+            .code(),
+        "StarkErrorCode.MALFORMED_REQUEST"
+    );
+}
@@ -0,0 +1,133 @@
+#![deny(warnings)]
+
+mod devnet_utils;
+
+use cairo_vm::felt::Felt252;
+use devnet_utils::{hex, invoke_transaction, setup};
+use serde_json::{json, Value};
+use starknet_rs::{
+    business_logic::transaction::objects::internal_invoke_function::InternalInvokeFunction,
+    core::transaction_hash::starknet_transaction_hash::query_version,
+    devnet::{devnet_state::DevnetState, rpc::handler::handle_rpc_body},
+    testing::accounts::KeyPair,
+    utils::calculate_sn_keccak,
+};
+
+fn rpc(devnet: &mut DevnetState, method: &str, params: Value) -> Value {
+    let body = json!({"jsonrpc": "2.0", "id": 0, "method": method, "params": params});
+    handle_rpc_body(devnet, body.to_string().as_bytes()).unwrap()
+}
+
+/// Returns an invoke transaction in the RPC format.
+fn rpc_transaction(tx: &InternalInvokeFunction, version: &Felt252) -> Value {
+    json!({
+        "type": "INVOKE",
+        "sender_address": hex(&tx.contract_address().0),
+        "calldata": tx.calldata().iter().map(hex).collect::<Vec<_>>(),
+        "max_fee": "0x0",
+        "version": hex(version),
+        "signature": tx.signature().iter().map(hex).collect::<Vec<_>>(),
+        "nonce": "0x0",
+    })
+}
+
+#[test]
+fn invoke_through_rpc_and_query_the_results() {
+    let (mut devnet, account, fib_address) = setup();
+    let tx = invoke_transaction(&devnet, &account, &fib_address);
+    let tx_hash = tx.hash_value().clone();
+
+    let response = rpc(
+        &mut devnet,
+        "starknet_addInvokeTransaction",
+        json!({ "invoke_transaction": rpc_transaction(&tx, &1.into()) }),
+    );
+    assert_eq!(response["result"]["transaction_hash"], json!(hex(&tx_hash)));
+
+    let receipt = rpc(
+        &mut devnet,
+        "starknet_getTransactionReceipt",
+        json!([hex(&tx_hash)]),
+    );
+    assert_eq!(receipt["result"]["type"], json!("INVOKE"));
+    assert_eq!(receipt["result"]["execution_status"], json!("SUCCEEDED"));
+
+    assert_eq!(
+        rpc(
+            &mut devnet,
+            "starknet_getNonce",
+            json!(["latest", hex(&account.address().0)])
+        )["result"],
+        json!("0x1")
+    );
+    let fib_selector = Felt252::from_bytes_be(&calculate_sn_keccak(b"fib"));
+    assert_eq!(
+        rpc(
+            &mut devnet,
+            "starknet_call",
+            json!({
+                "request": {
+                    "contract_address": hex(&fib_address.0),
+                    "entry_point_selector": hex(&fib_selector),
+                    "calldata": ["0x1", "0x1", "0xa"],
+                },
+                "block_id": "latest",
+            })
+        )["result"],
+        json!(["0x90"])
+    );
+}
+
+#[test]
+fn rejected_transactions_leave_no_receipt() {
+    let (mut devnet, account, fib_address) = setup();
+    let tx = invoke_transaction(&devnet, &account, &fib_address);
+    let tx_hash = tx.hash_value().clone();
+    let signature = KeyPair::from_seed(2).unwrap().sign(&tx_hash).unwrap();
+    let tx = rpc_transaction(&tx.with_signature(signature), &1.into());
+
+    let response = rpc(&mut devnet, "starknet_addInvokeTransaction", json!([tx]));
+
+    assert_eq!(response["error"]["code"], json!(55));
+    assert_eq!(
+        rpc(
+            &mut devnet,
+            "starknet_getTransactionReceipt",
+            json!([hex(&tx_hash)])
+        )["error"]["code"],
+        json!(29)
+    );
+    assert_eq!(
+        rpc(
+            &mut devnet,
+            "starknet_getNonce",
+            json!(["latest", hex(&account.address().0)])
+        )["result"],
+        json!("0x0")
+    );
+}
+
+#[test]
+fn estimate_fee_of_query_version_transactions() {
+    let (mut devnet, account, fib_address) = setup();
+    let chain_id = devnet
+        .starknet_state
+        .general_config
+        .starknet_os_config()
+        .chain_id()
+        .to_felt();
+    let tx = invoke_transaction(&devnet, &account, &fib_address)
+        .with_query_version(chain_id)
+        .unwrap();
+    let signature = KeyPair::from_seed(1)
+        .unwrap()
+        .sign(tx.hash_value())
+        .unwrap();
+    let tx = rpc_transaction(&tx.with_signature(signature), &query_version(1));
+
+    let estimate = rpc(&mut devnet, "starknet_estimateFee", json!([[tx], "latest"]));
+    assert!(estimate["result"][0]["overall_fee"].is_string());
+
+    let response = rpc(&mut devnet, "starknet_addInvokeTransaction", json!([tx]));
+    assert!(response["error"].is_object());
+}
@@ -0,0 +1,63 @@
+//! Helpers shared by the devnet tests.
+#![allow(dead_code)]
+
+use cairo_vm::felt::Felt252;
+use starknet_rs::{
+    business_logic::transaction::{
+        objects::{
+            call::{encode_execute_calldata, Call, CallFormat},
+            internal_invoke_function::InternalInvokeFunction,
+        },
+        transactions::Transaction,
+    },
+    devnet::devnet_state::DevnetState,
+    services::api::contract_class::ContractClass,
+    testing::accounts::{Account, KeyPair},
+    utils::Address,
+};
+use std::path::PathBuf;
+
+pub const FIBONACCI_PATH: &str = "starknet_programs/fibonacci.json";
+
+pub fn hex(felt: &Felt252) -> String {
+    format!("0x{}", felt.to_str_radix(16))
+}
+
+/// Returns a devnet with an account, of the key pair of seed 1, and a deployed fibonacci
+/// contract, along with the account and the address of the contract.
+pub fn setup() -> (DevnetState, Account, Address) {
+    let mut devnet = DevnetState::default();
+    let account =
+        Account::predeploy(&mut devnet.starknet_state, KeyPair::from_seed(1).unwrap()).unwrap();
+    let contract_class = ContractClass::try_from(PathBuf::from(FIBONACCI_PATH)).unwrap();
+    let (fib_address, _exec_info) = devnet
+        .starknet_state
+        .deploy(contract_class, vec![], Address(1.into()))
+        .unwrap();
+    devnet.create_block();
+
+    (devnet, account, fib_address)
+}
+
+/// Returns the first invoke transaction of the account, calling `fib`, signed by the account.
+pub fn invoke_transaction(
+    devnet: &DevnetState,
+    account: &Account,
+    fib_address: &Address,
+) -> InternalInvokeFunction {
+    let calldata = encode_execute_calldata(
+        &[Call::from_entry_point_name(
+            fib_address.clone(),
+            "fib",
+            vec![1.into(), 1.into(), 10.into()],
+        )],
+        CallFormat::Legacy,
+    );
+    match account
+        .invoke_transaction(&devnet.starknet_state, calldata, 0, 0.into())
+        .unwrap()
+    {
+        Transaction::InvokeFunction(tx) => tx,
+        _ => panic!("not an invoke transaction"),
+    }
+}
@@ -21,9 +21,11 @@ use starknet_rs::{
             state_cache::StorageEntry,
         },
         transaction::{
-            error::TransactionError,
+            error::{RevertError, TransactionError},
+            fee::FeePayerPolicy,
             objects::{
                 internal_deploy_account::InternalDeployAccount,
+                resource_bounds::{ResourceBounds, ResourceBoundsMapping, V3TransactionFields},
                 {
                     internal_declare::InternalDeclare,
                     internal_invoke_function::InternalInvokeFunction,
@@ -42,7 +44,7 @@ use starknet_rs::{
     },
     public::abi::VALIDATE_ENTRY_POINT_SELECTOR,
     services::api::contract_class::{ContractClass, EntryPointType},
-    utils::{calculate_sn_keccak, Address, ClassHash},
+    utils::{calculate_sn_keccak, get_erc20_balance_var_addresses, Address, ClassHash},
 };
 use starknet_rs::{
     core::errors::state_errors::StateError,
@@ -90,6 +92,9 @@ lazy_static! {
     // Others.
     // Blockifier had this value hardcoded to 2.
     static ref ACTUAL_FEE: Felt252 = Felt252::zero();
+    // Fee token balance of the accounts paying for the test transactions, enough to cover their
+    // max fee.
+    static ref INITIAL_BALANCE: Felt252 = Felt252::from(100);
 }
 
 fn get_contract_class<P>(path: P) -> Result<ContractClass, Box<dyn std::error::Error>>
@@ -104,7 +109,6 @@ pub fn new_starknet_general_config_for_testing() -> StarknetGeneralConfig {
         StarknetOsConfig::new(
             StarknetChainId::TestNet,
             TEST_ERC20_CONTRACT_ADDRESS.clone(),
-            0,
         ),
         0,
         0,
@@ -156,7 +160,7 @@ fn create_account_tx_test_state(
 
     let storage_view = HashMap::from([(
         (test_erc20_address, test_erc20_account_balance_key),
-        ACTUAL_FEE.clone(),
+        INITIAL_BALANCE.clone(),
     )]);
 
     let cached_state = CachedState::new(
@@ -262,7 +266,7 @@ fn state_cache_after_invoke_tx() -> StateCache {
                 TEST_ERC20_CONTRACT_ADDRESS.clone(),
                 TEST_ERC20_ACCOUNT_BALANCE_KEY.clone().to_be_bytes(),
             ),
-            Felt252::zero(),
+            INITIAL_BALANCE.clone(),
         ),
         (
             (
@@ -297,7 +301,7 @@ fn state_cache_after_invoke_tx() -> StateCache {
                 TEST_ERC20_CONTRACT_ADDRESS.clone(),
                 TEST_ERC20_ACCOUNT_BALANCE_KEY.clone().to_be_bytes(),
             ),
-            Felt252::from(0),
+            INITIAL_BALANCE.clone(),
         ),
         (
             (
@@ -348,7 +352,7 @@ fn initial_in_memory_state_reader() -> InMemoryStateReader {
                 TEST_ERC20_CONTRACT_ADDRESS.clone(),
                 TEST_ERC20_ACCOUNT_BALANCE_KEY.clone().to_be_bytes(),
             ),
-            Felt252::from(0),
+            INITIAL_BALANCE.clone(),
         )]),
         HashMap::from([
             (
@@ -389,7 +393,7 @@ fn expected_validate_call_info(
 fn expected_fee_transfer_call_info(
     general_config: &StarknetGeneralConfig,
     account_address: &Address,
-    actual_fee: u64,
+    actual_fee: u128,
 ) -> CallInfo {
     CallInfo {
         entry_point_type: EntryPointType::External.into(),
@@ -438,7 +442,7 @@ fn expected_fee_transfer_call_info(
             ],
         ]),
         storage_read_values: vec![
-            Felt252::zero(),
+            INITIAL_BALANCE.clone(),
             Felt252::zero(),
             Felt252::zero(),
             Felt252::zero(),
@@ -465,7 +469,10 @@ fn validate_final_balances<S>(
             *erc20_account_balance_storage_key,
         ))
         .unwrap();
-    assert_eq!(account_balance, Felt252::zero());
+    assert_eq!(
+        account_balance,
+        INITIAL_BALANCE.clone() - expected_sequencer_balance
+    );
 
     let sequencer_balance = state
         .get_storage_at(&(
@@ -494,7 +501,7 @@ fn test_create_account_tx_test_state() {
             TEST_ERC20_ACCOUNT_BALANCE_KEY.to_be_bytes(),
         ))
         .unwrap();
-    assert_eq!(value, *ACTUAL_FEE);
+    assert_eq!(value, *INITIAL_BALANCE);
 
     let class_hash = state.get_class_hash_at(&TEST_CONTRACT_ADDRESS).unwrap();
     assert_eq!(class_hash, TEST_CLASS_HASH.to_be_bytes());
@@ -546,7 +553,7 @@ fn expected_fee_transfer_info() -> CallInfo {
             ],
         }],
         storage_read_values: vec![
-            Felt252::zero(),
+            INITIAL_BALANCE.clone(),
             Felt252::zero(),
             Felt252::zero(),
             Felt252::zero(),
@@ -584,6 +591,8 @@ fn declare_tx() -> InternalDeclare {
         signature: vec![],
         nonce: 0.into(),
         hash_value: 0.into(),
+        compiled_class_hash: None,
+        v3_fields: None,
     }
 }
 
@@ -614,7 +623,7 @@ fn expected_declare_fee_transfer_info() -> CallInfo {
             ],
         )],
         storage_read_values: vec![
-            Felt252::zero(),
+            INITIAL_BALANCE.clone(),
             Felt252::zero(),
             Felt252::zero(),
             Felt252::zero(),
@@ -756,7 +765,7 @@ fn expected_transaction_execution_info() -> TransactionExecutionInfo {
         0,
         HashMap::from([
             ("pedersen_builtin".to_string(), 16),
-            ("range_check_builtin".to_string(), 72),
+            ("range_check_builtin".to_string(), 91),
             ("l1_gas_usage".to_string(), 0),
         ]),
         Some(TransactionType::InvokeFunction),
@@ -829,7 +838,7 @@ fn test_deploy_account() {
                 .clone(),
             TEST_ERC20_DEPLOYED_ACCOUNT_BALANCE_KEY.to_be_bytes(),
         ),
-        ACTUAL_FEE.clone(),
+        INITIAL_BALANCE.clone(),
     );
 
     let (state_before, state_after) = expected_deploy_account_states();
@@ -875,14 +884,14 @@ fn test_deploy_account() {
     let expected_fee_transfer_call_info = expected_fee_transfer_call_info(
         &general_config,
         deploy_account_tx.contract_address(),
-        ACTUAL_FEE.to_u64().unwrap(),
+        ACTUAL_FEE.to_u128().unwrap(),
     );
 
     let expected_execution_info = TransactionExecutionInfo::new(
         expected_validate_call_info.into(),
         expected_execute_call_info.into(),
         expected_fee_transfer_call_info.into(),
-        ACTUAL_FEE.to_u64().unwrap(),
+        ACTUAL_FEE.to_u128().unwrap(),
         // Entry **not** in blockifier.
         // Default::default(),
         [
@@ -949,7 +958,7 @@ fn expected_deploy_account_states() -> (
                     (
                         (Address(0x1001.into()),
                         felt_str!("1192211877881866289306604115402199097887041303917861778777990838480655617515").to_be_bytes()),
-                        Felt252::zero(),
+                        INITIAL_BALANCE.clone(),
                     ),
                         ]),
             HashMap::from([
@@ -968,7 +977,7 @@ fn expected_deploy_account_states() -> (
             )
             .to_be_bytes(),
         ),
-        0.into(),
+        INITIAL_BALANCE.clone(),
     );
 
     let mut state_after = state_before.clone();
@@ -1048,7 +1057,7 @@ fn expected_deploy_account_states() -> (
             )
             .to_be_bytes(),
         ),
-        Felt252::zero(),
+        INITIAL_BALANCE.clone(),
     );
     state_after.cache_mut().storage_writes_mut().insert(
         (
@@ -1129,7 +1138,7 @@ fn test_state_for_declare_tx() {
                 TEST_ERC20_CONTRACT_ADDRESS.clone(),
                 TEST_ERC20_ACCOUNT_BALANCE_KEY.to_be_bytes()
             ),
-            Felt252::zero()
+            INITIAL_BALANCE.clone()
         ),]),
     );
 
@@ -1196,7 +1205,7 @@ fn test_state_for_declare_tx() {
                     TEST_ERC20_CONTRACT_ADDRESS.clone(),
                     TEST_ERC20_ACCOUNT_BALANCE_KEY.to_be_bytes()
                     ),
-                    0.into()
+                    INITIAL_BALANCE.clone()
                 )
             ]),
             HashMap::new(),
@@ -1231,7 +1240,7 @@ fn test_state_for_declare_tx() {
                     TEST_ERC20_CONTRACT_ADDRESS.clone(),
                     TEST_ERC20_ACCOUNT_BALANCE_KEY.to_be_bytes()
                     ),
-                    0.into()
+                    INITIAL_BALANCE.clone()
                 ),
             ]),
         )
@@ -1273,6 +1282,12 @@ fn test_invoke_tx_wrong_call_data() {
     // Execute transaction
     let result = invoke_tx.execute(state, starknet_general_config);
 
+    // The transaction is rejected, so the nonce is left untouched
+    assert_eq!(
+        state.get_nonce_at(&TEST_ACCOUNT_CONTRACT_ADDRESS),
+        Ok(Felt252::zero())
+    );
+
     // Assert error
     assert_matches!(
         result,
@@ -1309,10 +1324,157 @@ fn test_invoke_tx_wrong_entrypoint() {
     .unwrap();
 
     // Execute transaction
-    let result = invoke_tx.execute(state, starknet_general_config);
+    let result = invoke_tx.execute(state, starknet_general_config).unwrap();
 
-    // Assert error
-    assert_matches!(result, Err(TransactionError::EntryPointNotFound));
+    // The transaction is reverted: the nonce is incremented and the fee is charged anyway
+    assert_matches!(
+        result.revert_error.as_ref().map(RevertError::root_cause),
+        Some(TransactionError::EntryPointNotFound)
+    );
+    assert_eq!(result.call_info, None);
+    assert!(result.fee_transfer_info.is_some());
+    assert_eq!(
+        state.get_nonce_at(&TEST_ACCOUNT_CONTRACT_ADDRESS),
+        Ok(Felt252::one())
+    );
+}
+
+#[test]
+fn test_invoke_tx_v3_pays_fee_in_strk() {
+    let (starknet_general_config, state) = &mut create_account_tx_test_state().unwrap();
+    let strk_fee_token_address = Address(4098.into());
+    state
+        .deploy_contract(
+            strk_fee_token_address.clone(),
+            TEST_ERC20_CONTRACT_CLASS_HASH.to_be_bytes(),
+        )
+        .unwrap();
+    *starknet_general_config
+        .starknet_os_config_mut()
+        .strk_fee_token_address_mut() = strk_fee_token_address.clone();
+    state.set_storage_at(
+        &(
+            strk_fee_token_address.clone(),
+            TEST_ERC20_ACCOUNT_BALANCE_KEY.to_be_bytes(),
+        ),
+        INITIAL_BALANCE.clone(),
+    );
+
+    let Address(test_contract_address) = TEST_CONTRACT_ADDRESS.clone();
+    let v3_fields = V3TransactionFields {
+        resource_bounds: ResourceBoundsMapping {
+            l1_gas: ResourceBounds {
+                max_amount: 100,
+                max_price_per_unit: 1,
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let invoke_tx = InternalInvokeFunction::new_v3(
+        TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+        vec![
+            test_contract_address, // CONTRACT_ADDRESS
+            Felt252::from_bytes_be(&calculate_sn_keccak(b"return_result")), // CONTRACT FUNCTION SELECTOR
+            Felt252::from(1),                                               // CONTRACT_CALLDATA LEN
+            Felt252::from(2),                                               // CONTRACT_CALLDATA
+        ],
+        vec![],
+        StarknetChainId::TestNet.to_felt(),
+        Felt252::zero(),
+        v3_fields,
+    )
+    .unwrap();
+
+    let result = invoke_tx.execute(state, starknet_general_config).unwrap();
+
+    assert_eq!(result.revert_error, None);
+    assert_eq!(
+        result.fee_transfer_info.unwrap().contract_address,
+        strk_fee_token_address
+    );
+    assert_eq!(
+        state.get_nonce_at(&TEST_ACCOUNT_CONTRACT_ADDRESS),
+        Ok(Felt252::one())
+    );
+}
+
+#[test]
+fn test_invoke_tx_v3_above_max_l1_gas_price_should_fail() {
+    let (starknet_general_config, state) = &mut create_account_tx_test_state().unwrap();
+    starknet_general_config.block_info_mut().gas_price = 2;
+
+    let invoke_tx = InternalInvokeFunction::new_v3(
+        TEST_ACCOUNT_CONTRACT_ADDRESS.clone(),
+        vec![],
+        vec![],
+        StarknetChainId::TestNet.to_felt(),
+        Felt252::zero(),
+        V3TransactionFields {
+            resource_bounds: ResourceBoundsMapping {
+                l1_gas: ResourceBounds {
+                    max_amount: 100,
+                    max_price_per_unit: 1,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .unwrap();
+
+    assert_matches!(
+        invoke_tx.execute(state, starknet_general_config),
+        Err(TransactionError::MaxL1GasPriceTooLow(1, 2))
+    );
+}
+
+#[test]
+fn test_invoke_tx_fee_paid_by_sponsor() {
+    let (starknet_general_config, state) = &mut create_account_tx_test_state().unwrap();
+    let sponsor_address = Address(4099.into());
+    let (sponsor_balance_key, _) = get_erc20_balance_var_addresses(&sponsor_address).unwrap();
+    let fee_token_address = starknet_general_config
+        .starknet_os_config()
+        .fee_token_address()
+        .clone();
+    *starknet_general_config.fee_payer_policy_mut() =
+        FeePayerPolicy::Sponsor(sponsor_address.clone());
+
+    let Address(test_contract_address) = TEST_CONTRACT_ADDRESS.clone();
+    let calldata = vec![
+        test_contract_address, // CONTRACT_ADDRESS
+        Felt252::from_bytes_be(&calculate_sn_keccak(b"return_result")), // CONTRACT FUNCTION SELECTOR
+        Felt252::from(1),                                               // CONTRACT_CALLDATA LEN
+        Felt252::from(2),                                               // CONTRACT_CALLDATA
+    ];
+
+    // The sponsor can't pay the max fee yet.
+    assert_matches!(
+        invoke_tx(calldata.clone()).execute(state, starknet_general_config),
+        Err(TransactionError::InsufficientBalance(_, 2))
+    );
+
+    state.set_storage_at(
+        &(fee_token_address.clone(), sponsor_balance_key),
+        INITIAL_BALANCE.clone(),
+    );
+    let result = invoke_tx(calldata)
+        .execute(state, starknet_general_config)
+        .unwrap();
+
+    assert_eq!(result.fee_payer(), Some(&sponsor_address));
+    assert_eq!(
+        state.get_storage_at(&(fee_token_address.clone(), sponsor_balance_key)),
+        Ok(INITIAL_BALANCE.clone() - Felt252::from(result.actual_fee))
+    );
+    assert_eq!(
+        state.get_storage_at(&(
+            fee_token_address,
+            TEST_ERC20_ACCOUNT_BALANCE_KEY.to_be_bytes()
+        )),
+        Ok(INITIAL_BALANCE.clone())
+    );
 }
 
 #[test]
@@ -1333,6 +1495,13 @@ fn test_deploy_undeclared_account() {
     )
     .unwrap();
 
+    let (balance_key, _) =
+        get_erc20_balance_var_addresses(deploy_account_tx.contract_address()).unwrap();
+    state.set_storage_at(
+        &(TEST_ERC20_CONTRACT_ADDRESS.clone(), balance_key),
+        INITIAL_BALANCE.clone(),
+    );
+
     // Check not_deployed_class_hash
     assert!(state.get_contract_class(&not_deployed_class_hash).is_err());
 
@@ -0,0 +1,138 @@
+#![deny(warnings)]
+
+use starknet_rs::{
+    business_logic::{
+        state::state_api::StateReader,
+        transaction::{
+            objects::{
+                call::{encode_execute_calldata, Call, CallFormat},
+                internal_invoke_function::InternalInvokeFunction,
+            },
+            transactions::Transaction,
+        },
+    },
+    definitions::constants::EXECUTE_ENTRY_POINT_SELECTOR,
+    services::api::contract_class::ContractClass,
+    testing::{
+        accounts::{Account, KeyPair},
+        block_producer::BlockProducer,
+        mempool::pool::Mempool,
+        starknet_state::StarknetState,
+    },
+    utils::Address,
+};
+use std::path::PathBuf;
+
+fn setup() -> (StarknetState, Account, Address) {
+    let mut starknet_state = StarknetState::new(None);
+    let account = Account::predeploy(&mut starknet_state, KeyPair::from_seed(1).unwrap()).unwrap();
+    let contract_class =
+        ContractClass::try_from(PathBuf::from("starknet_programs/fibonacci.json")).unwrap();
+    let (contract_address, _exec_info) = starknet_state
+        .deploy(contract_class, vec![], Address(1.into()))
+        .unwrap();
+
+    (starknet_state, account, contract_address)
+}
+
+#[test]
+fn burst_of_transactions_is_executed_in_nonce_order() {
+    let (mut starknet_state, account, contract_address) = setup();
+    let mut mempool = Mempool::default();
+    let calldata = encode_execute_calldata(
+        &[Call::from_entry_point_name(
+            contract_address,
+            "fib",
+            vec![1.into(), 1.into(), 10.into()],
+        )],
+        CallFormat::Legacy,
+    );
+
+    let mut tx_hashes = Vec::new();
+    for nonce in [2, 0, 1] {
+        let tx = account
+            .invoke_transaction(&starknet_state, calldata.clone(), 0, nonce.into())
+            .unwrap();
+        tx_hashes.push(tx.hash_value());
+        mempool
+            .add_transaction(tx, &mut starknet_state.state)
+            .unwrap();
+    }
+
+    let block = BlockProducer::new(2)
+        .produce_block(&mut starknet_state, &mut mempool)
+        .unwrap();
+    assert_eq!(block.block_info.block_number, 0);
+    assert!(block.rejected.is_empty());
+    assert_eq!(
+        block
+            .transactions
+            .iter()
+            .map(|(tx_hash, _)| tx_hash.clone())
+            .collect::<Vec<_>>(),
+        vec![tx_hashes[1].clone(), tx_hashes[2].clone()]
+    );
+    assert_eq!(mempool.len(), 1);
+
+    let block = BlockProducer::default()
+        .produce_block(&mut starknet_state, &mut mempool)
+        .unwrap();
+    assert_eq!(block.block_info.block_number, 1);
+    assert_eq!(block.transactions[0].0, tx_hashes[0]);
+    assert!(mempool.is_empty());
+    assert_eq!(
+        starknet_state.state.get_nonce_at(account.address()),
+        Ok(3.into())
+    );
+}
+
+#[test]
+fn failed_transactions_hold_the_following_nonces() {
+    let (mut starknet_state, account, contract_address) = setup();
+    let mut mempool = Mempool::default();
+    let calldata = encode_execute_calldata(
+        &[Call::from_entry_point_name(
+            contract_address,
+            "fib",
+            vec![1.into(), 1.into(), 10.into()],
+        )],
+        CallFormat::Legacy,
+    );
+    let chain_id = starknet_state
+        .general_config
+        .starknet_os_config()
+        .chain_id()
+        .to_felt();
+
+    // Signed by another key, so the validation of the account fails.
+    let invalid_tx = InternalInvokeFunction::new(
+        account.address().clone(),
+        EXECUTE_ENTRY_POINT_SELECTOR.clone(),
+        0,
+        calldata.clone(),
+        KeyPair::from_seed(2).unwrap().sign(&1.into()).unwrap(),
+        chain_id,
+        Some(0.into()),
+    )
+    .unwrap();
+    mempool
+        .add_transaction(
+            Transaction::InvokeFunction(invalid_tx),
+            &mut starknet_state.state,
+        )
+        .unwrap();
+    let next_tx = account
+        .invoke_transaction(&starknet_state, calldata, 0, 1.into())
+        .unwrap();
+    mempool
+        .add_transaction(next_tx, &mut starknet_state.state)
+        .unwrap();
+
+    let block = BlockProducer::default()
+        .produce_block(&mut starknet_state, &mut mempool)
+        .unwrap();
+
+    assert_eq!(block.rejected.len(), 1);
+    assert!(block.transactions.is_empty());
+    assert_eq!(mempool.queued_nonces(account.address()), vec![1.into()]);
+}
@@ -14,12 +14,18 @@ use starknet_rs::{
             cached_state::{CachedState, ContractClassCache},
             state_api::State,
         },
+        transaction::error::TransactionError,
     },
+    core::errors::syscall_handler_errors::SyscallHandlerError,
     definitions::{constants::TRANSACTION_VERSION, general_config::StarknetGeneralConfig},
     services::api::contract_class::{ContractClass, EntryPointType},
+    testing::cheatcodes::Cheatcodes,
     utils::{calculate_sn_keccak, Address, ClassHash},
 };
-use std::path::Path;
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
 
 use assert_matches::assert_matches;
 
@@ -206,3 +212,62 @@ fn deploy_not_declared_class_hash() {
         "No class hash declared in class_hash_to_contract_clas",
     );
 }
+
+#[test]
+fn failed_nested_call_keeps_error_chain_and_leaves_no_writes() {
+    let contract_address = Address(1111.into());
+    let class_hash = [1; 32];
+    // Nothing is deployed here: the first call to it is mocked, the second one fails after the
+    // caller already wrote to its storage.
+    let callee_address = Address(2222.into());
+
+    let mut state_reader = InMemoryStateReader::default();
+    state_reader
+        .address_to_class_hash_mut()
+        .insert(contract_address.clone(), class_hash);
+    state_reader
+        .address_to_nonce_mut()
+        .insert(contract_address.clone(), 0.into());
+    state_reader.class_hash_to_contract_class_mut().insert(
+        class_hash,
+        ContractClass::try_from(Path::new("starknet_programs/syscalls.json").to_path_buf())
+            .unwrap(),
+    );
+    let mut state = CachedState::new(state_reader, Some(ContractClassCache::new()));
+
+    let mut cheatcodes = Cheatcodes::new();
+    cheatcodes.mock_call(
+        callee_address.clone(),
+        Felt252::from_bytes_be(&calculate_sn_keccak(b"stateless_func")),
+        vec![42.into()],
+    );
+    let mut general_config = StarknetGeneralConfig::default();
+    *general_config.syscall_hooks_mut() = Some(Arc::new(Mutex::new(cheatcodes)));
+
+    let entry_point = ExecutionEntryPoint::new(
+        contract_address,
+        vec![callee_address.0.clone()],
+        Felt252::from_bytes_be(&calculate_sn_keccak(b"test_call_contract")),
+        Address(0.into()),
+        EntryPointType::External,
+        Some(CallType::Call),
+        None,
+    );
+
+    let error = entry_point
+        .execute(
+            &mut state,
+            &general_config,
+            &mut ExecutionResourcesManager::default(),
+            &TransactionExecutionContext::default(),
+        )
+        .unwrap_err();
+
+    assert_matches!(
+        &error,
+        TransactionError::Syscall(SyscallHandlerError::NestedCall { contract_address, .. })
+            if *contract_address == callee_address
+    );
+    assert_matches!(error.root_cause(), TransactionError::NotDeployedContract(_));
+    assert!(state.cache().storage_writes().is_empty());
+}
@@ -0,0 +1,123 @@
+#![deny(warnings)]
+
+use assert_matches::assert_matches;
+use cairo_vm::felt::Felt252;
+use starknet_rs::{
+    business_logic::state::state_api::StateReader,
+    services::api::contract_class::ContractClass,
+    testing::{
+        accounts::{Account, KeyPair},
+        starknet_state::StarknetState,
+        starknet_state_error::StarknetStateError,
+        udc::{calculate_udc_contract_address, ContractDeployed},
+    },
+    utils::{get_storage_var_address, ClassHash},
+};
+use std::path::PathBuf;
+
+fn setup() -> (StarknetState, Account, ClassHash) {
+    let mut starknet_state = StarknetState::new(None);
+    starknet_state.predeploy_udc().unwrap();
+    let account = Account::predeploy(&mut starknet_state, KeyPair::from_seed(1).unwrap()).unwrap();
+    let contract_class =
+        ContractClass::try_from(PathBuf::from("starknet_programs/constructor.json")).unwrap();
+    let (class_hash, _tx_info) = account
+        .declare(&mut starknet_state, contract_class, 0)
+        .unwrap();
+
+    (starknet_state, account, class_hash)
+}
+
+#[test]
+fn account_deploys_through_the_udc() {
+    let (mut starknet_state, account, class_hash) = setup();
+    let owner = Felt252::from(42);
+
+    for unique in [true, false] {
+        let salt = Felt252::from(unique as u8);
+        let (address, tx_info) = account
+            .deploy_contract(
+                &mut starknet_state,
+                &class_hash,
+                salt.clone(),
+                unique,
+                vec![owner.clone()],
+                0,
+            )
+            .unwrap();
+
+        assert_eq!(
+            address,
+            calculate_udc_contract_address(
+                &class_hash,
+                &salt,
+                unique,
+                &[owner.clone()],
+                account.address(),
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            ContractDeployed::from_execution_info(&tx_info).unwrap(),
+            vec![ContractDeployed {
+                address: address.clone(),
+                deployer: account.address().clone(),
+                unique,
+                class_hash,
+                constructor_calldata: vec![owner.clone()],
+                salt,
+            }]
+        );
+        assert_eq!(
+            starknet_state.state.get_class_hash_at(&address),
+            Ok(class_hash)
+        );
+        let owner_var = get_storage_var_address("owner", &[]).unwrap().to_be_bytes();
+        assert_eq!(
+            starknet_state.state.get_storage_at(&(address, owner_var)),
+            Ok(owner.clone())
+        );
+    }
+}
+
+#[test]
+fn only_unique_deployments_of_other_accounts_dont_collide() {
+    let (mut starknet_state, account, class_hash) = setup();
+    let other_account =
+        Account::predeploy(&mut starknet_state, KeyPair::from_seed(2).unwrap()).unwrap();
+
+    for deployer in [&account, &other_account] {
+        deployer
+            .deploy_contract(
+                &mut starknet_state,
+                &class_hash,
+                1.into(),
+                true,
+                vec![1.into()],
+                0,
+            )
+            .unwrap();
+    }
+
+    account
+        .deploy_contract(
+            &mut starknet_state,
+            &class_hash,
+            1.into(),
+            false,
+            vec![1.into()],
+            0,
+        )
+        .unwrap();
+    assert_matches!(
+        other_account.deploy_contract(
+            &mut starknet_state,
+            &class_hash,
+            1.into(),
+            false,
+            vec![1.into()],
+            0
+        ),
+        Err(StarknetStateError::MissingContractDeployedEvent)
+    );
+}